@@ -1,6 +1,10 @@
 pub mod general {
     use crate::{Com, Serde, ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
     use macro_rules_attribute::derive;
+    use mlc_data::fixture::patched::PatchedFixtureId;
+    use mlc_data::project::group::GroupId;
+    use mlc_data::project::universe::UniverseId;
+    use mlc_data::SavePercentage;
 
     use remoc::rtc::{Deserialize, Serialize};
     use remoc::{rch::watch, rtc};
@@ -41,6 +45,81 @@ pub mod general {
         BlueprintsChanged,
         UniverseListChanged,
         SettingsChanged,
+        CueListsChanged,
+        GroupsChanged,
+        ParkedChanged,
+        EndpointsChanged,
+        FixtureOutputChanged { fixture: PatchedFixtureId },
+    }
+
+    /// Output levels scaling the intensity channels, shared by all clients.
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Masters {
+        pub grand_master: SavePercentage,
+        pub blackout: bool,
+        pub universes: Vec<(UniverseId, SavePercentage)>,
+        pub groups: Vec<(GroupId, SavePercentage)>,
+    }
+
+    impl Default for Masters {
+        fn default() -> Self {
+            Self {
+                grand_master: SavePercentage::create(1.0),
+                blackout: false,
+                universes: vec![],
+                groups: vec![],
+            }
+        }
+    }
+
+    impl Masters {
+        pub fn universe_level(&self, universe: UniverseId) -> f32 {
+            self.universes
+                .iter()
+                .find(|(u, _)| *u == universe)
+                .map(|(_, l)| l.take())
+                .unwrap_or(1.0)
+        }
+
+        pub fn group_level(&self, group: &GroupId) -> f32 {
+            self.groups
+                .iter()
+                .find(|(g, _)| g == group)
+                .map(|(_, l)| l.take())
+                .unwrap_or(1.0)
+        }
+    }
+
+    /// The global tempo, cue lists with a beat step advance on its beats
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct Tempo {
+        pub bpm: f32,
+        /// Power of two from 1/4 to 4 the tempo is multiplied with
+        pub multiplier: f32,
+        /// Takes the tempo and beats from the audio analysis
+        pub follow_audio: bool,
+        /// Beats counted at the multiplied tempo
+        pub beat: u64,
+    }
+
+    impl Default for Tempo {
+        fn default() -> Self {
+            Self {
+                bpm: 120.0,
+                multiplier: 1.0,
+                follow_audio: false,
+                beat: 0,
+            }
+        }
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum TempoError {
+        #[error("The tempo {0} is not a finite number of beats per minute")]
+        InvalidBpm(f32),
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
     }
 
     impl From<ProjectInfo> for Info {
@@ -56,12 +135,35 @@ pub mod general {
         async fn info(&self) -> Result<watch::Receiver<Info>, rtc::CallError>;
         async fn status(&self) -> Result<watch::Receiver<String>, rtc::CallError>;
         async fn save(&self) -> Result<bool, rtc::CallError>;
+
+        async fn masters(&self) -> Result<watch::Receiver<Masters>, rtc::CallError>;
+        async fn set_grand_master(&self, level: SavePercentage) -> Result<(), rtc::CallError>;
+        async fn set_blackout(&self, blackout: bool) -> Result<(), rtc::CallError>;
+        async fn set_universe_master(
+            &self,
+            universe: UniverseId,
+            level: SavePercentage,
+        ) -> Result<(), rtc::CallError>;
+        async fn set_group_master(
+            &self,
+            group: GroupId,
+            level: SavePercentage,
+        ) -> Result<(), rtc::CallError>;
+
+        async fn tempo(&self) -> Result<watch::Receiver<Tempo>, rtc::CallError>;
+        /// Taps the tempo and starts a beat
+        async fn tap_tempo(&self) -> Result<(), rtc::CallError>;
+        async fn set_bpm(&self, bpm: f32) -> Result<(), TempoError>;
+        async fn set_tempo_multiplier(&self, multiplier: f32) -> Result<(), rtc::CallError>;
+        /// Starts the next beat now
+        async fn reset_tempo_phase(&self) -> Result<(), rtc::CallError>;
+        async fn set_tempo_follow_audio(&self, follow: bool) -> Result<(), rtc::CallError>;
     }
 }
 
 pub mod project_selection {
     use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
-    use mlc_data::project::{ProjectMetadata, ProjectType};
+    use mlc_data::project::{ProjectBackup, ProjectMetadata, ProjectType};
     use remoc::rtc;
     use serde::{Deserialize, Serialize};
 
@@ -78,6 +180,14 @@ pub mod project_selection {
 
     pub type ProjectIdent = String;
 
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct ImportOptions {
+        /// Imports the project under this name instead of the exported one
+        pub name: Option<String>,
+        /// Accepts archives exported by another MLC version
+        pub ignore_version: bool,
+    }
+
     #[rtc::remote]
     pub trait ProjectSelectionService {
         async fn create(
@@ -87,7 +197,42 @@ pub mod project_selection {
         ) -> Result<ProjectIdent, ProjectSelectionServiceError>;
         async fn list(&self) -> Result<Vec<ProjectMetadata>, ProjectSelectionServiceError>;
         async fn open(&self, ident: ProjectIdent) -> Result<bool, ProjectSelectionServiceError>;
+        /// Moves the project to the trash folder, the open project can't be deleted
         async fn delete(&self, ident: ProjectIdent) -> Result<(), ProjectSelectionServiceError>;
+        /// Returns the new identifier, which changes with the file name of the name
+        async fn rename(
+            &self,
+            ident: ProjectIdent,
+            name: String,
+        ) -> Result<ProjectIdent, ProjectSelectionServiceError>;
+        /// Copies the saved state of the project
+        async fn duplicate(
+            &self,
+            ident: ProjectIdent,
+            name: String,
+        ) -> Result<ProjectIdent, ProjectSelectionServiceError>;
+        /// Packs the saved project with its assets into an archive
+        async fn export(
+            &self,
+            ident: ProjectIdent,
+        ) -> Result<Vec<u8>, ProjectSelectionServiceError>;
+        /// Unpacks an exported archive as a new project
+        async fn import(
+            &self,
+            archive: Vec<u8>,
+            options: ImportOptions,
+        ) -> Result<ProjectIdent, ProjectSelectionServiceError>;
+        /// Previous saves of the project, newest first
+        async fn backups(
+            &self,
+            ident: ProjectIdent,
+        ) -> Result<Vec<ProjectBackup>, ProjectSelectionServiceError>;
+        /// Replaces the saved project with the backup, the replaced save is kept as a backup
+        async fn restore_backup(
+            &self,
+            ident: ProjectIdent,
+            backup: String,
+        ) -> Result<(), ProjectSelectionServiceError>;
     }
 
     #[derive(Debug, thiserror::Error, Serialize, Deserialize)]
@@ -104,6 +249,47 @@ pub mod project_selection {
         #[error("Failed to delete the project: {0: }")]
         ProjectDeleteError(String),
 
+        #[error("Failed to rename the project: {0: }")]
+        ProjectRenameError(String),
+
+        #[error("Failed to duplicate the project: {0: }")]
+        ProjectDuplicateError(String),
+
+        #[error("The project {0:?} does not exist")]
+        UnknownProject(ProjectIdent),
+
+        #[error("The project {0:?} is currently open")]
+        ProjectInUse(ProjectIdent),
+
+        #[error("The name {0:?} contains no characters usable for a file name")]
+        InvalidProjectName(String),
+
+        #[error("Failed to export the project: {0: }")]
+        ProjectExportError(String),
+
+        #[error("Failed to import the project: {0: }")]
+        ProjectImportError(String),
+
+        #[error("A project with the file name {0:?} already exists")]
+        ProjectExists(ProjectIdent),
+
+        #[error("The archive was exported by MLC {archive}, this is MLC {current}")]
+        VersionMismatch { archive: String, current: String },
+
+        /// The newest readable backup is suggested as `backup`
+        #[error("The project {ident:?} is corrupt: {error}")]
+        ProjectCorrupt {
+            ident: ProjectIdent,
+            error: String,
+            backup: Option<String>,
+        },
+
+        #[error("Failed to access the backups: {0: }")]
+        ProjectBackupError(String),
+
+        #[error("The backup {0:?} does not exist")]
+        UnknownBackup(String),
+
         #[error("Network communication error: {0:?}")]
         RemocError(#[from] rtc::CallError),
     }
@@ -111,10 +297,23 @@ pub mod project_selection {
 
 pub mod project {
     use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use mlc_data::endpoints::{EndpointConfig, EndpointMapping};
     use mlc_data::fixture::blueprint::{FixtureBlueprint, Metadata};
-    use mlc_data::project::universe::{UniverseAddress, UniverseId};
+    use mlc_data::fixture::patched::output::OutputProcessing;
+    use mlc_data::fixture::patched::PatchedFixtureId;
+    use mlc_data::project::audio::{AudioFeature, AudioSettings};
+    use mlc_data::project::cue::CueListId;
+    use mlc_data::project::group::{FixtureGroup, GroupId};
+    use mlc_data::project::midi::{MidiControl, MidiSettings};
+    use mlc_data::project::osc::OscSettings;
+    use mlc_data::project::schedule::ScheduleSettings;
+    use mlc_data::project::script::Script;
+    use mlc_data::project::timecode::TimecodeSettings;
+    use mlc_data::project::universe::{
+        FixtureAddress, ParkedChannel, UniverseAddress, UniverseId,
+    };
     use mlc_data::project::{ProjectMetadata, ProjectSettings};
-    use remoc::rtc;
+    use remoc::{rch::watch, rtc};
     use serde::{Deserialize, Serialize};
 
     pub struct ProjectServiceIdent;
@@ -129,6 +328,12 @@ pub mod project {
         type S = ProjectServiceServerShared<T>;
     }
 
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct UniverseHead {
+        pub id: UniverseId,
+        pub name: String,
+    }
+
     #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
     pub struct FixtureBlueprintHead {
         pub meta: Metadata,
@@ -136,6 +341,15 @@ pub mod project {
         pub num_channels: u32,
     }
 
+    /// Descriptions of the recorded project edits, shared by all clients
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct EditHistory {
+        /// Oldest first, the last edit is undone next
+        pub undo: Vec<String>,
+        /// The last edit is redone next
+        pub redo: Vec<String>,
+    }
+
     #[rtc::remote]
     pub trait ProjectService {
         async fn list_available_fixture_blueprints(
@@ -147,7 +361,15 @@ pub mod project {
         ) -> Result<(), ProjectServiceError>;
         async fn list_blueprints(&self) -> Result<Vec<FixtureBlueprint>, ProjectServiceError>;
 
-        async fn universe_list(&self) -> Result<Vec<UniverseId>, ProjectServiceError>;
+        async fn universe_list(&self) -> Result<Vec<UniverseHead>, ProjectServiceError>;
+        async fn universe_create(&self, name: String) -> Result<UniverseId, ProjectServiceError>;
+        async fn universe_rename(
+            &self,
+            universe: UniverseId,
+            name: String,
+        ) -> Result<(), ProjectServiceError>;
+        /// Fails if fixtures are patched in or endpoints are mapped to the universe
+        async fn universe_delete(&self, universe: UniverseId) -> Result<(), ProjectServiceError>;
         async fn universe_sub(
             &self,
             universe: UniverseId,
@@ -158,6 +380,25 @@ pub mod project {
             ),
             ProjectServiceError,
         >;
+
+        async fn endpoint_mapping(&self) -> Result<EndpointMapping, ProjectServiceError>;
+        async fn endpoint_add(
+            &self,
+            universe: UniverseId,
+            config: EndpointConfig,
+        ) -> Result<(), ProjectServiceError>;
+        async fn endpoint_update(
+            &self,
+            universe: UniverseId,
+            index: usize,
+            config: EndpointConfig,
+        ) -> Result<(), ProjectServiceError>;
+        async fn endpoint_remove(
+            &self,
+            universe: UniverseId,
+            index: usize,
+        ) -> Result<(), ProjectServiceError>;
+
         async fn get_settings(&self) -> Result<ProjectSettings, ProjectServiceError>;
         async fn update_settings(
             &self,
@@ -165,6 +406,70 @@ pub mod project {
         ) -> Result<(), ProjectServiceError>;
 
         async fn get_meta(&self) -> Result<ProjectMetadata, ProjectServiceError>;
+
+        async fn group_list(&self) -> Result<Vec<FixtureGroup>, ProjectServiceError>;
+        async fn group_create(
+            &self,
+            name: String,
+            fixtures: Vec<PatchedFixtureId>,
+        ) -> Result<GroupId, ProjectServiceError>;
+        async fn group_update(&self, group: FixtureGroup) -> Result<(), ProjectServiceError>;
+        async fn group_delete(&self, group: GroupId) -> Result<(), ProjectServiceError>;
+
+        async fn fixture_output(
+            &self,
+            fixture: PatchedFixtureId,
+        ) -> Result<OutputProcessing, ProjectServiceError>;
+        async fn update_fixture_output(
+            &self,
+            fixture: PatchedFixtureId,
+            output: OutputProcessing,
+        ) -> Result<(), ProjectServiceError>;
+
+        async fn parked_list(&self) -> Result<Vec<ParkedChannel>, ProjectServiceError>;
+        /// Parks the channels at the given values, already parked channels are updated
+        async fn park(&self, channels: Vec<ParkedChannel>) -> Result<(), ProjectServiceError>;
+        async fn unpark(&self, addresses: Vec<FixtureAddress>) -> Result<(), ProjectServiceError>;
+
+        async fn osc_settings(&self) -> Result<OscSettings, ProjectServiceError>;
+        async fn update_osc_settings(
+            &self,
+            settings: OscSettings,
+        ) -> Result<(), ProjectServiceError>;
+
+        async fn midi_settings(&self) -> Result<MidiSettings, ProjectServiceError>;
+        async fn update_midi_settings(
+            &self,
+            settings: MidiSettings,
+        ) -> Result<(), ProjectServiceError>;
+
+        async fn timecode_settings(&self) -> Result<TimecodeSettings, ProjectServiceError>;
+        async fn update_timecode_settings(
+            &self,
+            settings: TimecodeSettings,
+        ) -> Result<(), ProjectServiceError>;
+
+        async fn audio_settings(&self) -> Result<AudioSettings, ProjectServiceError>;
+        async fn update_audio_settings(
+            &self,
+            settings: AudioSettings,
+        ) -> Result<(), ProjectServiceError>;
+
+        async fn schedule_settings(&self) -> Result<ScheduleSettings, ProjectServiceError>;
+        async fn update_schedule_settings(
+            &self,
+            settings: ScheduleSettings,
+        ) -> Result<(), ProjectServiceError>;
+
+        async fn scripts(&self) -> Result<Vec<Script>, ProjectServiceError>;
+        /// Fails if a script does not compile
+        async fn update_scripts(&self, scripts: Vec<Script>) -> Result<(), ProjectServiceError>;
+
+        async fn history(&self) -> Result<watch::Receiver<EditHistory>, ProjectServiceError>;
+        /// Reverts the last edit and returns its description
+        async fn undo(&self) -> Result<String, ProjectServiceError>;
+        /// Reapplies the last undone edit and returns its description
+        async fn redo(&self) -> Result<String, ProjectServiceError>;
     }
 
     #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
@@ -178,6 +483,626 @@ pub mod project {
         #[error("Listing available fixture blueprints failed: {0:?}")]
         BlueprintListFailed(String),
 
+        #[error("The group {0} does not exist")]
+        UnknownGroup(GroupId),
+
+        #[error("The fixture {0} is not patched")]
+        UnknownFixture(PatchedFixtureId),
+
+        #[error("The output limits of channel {0:?} have a minimum above the maximum")]
+        InvalidOutputLimits(String),
+
+        #[error("The universe {0} does not exist")]
+        UnknownUniverse(UniverseId),
+
+        #[error("The universe {0} still has fixtures patched")]
+        UniversePatched(UniverseId),
+
+        #[error("The universe {0} is still mapped to endpoints")]
+        UniverseMapped(UniverseId),
+
+        #[error("All universe ids have been used")]
+        NoUniverseIdLeft,
+
+        #[error("The universe {0} has no endpoint {1}")]
+        UnknownEndpoint(UniverseId, usize),
+
+        #[error("The sACN universe {0} is out of range (1-63999)")]
+        InvalidSacnUniverse(u16),
+
+        #[error("The sACN universe {0} is already in use")]
+        DuplicateSacnUniverse(u16),
+
+        #[error("The sACN priority {0} is out of range (0-200)")]
+        InvalidSacnPriority(u8),
+
+        #[error("The sACN source name {0:?} is longer than 63 bytes")]
+        InvalidSacnSourceName(String),
+
+        #[error("The endpoints synchronized by sACN universe {0} use different speeds")]
+        SacnSyncSpeedMismatch(u16),
+
+        #[error("The Art-Net Port-Address {0} is out of range (0-32767)")]
+        InvalidArtNetPortAddress(u16),
+
+        #[error("The output {0} is already in use")]
+        DuplicateArtNetOutput(String),
+
+        #[error("The endpoint speed must be at least 1 ms")]
+        InvalidEndpointSpeed,
+
+        #[error("The USB port {0} is already in use")]
+        DuplicateUsbPort(String),
+
+        #[error("The OSC port must not be 0")]
+        InvalidOscPort,
+
+        #[error("The OSC address {0:?} does not start with '/'")]
+        InvalidOscAddress(String),
+
+        #[error("The OSC address {0:?} is mapped more than once")]
+        DuplicateOscAddress(String),
+
+        #[error("The MIDI control {0} does not exist")]
+        InvalidMidiControl(MidiControl),
+
+        #[error("The MIDI control {0} is mapped more than once")]
+        DuplicateMidiControl(MidiControl),
+
+        #[error("The cue list {0} does not exist")]
+        UnknownCueList(CueListId),
+
+        #[error("The MSC device id {0} is out of range (0-127)")]
+        InvalidMscDeviceId(u8),
+
+        #[error("The audio input needs a path and a sample rate above 0")]
+        InvalidAudioInput,
+
+        #[error("The {0:?} mapping needs a trigger for beats or a fader for levels")]
+        InvalidAudioMapping(AudioFeature),
+
+        #[error("The coordinates {0}, {1} are out of range")]
+        InvalidCoordinates(f64, f64),
+
+        #[error("The schedule entry {0:?} has no valid action")]
+        InvalidScheduleAction(String),
+
+        #[error("The script name {0:?} is empty or used more than once")]
+        InvalidScriptName(String),
+
+        #[error("The script {name:?} does not compile: {msg}")]
+        InvalidScript { name: String, msg: String },
+
+        #[error("There is no edit to undo")]
+        NothingToUndo,
+
+        #[error("There is no edit to redo")]
+        NothingToRedo,
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod programmer {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use mlc_data::fixture::patched::attribute::FixtureAttribute;
+    use mlc_data::fixture::patched::PatchedFixtureId;
+    use mlc_data::project::cue::{CueId, CueListId};
+    use mlc_data::timecode::Timecode;
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+    use std::time::Duration;
+
+    pub struct ProgrammerServiceIdent;
+    impl ServiceIdentifiable for ProgrammerServiceIdent {
+        const IDENT: ServiceIdentifier = *b"prgmr";
+        type Client = ProgrammerServiceClient;
+    }
+
+    impl<T: ProgrammerService + Send + Sync + 'static> ServiceIdentifiableServer<T>
+        for ProgrammerServiceIdent
+    {
+        type S = ProgrammerServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct ProgrammerState {
+        pub selection: Vec<PatchedFixtureId>,
+        pub values: Vec<(PatchedFixtureId, Vec<FixtureAttribute>)>,
+        pub highlight: bool,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct CuePlaybackState {
+        /// The running cue lists with the index of their current cue, later ones take priority
+        pub active: Vec<(CueListId, usize)>,
+    }
+
+    impl CuePlaybackState {
+        pub fn current(&self, cue_list: CueListId) -> Option<usize> {
+            self.active
+                .iter()
+                .find(|(l, _)| *l == cue_list)
+                .map(|(_, i)| *i)
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct TimecodeState {
+        /// The last received timecode
+        pub timecode: Option<Timecode>,
+        /// The position of the cue list clock, `None` without timecode
+        pub position: Option<Duration>,
+        pub freewheeling: bool,
+        /// Cue lists held by MSC STOP, they don't follow the timecode until resumed
+        pub stopped: Vec<CueListId>,
+    }
+
+    #[rtc::remote]
+    pub trait ProgrammerService {
+        async fn state(&self) -> Result<watch::Receiver<ProgrammerState>, ProgrammerServiceError>;
+        async fn select(&self, fixtures: Vec<PatchedFixtureId>)
+        -> Result<(), ProgrammerServiceError>;
+        async fn set_attribute(
+            &self,
+            attribute: FixtureAttribute,
+        ) -> Result<(), ProgrammerServiceError>;
+        async fn highlight(&self, enabled: bool) -> Result<(), ProgrammerServiceError>;
+        async fn clear(&self) -> Result<(), ProgrammerServiceError>;
+        async fn record(
+            &self,
+            cue_list: Option<CueListId>,
+            name: String,
+        ) -> Result<CueId, ProgrammerServiceError>;
+
+        async fn cue_playback(
+            &self,
+        ) -> Result<watch::Receiver<CuePlaybackState>, ProgrammerServiceError>;
+        /// Starts the cue list or advances it to the next cue, `None` is the first cue list
+        async fn cue_go(&self, cue_list: Option<CueListId>) -> Result<(), ProgrammerServiceError>;
+        async fn cue_back(&self, cue_list: Option<CueListId>)
+        -> Result<(), ProgrammerServiceError>;
+        async fn cue_release(
+            &self,
+            cue_list: Option<CueListId>,
+        ) -> Result<(), ProgrammerServiceError>;
+
+        async fn timecode(&self) -> Result<watch::Receiver<TimecodeState>, ProgrammerServiceError>;
+        /// Sets the time on the cue list clock the cue is triggered at
+        async fn cue_set_timecode(
+            &self,
+            cue_list: CueListId,
+            cue: CueId,
+            timecode: Option<Duration>,
+        ) -> Result<(), ProgrammerServiceError>;
+        /// Lets the running cue list advance every number of beats of the tempo clock,
+        /// `None` only steps it manually
+        async fn cue_set_beat_step(
+            &self,
+            cue_list: CueListId,
+            beats: Option<u32>,
+        ) -> Result<(), ProgrammerServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum ProgrammerServiceError {
+        #[error("It is no valid project loaded!")]
+        InvalidProject,
+
+        #[error("The fixture {0} is not patched")]
+        UnknownFixture(PatchedFixtureId),
+
+        #[error("The cue list {0} does not exist")]
+        UnknownCueList(CueListId),
+
+        #[error("Nothing to record, the programmer is empty")]
+        EmptyProgrammer,
+
+        #[error("The project has no cue lists")]
+        NoCueLists,
+
+        #[error("The cue list {0} has no cues")]
+        EmptyCueList(CueListId),
+
+        #[error("The cue {0} does not exist")]
+        UnknownCue(CueId),
+
+        #[error("Cue lists can only step every 1 or more beats")]
+        InvalidBeatStep,
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod endpoints {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use mlc_data::endpoints::EndpointConfig;
+    use mlc_data::project::universe::{FixtureAddress, UniverseId};
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+    use std::fmt::Display;
+    use std::net::Ipv4Addr;
+    use std::time::SystemTime;
+
+    pub struct EndpointServiceIdent;
+    impl ServiceIdentifiable for EndpointServiceIdent {
+        const IDENT: ServiceIdentifier = *b"endpt";
+        type Client = EndpointServiceClient;
+    }
+
+    impl<T: EndpointService + Send + Sync + 'static> ServiceIdentifiableServer<T>
+        for EndpointServiceIdent
+    {
+        type S = EndpointServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct EndpointStats {
+        pub universe: UniverseId,
+        pub endpoint: EndpointConfig,
+        pub packets_sent: u64,
+        pub send_errors: u64,
+        /// Frames per second achieved during the last measuring interval
+        pub fps: f32,
+        /// Frames per second requested by the configured speed, `None` for endpoints sending on change
+        pub target_fps: Option<f32>,
+        pub last_error: Option<String>,
+    }
+
+    /// The last frame a simulator endpoint received
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct SimulatedFrame {
+        pub universe: UniverseId,
+        pub values: Vec<u8>,
+        pub updated: SystemTime,
+        /// Number of updates received
+        pub updates: u64,
+    }
+
+    /// An Art-Net node that answered an ArtPoll
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct ArtNetNode {
+        pub ip: Ipv4Addr,
+        pub short_name: String,
+        pub long_name: String,
+        pub ports: Vec<ArtNetPort>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+    pub struct ArtNetPort {
+        /// The 15 bit Port-Address, combined of net, sub-net and universe
+        pub universe: u16,
+        pub input: bool,
+        pub output: bool,
+    }
+
+    /// Unique id of an RDM device
+    #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct RdmUid {
+        pub manufacturer: u16,
+        pub device: u32,
+    }
+
+    impl Display for RdmUid {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:04X}:{:08X}", self.manufacturer, self.device)
+        }
+    }
+
+    #[rtc::remote]
+    pub trait EndpointService {
+        async fn stats(&self) -> Result<watch::Receiver<Vec<EndpointStats>>, EndpointServiceError>;
+        async fn simulator_frames(&self) -> Result<Vec<SimulatedFrame>, EndpointServiceError>;
+        async fn simulator_frame(
+            &self,
+            universe: UniverseId,
+        ) -> Result<Option<SimulatedFrame>, EndpointServiceError>;
+        /// The last value a simulator sent to a channel, `None` if its universe is not simulated
+        async fn simulator_value(
+            &self,
+            address: FixtureAddress,
+        ) -> Result<Option<u8>, EndpointServiceError>;
+        /// Broadcasts an ArtPoll and collects the nodes answering within a few seconds
+        async fn artnet_nodes(&self) -> Result<Vec<ArtNetNode>, EndpointServiceError>;
+        /// The RDM devices a node has discovered on one of its ports
+        async fn artnet_rdm_devices(
+            &self,
+            node: Ipv4Addr,
+            universe: u16,
+        ) -> Result<Vec<RdmUid>, EndpointServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum EndpointServiceError {
+        #[error("Art-Net discovery failed: {0}")]
+        DiscoveryFailed(String),
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod recorder {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+
+    pub struct RecorderServiceIdent;
+    impl ServiceIdentifiable for RecorderServiceIdent {
+        const IDENT: ServiceIdentifier = *b"recdr";
+        type Client = RecorderServiceClient;
+    }
+
+    impl<T: RecorderService + Send + Sync + 'static> ServiceIdentifiableServer<T>
+        for RecorderServiceIdent
+    {
+        type S = RecorderServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub enum RecordingSource {
+        /// The processed output of the universe runtime
+        Output,
+        /// Incoming sACN, recorded with the sACN universe as universe id
+        Sacn { universes: Vec<u16> },
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct RecorderState {
+        pub recording: Option<String>,
+        pub playback: Option<PlaybackState>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct PlaybackState {
+        pub name: String,
+        pub speed: f32,
+        pub looping: bool,
+        pub duration_ms: u64,
+    }
+
+    #[rtc::remote]
+    pub trait RecorderService {
+        async fn state(&self) -> Result<watch::Receiver<RecorderState>, RecorderServiceError>;
+        async fn recordings(&self) -> Result<Vec<String>, RecorderServiceError>;
+        /// An existing recording of the same name is only replaced with `overwrite`
+        async fn start_recording(
+            &self,
+            name: String,
+            source: RecordingSource,
+            overwrite: bool,
+        ) -> Result<(), RecorderServiceError>;
+        async fn stop_recording(&self) -> Result<(), RecorderServiceError>;
+        /// Plays a recording as a runtime layer below the programmer
+        async fn play(
+            &self,
+            name: String,
+            speed: f32,
+            looping: bool,
+        ) -> Result<(), RecorderServiceError>;
+        async fn set_playback_speed(&self, speed: f32) -> Result<(), RecorderServiceError>;
+        async fn stop_playback(&self) -> Result<(), RecorderServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum RecorderServiceError {
+        #[error("A recording is already running")]
+        AlreadyRecording,
+
+        #[error("Nothing is being recorded")]
+        NotRecording,
+
+        #[error("Nothing is being played back")]
+        NotPlaying,
+
+        #[error("The recording {0} does not exist")]
+        UnknownRecording(String),
+
+        #[error("The recording {0} already exists")]
+        RecordingExists(String),
+
+        #[error("The name {0:?} can not be used for a recording")]
+        InvalidName(String),
+
+        #[error("The playback speed {0} is not positive")]
+        InvalidSpeed(f32),
+
+        #[error("Accessing the recording failed: {0}")]
+        FileError(String),
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod midi {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use mlc_data::project::control::ControlAction;
+    use mlc_data::project::midi::MidiControl;
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+
+    pub struct MidiServiceIdent;
+    impl ServiceIdentifiable for MidiServiceIdent {
+        const IDENT: ServiceIdentifier = *b"midin";
+        type Client = MidiServiceClient;
+    }
+
+    impl<T: MidiService + Send + Sync + 'static> ServiceIdentifiableServer<T> for MidiServiceIdent {
+        type S = MidiServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct MidiState {
+        /// The ports currently read
+        pub ports: Vec<String>,
+        /// The action the next moved control gets mapped to
+        pub learning: Option<ControlAction>,
+        pub last_control: Option<MidiControl>,
+    }
+
+    #[rtc::remote]
+    pub trait MidiService {
+        async fn state(&self) -> Result<watch::Receiver<MidiState>, MidiServiceError>;
+        async fn available_ports(&self) -> Result<Vec<String>, MidiServiceError>;
+        /// Maps the next control moved on any port to the action
+        async fn start_learn(&self, action: ControlAction) -> Result<(), MidiServiceError>;
+        async fn cancel_learn(&self) -> Result<(), MidiServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum MidiServiceError {
+        #[error("It is no valid project loaded!")]
+        InvalidProject,
+
+        #[error("MIDI input is disabled")]
+        Disabled,
+
+        #[error("No control is being learned")]
+        NotLearning,
+
+        #[error("Listing the MIDI ports failed: {0}")]
+        PortError(String),
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod audio {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use mlc_data::audio::analysis::AudioLevels;
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+
+    pub struct AudioServiceIdent;
+    impl ServiceIdentifiable for AudioServiceIdent {
+        const IDENT: ServiceIdentifier = *b"audio";
+        type Client = AudioServiceClient;
+    }
+
+    impl<T: AudioService + Send + Sync + 'static> ServiceIdentifiableServer<T> for AudioServiceIdent {
+        type S = AudioServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct AudioState {
+        /// The input currently analyzed
+        pub input: Option<String>,
+        pub levels: AudioLevels,
+    }
+
+    #[rtc::remote]
+    pub trait AudioService {
+        async fn state(&self) -> Result<watch::Receiver<AudioState>, AudioServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum AudioServiceError {
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod schedule {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use mlc_data::chrono::{DateTime, Utc};
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+
+    pub struct ScheduleServiceIdent;
+    impl ServiceIdentifiable for ScheduleServiceIdent {
+        const IDENT: ServiceIdentifier = *b"sched";
+        type Client = ScheduleServiceClient;
+    }
+
+    impl<T: ScheduleService + Send + Sync + 'static> ServiceIdentifiableServer<T>
+        for ScheduleServiceIdent
+    {
+        type S = ScheduleServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+    pub struct ScheduledTrigger {
+        pub entry: String,
+        pub at: DateTime<Utc>,
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct ScheduleState {
+        /// The time of the scheduler clock if it is simulated
+        pub simulated: Option<DateTime<Utc>>,
+        pub next: Option<ScheduledTrigger>,
+        pub last: Option<ScheduledTrigger>,
+    }
+
+    #[rtc::remote]
+    pub trait ScheduleService {
+        async fn state(&self) -> Result<watch::Receiver<ScheduleState>, ScheduleServiceError>;
+        /// Runs the scheduler clock from the time on, the real time if `None`
+        async fn simulate_time(
+            &self,
+            time: Option<DateTime<Utc>>,
+        ) -> Result<(), ScheduleServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum ScheduleServiceError {
+        #[error("The scheduler is not running")]
+        NotRunning,
+
+        #[error("Network communication error: {0:?}")]
+        RemocError(#[from] rtc::CallError),
+    }
+}
+
+pub mod script {
+    use crate::{ServiceIdentifiable, ServiceIdentifiableServer, ServiceIdentifier};
+    use remoc::{rch::watch, rtc};
+    use serde::{Deserialize, Serialize};
+
+    pub struct ScriptServiceIdent;
+    impl ServiceIdentifiable for ScriptServiceIdent {
+        const IDENT: ServiceIdentifier = *b"scrpt";
+        type Client = ScriptServiceClient;
+    }
+
+    impl<T: ScriptService + Send + Sync + 'static> ServiceIdentifiableServer<T>
+        for ScriptServiceIdent
+    {
+        type S = ScriptServiceServerShared<T>;
+    }
+
+    #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+    pub struct ScriptState {
+        /// Names of the scripts currently running
+        pub running: Vec<String>,
+    }
+
+    #[rtc::remote]
+    pub trait ScriptService {
+        async fn state(&self) -> Result<watch::Receiver<ScriptState>, ScriptServiceError>;
+        /// Starts the project script, errors while it runs are sent as warnings
+        async fn run(&self, name: String) -> Result<(), ScriptServiceError>;
+        async fn stop(&self, name: String) -> Result<(), ScriptServiceError>;
+    }
+
+    #[derive(Debug, thiserror::Error, Serialize, Deserialize, Clone)]
+    pub enum ScriptServiceError {
+        #[error("It is no valid project loaded!")]
+        InvalidProject,
+
+        #[error("The script {0:?} does not exist")]
+        UnknownScript(String),
+
+        #[error("The script {0:?} is already running")]
+        AlreadyRunning(String),
+
+        #[error("The script {0:?} is not running")]
+        NotRunning(String),
+
         #[error("Network communication error: {0:?}")]
         RemocError(#[from] rtc::CallError),
     }
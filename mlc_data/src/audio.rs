@@ -0,0 +1,234 @@
+//! Mono audio input from WAV files and raw PCM streams.
+
+use std::io::{self, Read, Write};
+
+pub mod analysis;
+
+/// A source of mono samples in the range -1 to 1
+pub trait AudioSource {
+    fn sample_rate(&self) -> u32;
+    /// Fills the buffer with the next samples and returns their number, 0 at the end
+    fn read(&mut self, buf: &mut [f32]) -> io::Result<usize>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SampleFormat {
+    Int(u16),
+    Float,
+}
+
+/// Reads PCM WAV files with 8, 16, 24 or 32 bit integer or 32 bit float samples,
+/// multiple channels are mixed down.
+pub struct WavReader<R> {
+    reader: R,
+    sample_rate: u32,
+    channels: u16,
+    format: SampleFormat,
+    remaining: u64,
+}
+
+impl<R: Read> WavReader<R> {
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0; 12];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != b"RIFF" || &header[8..] != b"WAVE" {
+            return Err(invalid("Not a WAV file"));
+        }
+
+        let mut fmt = None;
+        loop {
+            let mut chunk = [0; 8];
+            reader.read_exact(&mut chunk)?;
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+
+            match &chunk[..4] {
+                b"fmt " => {
+                    let mut data = vec![0; size as usize];
+                    reader.read_exact(&mut data)?;
+                    if data.len() < 16 {
+                        return Err(invalid("Invalid format chunk"));
+                    }
+                    fmt = Some(data);
+                }
+                b"data" => {
+                    let fmt = fmt.ok_or_else(|| invalid("Missing format chunk"))?;
+                    let tag = u16::from_le_bytes([fmt[0], fmt[1]]);
+                    let channels = u16::from_le_bytes([fmt[2], fmt[3]]);
+                    let sample_rate = u32::from_le_bytes([fmt[4], fmt[5], fmt[6], fmt[7]]);
+                    let bits = u16::from_le_bytes([fmt[14], fmt[15]]);
+                    // Extensible files store the actual format in the sub format
+                    let tag = if tag == 0xFFFE && fmt.len() >= 26 {
+                        u16::from_le_bytes([fmt[24], fmt[25]])
+                    } else {
+                        tag
+                    };
+
+                    let format = match (tag, bits) {
+                        (1, 8 | 16 | 24 | 32) => SampleFormat::Int(bits),
+                        (3, 32) => SampleFormat::Float,
+                        _ => return Err(invalid("Unsupported sample format")),
+                    };
+                    if channels == 0 || sample_rate == 0 {
+                        return Err(invalid("Invalid format chunk"));
+                    }
+
+                    return Ok(Self {
+                        reader,
+                        sample_rate,
+                        channels,
+                        format,
+                        remaining: size,
+                    });
+                }
+                _ => {
+                    // Chunks are padded to an even size
+                    io::copy(&mut (&mut reader).take(size + size % 2), &mut io::sink())?;
+                }
+            }
+        }
+    }
+
+    fn bytes_per_sample(&self) -> usize {
+        match self.format {
+            SampleFormat::Int(bits) => bits as usize / 8,
+            SampleFormat::Float => 4,
+        }
+    }
+
+    fn decode(&self, b: &[u8]) -> f32 {
+        match self.format {
+            SampleFormat::Int(8) => (b[0] as f32 - 128.0) / 128.0,
+            SampleFormat::Int(16) => i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0,
+            SampleFormat::Int(24) => {
+                (i32::from_le_bytes([0, b[0], b[1], b[2]]) >> 8) as f32 / 8_388_608.0
+            }
+            SampleFormat::Int(_) => {
+                i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32 / 2_147_483_648.0
+            }
+            SampleFormat::Float => f32::from_le_bytes([b[0], b[1], b[2], b[3]]),
+        }
+    }
+}
+
+impl<R: Read> AudioSource for WavReader<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buf: &mut [f32]) -> io::Result<usize> {
+        let frame_size = self.bytes_per_sample() * self.channels as usize;
+        let frames = buf.len().min((self.remaining / frame_size as u64) as usize);
+        let mut data = vec![0; frames * frame_size];
+        let read = read_full(&mut self.reader, &mut data)? / frame_size;
+        self.remaining -= (read * frame_size) as u64;
+
+        for (sample, frame) in buf
+            .iter_mut()
+            .zip(data[..read * frame_size].chunks(frame_size))
+        {
+            let sum = frame
+                .chunks(self.bytes_per_sample())
+                .map(|b| self.decode(b))
+                .sum::<f32>();
+            *sample = sum / self.channels as f32;
+        }
+        Ok(read)
+    }
+}
+
+/// Reads a headerless stream of 16 bit little endian mono samples, e.g. from `arecord -t raw`
+pub struct RawPcmReader<R> {
+    reader: R,
+    sample_rate: u32,
+}
+
+impl<R: Read> RawPcmReader<R> {
+    pub fn new(reader: R, sample_rate: u32) -> Self {
+        Self {
+            reader,
+            sample_rate,
+        }
+    }
+}
+
+impl<R: Read> AudioSource for RawPcmReader<R> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn read(&mut self, buf: &mut [f32]) -> io::Result<usize> {
+        let mut data = vec![0; buf.len() * 2];
+        let read = read_full(&mut self.reader, &mut data)? / 2;
+        for (sample, b) in buf.iter_mut().zip(data[..read * 2].chunks(2)) {
+            *sample = i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0;
+        }
+        Ok(read)
+    }
+}
+
+/// Writes mono samples as a 16 bit WAV file
+pub fn write_wav(mut writer: impl Write, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    let data_size = samples.len() as u32 * 2;
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(36 + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVEfmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    writer.write_all(&(sample_rate * 2).to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Reads until the buffer is full or the end is reached, a trailing partial sample is dropped
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AudioSource, RawPcmReader, WavReader, write_wav};
+
+    #[test]
+    fn wav_roundtrip() {
+        let samples = [0.0, 0.5, -0.5, 1.0, -1.0];
+        let mut data = vec![];
+        write_wav(&mut data, 48_000, &samples).unwrap();
+
+        let mut reader = WavReader::new(data.as_slice()).unwrap();
+        assert_eq!(reader.sample_rate(), 48_000);
+        let mut buf = [0.0; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        for (a, b) in samples.iter().zip(buf) {
+            assert!((a - b).abs() < 0.001);
+        }
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+
+        let mut raw = RawPcmReader::new(&data[44..], 48_000);
+        assert_eq!(raw.read(&mut buf[..2]).unwrap(), 2);
+        assert!((buf[1] - 0.5).abs() < 0.001);
+
+        assert!(WavReader::new(&b"RIFF\0\0\0\0WAVX"[..]).is_err());
+    }
+}
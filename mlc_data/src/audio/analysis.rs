@@ -0,0 +1,239 @@
+//! Level, band and beat analysis of mono audio for sound to light.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+
+/// Analysis results per second
+pub const WINDOWS_PER_SECOND: u32 = 50;
+/// Crossover frequencies between the low, mid and high band in Hz
+const CROSSOVERS: [f32; 2] = [200.0, 2000.0];
+/// Energy of the low band compared to its recent average that counts as a beat
+const BEAT_SENSITIVITY: f32 = 1.5;
+/// Quieter windows never count as a beat
+const BEAT_MIN_RMS: f32 = 0.02;
+/// Shortest time between two beats in windows, limits detection to 200 BPM
+const BEAT_HOLDOFF: u64 = (WINDOWS_PER_SECOND * 3 / 10) as u64;
+/// Beat intervals used for the tempo estimate
+const BEAT_INTERVALS: usize = 8;
+const BPM_RANGE: (f32, f32) = (70.0, 180.0);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
+pub struct AudioLevels {
+    /// RMS of the whole signal
+    pub rms: f32,
+    /// RMS of the low, mid and high band
+    pub bands: [f32; 3],
+    /// A beat started in this window
+    pub beat: bool,
+    pub bpm: Option<f32>,
+}
+
+/// Splits the signal with cascaded one pole low pass filters
+#[derive(Debug, Clone)]
+struct Crossover {
+    coefficients: [f32; 2],
+    state: [[f32; 2]; 2],
+}
+
+impl Crossover {
+    fn new(sample_rate: u32) -> Self {
+        let coefficient = |f: f32| 1.0 - (-2.0 * PI * f / sample_rate as f32).exp();
+        Self {
+            coefficients: CROSSOVERS.map(coefficient),
+            state: [[0.0; 2]; 2],
+        }
+    }
+
+    fn split(&mut self, sample: f32) -> [f32; 3] {
+        let mut low_pass = [0.0; 2];
+        for ((coefficient, state), out) in self
+            .coefficients
+            .iter()
+            .zip(self.state.iter_mut())
+            .zip(low_pass.iter_mut())
+        {
+            state[0] += (sample - state[0]) * coefficient;
+            state[1] += (state[0] - state[1]) * coefficient;
+            *out = state[1];
+        }
+        [low_pass[0], low_pass[1] - low_pass[0], sample - low_pass[1]]
+    }
+}
+
+/// Analyzes windows of a fixed length, results only depend on the samples.
+pub struct AudioAnalyzer {
+    window: usize,
+    crossover: Crossover,
+    filled: usize,
+    sum: f32,
+    band_sums: [f32; 3],
+    /// Windows analyzed so far
+    windows: u64,
+    /// Low band energies of the last second
+    history: VecDeque<f32>,
+    last_beat: Option<u64>,
+    intervals: VecDeque<u64>,
+}
+
+impl AudioAnalyzer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            window: (sample_rate / WINDOWS_PER_SECOND).max(1) as usize,
+            crossover: Crossover::new(sample_rate),
+            filled: 0,
+            sum: 0.0,
+            band_sums: [0.0; 3],
+            windows: 0,
+            history: VecDeque::new(),
+            last_beat: None,
+            intervals: VecDeque::new(),
+        }
+    }
+
+    /// Returns the levels of every window completed by the samples
+    pub fn push(&mut self, samples: &[f32]) -> Vec<AudioLevels> {
+        let mut levels = vec![];
+        for sample in samples {
+            self.sum += sample * sample;
+            for (sum, band) in self.band_sums.iter_mut().zip(self.crossover.split(*sample)) {
+                *sum += band * band;
+            }
+
+            self.filled += 1;
+            if self.filled == self.window {
+                levels.push(self.finish_window());
+            }
+        }
+        levels
+    }
+
+    fn finish_window(&mut self) -> AudioLevels {
+        let n = self.filled as f32;
+        let rms = (self.sum / n).sqrt();
+        let bands = self.band_sums.map(|s| (s / n).sqrt());
+        self.filled = 0;
+        self.sum = 0.0;
+        self.band_sums = [0.0; 3];
+
+        let energy = bands[0] * bands[0];
+        let average = self.history.iter().sum::<f32>() / self.history.len().max(1) as f32;
+        let beat = bands[0] >= BEAT_MIN_RMS
+            && energy > average * BEAT_SENSITIVITY
+            && self
+                .last_beat
+                .is_none_or(|last| self.windows - last >= BEAT_HOLDOFF);
+        if beat {
+            if let Some(last) = self.last_beat {
+                self.intervals.push_back(self.windows - last);
+                if self.intervals.len() > BEAT_INTERVALS {
+                    self.intervals.pop_front();
+                }
+            }
+            self.last_beat = Some(self.windows);
+        }
+        // Without beats for a few seconds the tempo is unknown
+        if self
+            .last_beat
+            .is_some_and(|last| self.windows - last > WINDOWS_PER_SECOND as u64 * 3)
+        {
+            self.last_beat = None;
+            self.intervals.clear();
+        }
+
+        self.history.push_back(energy);
+        if self.history.len() > WINDOWS_PER_SECOND as usize {
+            self.history.pop_front();
+        }
+        self.windows += 1;
+
+        AudioLevels {
+            rms,
+            bands,
+            beat,
+            bpm: self.bpm(),
+        }
+    }
+
+    /// The median beat interval folded into the usual tempo range
+    fn bpm(&self) -> Option<f32> {
+        if self.intervals.len() < 3 {
+            return None;
+        }
+        let mut intervals = self.intervals.iter().copied().collect::<Vec<_>>();
+        intervals.sort_unstable();
+        let median = intervals[intervals.len() / 2];
+
+        let mut bpm = 60.0 * WINDOWS_PER_SECOND as f32 / median as f32;
+        while bpm < BPM_RANGE.0 {
+            bpm *= 2.0;
+        }
+        while bpm > BPM_RANGE.1 {
+            bpm /= 2.0;
+        }
+        Some(bpm)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AudioAnalyzer, AudioLevels};
+    use crate::audio::{AudioSource, WavReader, write_wav};
+    use std::f32::consts::PI;
+
+    /// Kick drums at the tempo over a quiet hi-hat like tone
+    fn beat_track(bpm: f32, seconds: f32, sample_rate: u32) -> Vec<f32> {
+        let beat = 60.0 / bpm;
+        (0..(seconds * sample_rate as f32) as usize)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let since_beat = t % beat;
+                let kick = if since_beat < 0.1 {
+                    (2.0 * PI * 60.0 * since_beat).sin() * (1.0 - since_beat * 10.0) * 0.8
+                } else {
+                    0.0
+                };
+                kick + (2.0 * PI * 6000.0 * t).sin() * 0.1
+            })
+            .collect()
+    }
+
+    fn analyze(samples: &[f32], sample_rate: u32) -> Vec<AudioLevels> {
+        let mut wav = vec![];
+        write_wav(&mut wav, sample_rate, samples).unwrap();
+        let mut reader = WavReader::new(wav.as_slice()).unwrap();
+        let mut analyzer = AudioAnalyzer::new(reader.sample_rate());
+        let mut buf = [0.0; 1000];
+        let mut levels = vec![];
+        while let Ok(n @ 1..) = reader.read(&mut buf) {
+            levels.extend(analyzer.push(&buf[..n]));
+        }
+        levels
+    }
+
+    #[test]
+    fn detect_beats() {
+        let levels = analyze(&beat_track(120.0, 6.0, 48_000), 48_000);
+        assert_eq!(levels.len(), 300);
+
+        let beats = levels.iter().filter(|l| l.beat).count();
+        assert_eq!(beats, 12);
+        let bpm = levels.last().unwrap().bpm.unwrap();
+        assert!((bpm - 120.0).abs() < 1.0, "{bpm}");
+
+        // The kick is in the low band, the tone in the high band
+        let kick = levels.iter().find(|l| l.beat).unwrap();
+        assert!(kick.bands[0] > kick.bands[1] && kick.bands[0] > kick.bands[2]);
+        let between = &levels[levels.len() - 10];
+        assert!(between.bands[2] > between.bands[0]);
+        assert!((between.rms - 0.1 / 2f32.sqrt()).abs() < 0.01);
+
+        // Slow songs are folded into the tempo range
+        let levels = analyze(&beat_track(60.0, 6.0, 44_100), 44_100);
+        let bpm = levels.last().unwrap().bpm.unwrap();
+        assert!((bpm - 120.0).abs() < 2.0, "{bpm}");
+
+        let silence = analyze(&[0.0; 48_000], 48_000);
+        assert!(silence.iter().all(|l| *l == AudioLevels::default()));
+    }
+}
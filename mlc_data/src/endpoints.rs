@@ -1,21 +1,137 @@
 use crate::project::universe::UniverseId;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt::Display;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::time::Duration;
 
+/// Source name used for sACN endpoints that do not specify one
+pub const DEFAULT_SACN_SOURCE_NAME: &str = "MLC Controller";
+/// Priority receivers assume when none is specified
+pub const DEFAULT_SACN_PRIORITY: u8 = 100;
+pub const MAX_SACN_PRIORITY: u8 = 200;
+/// Port-Addresses have 15 bits, the net in the upper 7, sub-net and universe in 4 bits each
+pub const MAX_ARTNET_PORT_ADDRESS: u16 = 0x7FFF;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EndpointMapping {
     pub endpoints: HashMap<UniverseId, Vec<EndpointConfig>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub enum EndpointConfig {
     Logger,
-    ArtNet,
-    Sacn { universe: u16, speed: EndpointSpeed },
+    /// Keeps the last frame in memory, for testing without hardware
+    Simulator,
+    ArtNet {
+        /// The node to send to, a broadcast address reaches every node in the network
+        #[serde(default = "default_artnet_node")]
+        node: Ipv4Addr,
+        /// Net, sub-net and universe of the output
+        port_address: u16,
+        speed: EndpointSpeed,
+    },
+    Sacn {
+        universe: u16,
+        speed: EndpointSpeed,
+        #[serde(default = "default_sacn_priority")]
+        priority: u8,
+        #[serde(default)]
+        source_name: Option<String>,
+        /// Receivers to send to directly, multicast is used if empty
+        #[serde(default)]
+        unicast: Vec<SocketAddr>,
+        /// Marks the data as preview data, not meant for live output
+        #[serde(default)]
+        preview: bool,
+        /// Endpoints with the same sync universe are applied together by the receivers
+        #[serde(default)]
+        sync_universe: Option<u16>,
+    },
     Usb { port: String, speed: EndpointSpeed },
 }
 
+fn default_sacn_priority() -> u8 {
+    DEFAULT_SACN_PRIORITY
+}
+
+fn default_artnet_node() -> Ipv4Addr {
+    Ipv4Addr::BROADCAST
+}
+
+impl EndpointConfig {
+    /// A multicast sACN endpoint with default settings
+    pub fn sacn(universe: u16, speed: EndpointSpeed) -> Self {
+        EndpointConfig::Sacn {
+            universe,
+            speed,
+            priority: DEFAULT_SACN_PRIORITY,
+            source_name: None,
+            unicast: vec![],
+            preview: false,
+            sync_universe: None,
+        }
+    }
+
+    /// An Art-Net endpoint sending to the port of a node
+    pub fn artnet(node: Ipv4Addr, port_address: u16, speed: EndpointSpeed) -> Self {
+        EndpointConfig::ArtNet {
+            node,
+            port_address,
+            speed,
+        }
+    }
+
+    /// The configured output rate, `None` for endpoints sending on every change
+    pub fn speed(&self) -> Option<EndpointSpeed> {
+        match self {
+            EndpointConfig::Logger | EndpointConfig::Simulator => None,
+            EndpointConfig::ArtNet { speed, .. }
+            | EndpointConfig::Sacn { speed, .. }
+            | EndpointConfig::Usb { speed, .. } => Some(*speed),
+        }
+    }
+}
+
+impl Display for EndpointConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EndpointConfig::Logger => write!(f, "Logger"),
+            EndpointConfig::Simulator => write!(f, "Simulator"),
+            EndpointConfig::ArtNet {
+                node,
+                port_address,
+                speed,
+            } => {
+                let (net, sub_net, universe) = (
+                    port_address >> 8,
+                    (port_address >> 4) & 0xF,
+                    port_address & 0xF,
+                );
+                write!(
+                    f,
+                    "Art-Net {node} port {net}:{sub_net}:{universe} ({} ms)",
+                    speed.ms()
+                )
+            }
+            EndpointConfig::Sacn {
+                universe,
+                speed,
+                priority,
+                sync_universe,
+                ..
+            } => {
+                write!(f, "sACN universe {universe} ({} ms, priority {priority}", speed.ms())?;
+                if let Some(sync) = sync_universe {
+                    write!(f, ", sync {sync}")?;
+                }
+                write!(f, ")")
+            }
+            EndpointConfig::Usb { port, speed } => write!(f, "USB {port} ({} ms)", speed.ms()),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum EndpointSpeed {
     Slow,
@@ -35,7 +151,11 @@ impl EndpointSpeed {
             EndpointSpeed::Custom(ms) => *ms,
         }
     }
+    /// At least a millisecond, intervals can't be empty
     pub fn duration(&self) -> Duration {
-        Duration::from_millis(self.ms())
+        Duration::from_millis(self.ms().max(1))
+    }
+    pub fn fps(&self) -> f32 {
+        1000.0 / self.ms().max(1) as f32
     }
 }
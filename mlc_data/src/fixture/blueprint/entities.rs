@@ -78,6 +78,27 @@ pub enum Color {
     Indigo
 }
 
+impl Color {
+    /// Approximation of the color in the rgb space, used when a fixture has no dedicated emitter for it.
+    pub fn rgb(&self) -> [f32; 3] {
+        match self {
+            Color::Red => [1.0, 0.0, 0.0],
+            Color::Green => [0.0, 1.0, 0.0],
+            Color::Blue => [0.0, 0.0, 1.0],
+            Color::Cyan => [0.0, 1.0, 1.0],
+            Color::Magenta => [1.0, 0.0, 1.0],
+            Color::Yellow => [1.0, 1.0, 0.0],
+            Color::Amber => [1.0, 0.75, 0.0],
+            Color::White => [1.0, 1.0, 1.0],
+            Color::WarmWhite => [1.0, 0.85, 0.6],
+            Color::ColdWhite => [0.85, 0.9, 1.0],
+            Color::UV => [0.3, 0.0, 1.0],
+            Color::Lime => [0.75, 1.0, 0.0],
+            Color::Indigo => [0.3, 0.0, 0.5],
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct DynamicColor {
     pub r: u8,
@@ -1,4 +1,5 @@
 use feature::FixtureFeature;
+use output::OutputProcessing;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -6,7 +7,9 @@ use crate::D3Vec;
 
 use super::blueprint::FixtureBlueprint;
 
+pub mod attribute;
 pub mod feature;
+pub mod output;
 
 pub type PatchedFixtureId = Uuid;
 
@@ -18,6 +21,8 @@ pub struct PatchedFixture {
     pub mode_index: usize,
     pub features: Vec<FixtureFeature>,
     pub matrix_features: Option<FeatureMatrix>,
+    #[serde(default)]
+    pub output: OutputProcessing,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
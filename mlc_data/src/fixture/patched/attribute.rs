@@ -0,0 +1,445 @@
+use serde::{Deserialize, Serialize};
+
+use super::PatchedFixture;
+use crate::fixture::blueprint::entities::{Color, RotationAngle};
+use crate::fixture::blueprint::units::Degree;
+use crate::fixture::blueprint::{
+    Capability, CapabilityKind, Channel, ChannelIdentifier, CommonChannel, Mode,
+};
+use crate::{MaybeLinear, PercentageDmxExt, SavePercentage};
+
+/// A feature level value of a fixture, independent of the channel layout of its blueprint.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum FixtureAttribute {
+    Intensity(SavePercentage),
+    Color(Color),
+    Pan(Degree),
+    Tilt(Degree),
+    Zoom(SavePercentage),
+    Channel {
+        channel: ChannelIdentifier,
+        value: SavePercentage,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AttributeKind {
+    Intensity,
+    Color,
+    Pan,
+    Tilt,
+    Zoom,
+    Channel(ChannelIdentifier),
+}
+
+impl FixtureAttribute {
+    pub fn kind(&self) -> AttributeKind {
+        match self {
+            FixtureAttribute::Intensity(_) => AttributeKind::Intensity,
+            FixtureAttribute::Color(_) => AttributeKind::Color,
+            FixtureAttribute::Pan(_) => AttributeKind::Pan,
+            FixtureAttribute::Tilt(_) => AttributeKind::Tilt,
+            FixtureAttribute::Zoom(_) => AttributeKind::Zoom,
+            FixtureAttribute::Channel { channel, .. } => AttributeKind::Channel(channel.clone()),
+        }
+    }
+}
+
+/// Replaces an attribute of the same kind or appends it if there is none yet.
+pub fn merge_attribute(attributes: &mut Vec<FixtureAttribute>, attribute: FixtureAttribute) {
+    let kind = attribute.kind();
+    match attributes.iter_mut().find(|a| a.kind() == kind) {
+        Some(a) => *a = attribute,
+        None => attributes.push(attribute),
+    }
+}
+
+/// A dmx value relative to the start address of a fixture (0 based).
+pub type ChannelValue = (usize, u8);
+
+impl PatchedFixture {
+    pub fn mode(&self) -> Option<&Mode> {
+        self.config.modes.get(self.mode_index)
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.mode().map(|m| m.channels.len()).unwrap_or(0)
+    }
+
+    /// Converts the attributes to dmx values through the capabilities of the blueprint.
+    /// Attributes the fixture has no capability for are skipped.
+    pub fn render_attributes(&self, attributes: &[FixtureAttribute]) -> Vec<ChannelValue> {
+        let Some(mode) = self.mode() else {
+            return vec![];
+        };
+        let mut out = vec![];
+
+        let intensity = attributes.iter().find_map(|a| match a {
+            FixtureAttribute::Intensity(p) => Some(p.take()),
+            _ => None,
+        });
+        let color = attributes.iter().find_map(|a| match a {
+            FixtureAttribute::Color(c) => Some(c.clone()),
+            _ => None,
+        });
+
+        let has_dimmer = self
+            .capabilities(mode)
+            .any(|(_, _, c)| matches!(c.kind, CapabilityKind::Intensity { .. }));
+        // Fixtures without a dimmer channel get their intensity by scaling the color emitters
+        let emitter_level = if has_dimmer {
+            1.0
+        } else {
+            intensity.unwrap_or(1.0)
+        };
+
+        for attribute in attributes {
+            match attribute {
+                FixtureAttribute::Intensity(p) => {
+                    for (ident, channel, cap) in self.capabilities(mode) {
+                        if matches!(cap.kind, CapabilityKind::Intensity { .. }) {
+                            write_channel(
+                                mode,
+                                ident,
+                                channel,
+                                capability_value(cap, p.take()),
+                                &mut out,
+                            );
+                        }
+                    }
+                }
+                FixtureAttribute::Pan(degree) | FixtureAttribute::Tilt(degree) => {
+                    let is_pan = matches!(attribute, FixtureAttribute::Pan(_));
+                    for (ident, channel, cap) in self.capabilities(mode) {
+                        let fraction = match &cap.kind {
+                            CapabilityKind::Pan { angle } if is_pan => {
+                                angle_fraction(angle, degree)
+                            }
+                            CapabilityKind::Tilt { angle } if !is_pan => {
+                                angle_fraction(angle, degree)
+                            }
+                            _ => None,
+                        };
+                        if let Some(f) = fraction {
+                            write_channel(mode, ident, channel, capability_value(cap, f), &mut out);
+                            break;
+                        }
+                    }
+                }
+                FixtureAttribute::Zoom(p) => {
+                    if let Some((ident, channel, cap)) = self
+                        .capabilities(mode)
+                        .find(|(_, _, c)| matches!(c.kind, CapabilityKind::Zoom { .. }))
+                    {
+                        write_channel(
+                            mode,
+                            ident,
+                            channel,
+                            capability_value(cap, p.take()),
+                            &mut out,
+                        );
+                    }
+                }
+                FixtureAttribute::Channel {
+                    channel: ident,
+                    value,
+                } => {
+                    if let Some(channel) = self.config.channels.get(ident) {
+                        write_channel(mode, ident, channel, *value, &mut out);
+                    }
+                }
+                FixtureAttribute::Color(_) => {}
+            }
+        }
+
+        if color.is_some() || (!has_dimmer && intensity.is_some()) {
+            let color = color.unwrap_or(Color::White);
+            let emitters = self
+                .capabilities(mode)
+                .filter_map(|(i, ch, cap)| match &cap.kind {
+                    CapabilityKind::ColorIntensity { color, .. } => Some((i, ch, cap, color)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            let exact = emitters.iter().any(|(_, _, _, c)| **c == color);
+            let rgb = color.rgb();
+
+            for (ident, channel, cap, emitter) in emitters {
+                let level = if exact {
+                    if *emitter == color { 1.0 } else { 0.0 }
+                } else {
+                    match emitter {
+                        Color::Red => rgb[0],
+                        Color::Green => rgb[1],
+                        Color::Blue => rgb[2],
+                        _ => 0.0,
+                    }
+                };
+                write_channel(
+                    mode,
+                    ident,
+                    channel,
+                    capability_value(cap, level * emitter_level),
+                    &mut out,
+                );
+            }
+        }
+
+        out
+    }
+
+    /// Offsets of the channels controlling the intensity of the fixture, ordered from coarse to fine.
+    /// Fixtures without a dimmer channel report their color emitters instead.
+    /// Channels that mix intensity with other functions (e.g. strobe ranges) are left out.
+    pub fn intensity_channels(&self) -> Vec<Vec<usize>> {
+        let Some(mode) = self.mode() else {
+            return vec![];
+        };
+
+        let is_dimmer = |c: &Capability| matches!(c.kind, CapabilityKind::Intensity { .. });
+        let is_emitter = |c: &Capability| matches!(c.kind, CapabilityKind::ColorIntensity { .. });
+        let has_dimmer = self.capabilities(mode).any(|(_, _, c)| is_dimmer(c));
+
+        let mut out = vec![];
+        for ident in mode.channels.iter().flatten() {
+            let Some(channel) = self.config.channels.get(ident) else {
+                continue;
+            };
+            let caps = &common_channel(channel).capabilities;
+            let matching = caps.iter().any(|c| {
+                if has_dimmer {
+                    is_dimmer(c)
+                } else {
+                    is_emitter(c)
+                }
+            });
+            let pure = caps.iter().all(|c| {
+                matches!(c.kind, CapabilityKind::NoFunction)
+                    || if has_dimmer {
+                        is_dimmer(c)
+                    } else {
+                        is_emitter(c)
+                    }
+            });
+
+            if matching && pure {
+                let offsets = channel_offsets(mode, ident, channel);
+                if !offsets.is_empty() && !out.contains(&offsets) {
+                    out.push(offsets);
+                }
+            }
+        }
+        out
+    }
+
+    fn capabilities<'a>(
+        &'a self,
+        mode: &'a Mode,
+    ) -> impl Iterator<Item = (&'a ChannelIdentifier, &'a Channel, &'a Capability)> {
+        mode.channels.iter().flatten().flat_map(|ident| {
+            self.config
+                .channels
+                .get(ident)
+                .into_iter()
+                .flat_map(move |channel| {
+                    common_channel(channel)
+                        .capabilities
+                        .iter()
+                        .map(move |cap| (ident, channel, cap))
+                })
+        })
+    }
+}
+
+pub(super) fn common_channel(channel: &Channel) -> &CommonChannel {
+    match channel {
+        Channel::Single { channel } => channel,
+        Channel::Double { channel, .. } => channel,
+        Channel::Tripple { channel, .. } => channel,
+    }
+}
+
+fn capability_value(cap: &Capability, fraction: f32) -> SavePercentage {
+    let start = cap.range.start().take();
+    let end = cap.range.end().take();
+    SavePercentage::create(start + (end - start) * fraction.clamp(0.0, 1.0))
+}
+
+fn angle_fraction(angle: &MaybeLinear<RotationAngle>, degree: &Degree) -> Option<f32> {
+    match angle {
+        MaybeLinear::Linear {
+            start: RotationAngle::Degrees(Degree(start)),
+            end: RotationAngle::Degrees(Degree(end)),
+        } if start != end => Some(((degree.0 - start) / (end - start)).clamp(0.0, 1.0)),
+        _ => None,
+    }
+}
+
+pub(super) fn channel_offsets(mode: &Mode, ident: &ChannelIdentifier, channel: &Channel) -> Vec<usize> {
+    let names = match channel {
+        Channel::Single { .. } => vec![ident],
+        Channel::Double {
+            second_channel_name,
+            ..
+        } => vec![ident, second_channel_name],
+        Channel::Tripple {
+            second_channel_name,
+            third_channel_name,
+            ..
+        } => vec![ident, second_channel_name, third_channel_name],
+    };
+
+    names
+        .into_iter()
+        .filter_map(|name| mode.channels.iter().position(|c| c.as_ref() == Some(name)))
+        .collect()
+}
+
+fn write_channel(
+    mode: &Mode,
+    ident: &ChannelIdentifier,
+    channel: &Channel,
+    value: SavePercentage,
+    out: &mut Vec<ChannelValue>,
+) {
+    let offset =
+        |name: &ChannelIdentifier| mode.channels.iter().position(|c| c.as_ref() == Some(name));
+    let mut push = |name: &ChannelIdentifier, v: u8| {
+        if let Some(o) = offset(name) {
+            out.push((o, v));
+        }
+    };
+
+    match channel {
+        Channel::Single { .. } => push(ident, value.to_single_dmx().take()),
+        Channel::Double {
+            second_channel_name,
+            ..
+        } => {
+            let [coarse, fine] = value.to_double_dmx().take().to_be_bytes();
+            push(ident, coarse);
+            push(second_channel_name, fine);
+        }
+        Channel::Tripple {
+            second_channel_name,
+            third_channel_name,
+            ..
+        } => {
+            let [_, coarse, fine, ultra] = value.to_tripple_dmx().take().to_be_bytes();
+            push(ident, coarse);
+            push(second_channel_name, fine);
+            push(third_channel_name, ultra);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FixtureAttribute, PatchedFixture};
+    use crate::fixture::blueprint::entities::{Brightness, Color, RotationAngle};
+    use crate::fixture::blueprint::units::{Degree, Percentage};
+    use crate::fixture::blueprint::{
+        Capability, CapabilityKind, Channel, CommonChannel, FixtureBlueprint, Metadata, Mode,
+        Physical, PixelIdentifier,
+    };
+    use crate::{MaybeLinear, SavePercentage};
+    use std::collections::HashMap;
+
+    fn full(kind: CapabilityKind) -> CommonChannel {
+        CommonChannel {
+            default_value: SavePercentage::create(0.0),
+            capabilities: vec![Capability {
+                range: SavePercentage::create(0.0)..=SavePercentage::create(1.0),
+                pixel: PixelIdentifier::Master,
+                comment: None,
+                kind,
+            }],
+        }
+    }
+
+    fn emitter(color: Color) -> Channel {
+        Channel::Single {
+            channel: full(CapabilityKind::ColorIntensity {
+                brightness: MaybeLinear::Constant(Brightness::Percent(Percentage(1.0))),
+                color,
+            }),
+        }
+    }
+
+    fn fixture() -> PatchedFixture {
+        let mut channels = HashMap::new();
+        channels.insert(
+            "Dimmer".to_string(),
+            Channel::Single {
+                channel: full(CapabilityKind::Intensity {
+                    brightness: MaybeLinear::Constant(Brightness::Percent(Percentage(1.0))),
+                }),
+            },
+        );
+        channels.insert(
+            "Pan".to_string(),
+            Channel::Double {
+                channel: full(CapabilityKind::Pan {
+                    angle: MaybeLinear::Linear {
+                        start: RotationAngle::Degrees(Degree(0.0)),
+                        end: RotationAngle::Degrees(Degree(540.0)),
+                    },
+                }),
+                second_channel_name: "Pan fine".to_string(),
+            },
+        );
+        channels.insert("Red".to_string(), emitter(Color::Red));
+        channels.insert("Green".to_string(), emitter(Color::Green));
+        channels.insert("Blue".to_string(), emitter(Color::Blue));
+
+        PatchedFixture {
+            id: uuid::Uuid::nil(),
+            identifier: "Test".to_string(),
+            config: FixtureBlueprint {
+                meta: Metadata {
+                    name: "Test".to_string(),
+                    identifier: "test".to_string(),
+                    manufacturer: "Test".to_string(),
+                    physical: Physical {
+                        dimensions: None,
+                        weight: 0.0,
+                        power_consumption: 0.0,
+                        power_connectors: String::new(),
+                        dmx_connector: String::new(),
+                        bulb: String::new(),
+                        lens: String::new(),
+                    },
+                },
+                channels,
+                modes: vec![Mode {
+                    name: "Default".to_string(),
+                    channels: ["Dimmer", "Pan", "Pan fine", "Red", "Green", "Blue"]
+                        .into_iter()
+                        .map(|c| Some(c.to_string()))
+                        .collect(),
+                }],
+                matrix: None,
+                wheels: None,
+            },
+            mode_index: 0,
+            features: vec![],
+            matrix_features: None,
+            output: Default::default(),
+        }
+    }
+
+    #[test]
+    fn render_attributes() {
+        let mut values = fixture().render_attributes(&[
+            FixtureAttribute::Intensity(SavePercentage::create(1.0)),
+            FixtureAttribute::Pan(Degree(270.0)),
+            FixtureAttribute::Color(Color::Amber),
+        ]);
+        values.sort();
+
+        assert_eq!(
+            values,
+            vec![(0, 255), (1, 127), (2, 255), (3, 255), (4, 191), (5, 0)]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use super::attribute::{channel_offsets, common_channel};
+use super::PatchedFixture;
+use crate::fixture::blueprint::{CapabilityKind, ChannelIdentifier, Mode};
+use crate::SavePercentage;
+
+/// Processing of the dmx values of a fixture, applied right before they are sent to the endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct OutputProcessing {
+    pub channels: Vec<ChannelProcessing>,
+    pub swap_pan_tilt: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChannelProcessing {
+    pub channel: ChannelIdentifier,
+    pub curve: ResponseCurve,
+    pub invert: bool,
+    pub min: SavePercentage,
+    pub max: SavePercentage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    Square,
+    SCurve,
+    /// Output levels evenly spread over the input range, interpolated linearly in between
+    Lut(Vec<SavePercentage>),
+}
+
+impl ResponseCurve {
+    pub fn apply(&self, value: f32) -> f32 {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Square => value * value,
+            ResponseCurve::SCurve => value * value * (3.0 - 2.0 * value),
+            ResponseCurve::Lut(points) => match points.as_slice() {
+                [] => value,
+                [point] => point.take(),
+                points => {
+                    let pos = value * (points.len() - 1) as f32;
+                    let i = (pos.floor() as usize).min(points.len() - 2);
+                    let (a, b) = (points[i].take(), points[i + 1].take());
+                    a + (b - a) * (pos - i as f32)
+                }
+            },
+        }
+    }
+}
+
+impl ChannelProcessing {
+    pub fn new(channel: ChannelIdentifier) -> Self {
+        Self {
+            channel,
+            curve: ResponseCurve::Linear,
+            invert: false,
+            min: SavePercentage::create(0.0),
+            max: SavePercentage::create(1.0),
+        }
+    }
+
+    /// Applies curve, inversion and limits in that order.
+    pub fn apply(&self, value: f32) -> f32 {
+        let value = self.curve.apply(value);
+        let value = if self.invert { 1.0 - value } else { value };
+        value.max(self.min.take()).min(self.max.take())
+    }
+}
+
+/// The output processing of a fixture resolved to channel offsets relative to its start address.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputProcessor {
+    swap: Option<(Vec<usize>, Vec<usize>)>,
+    channels: Vec<(Vec<usize>, ChannelProcessing)>,
+}
+
+impl OutputProcessor {
+    /// Processes the values of a fixture in place, `values` starts at the fixture start address.
+    pub fn apply(&self, values: &mut [u8]) {
+        if let Some((pan, tilt)) = &self.swap {
+            for (p, t) in pan.iter().zip(tilt) {
+                if *p < values.len() && *t < values.len() {
+                    values.swap(*p, *t);
+                }
+            }
+        }
+
+        for (offsets, processing) in &self.channels {
+            if offsets.iter().any(|o| *o >= values.len()) {
+                continue;
+            }
+
+            let max = ((1_u64 << (8 * offsets.len())) - 1) as f64;
+            let value = offsets
+                .iter()
+                .fold(0_u64, |v, o| (v << 8) | values[*o] as u64);
+            let processed = processing.apply((value as f64 / max) as f32) as f64;
+            let mut out = (processed * max).round() as u64;
+
+            for o in offsets.iter().rev() {
+                values[*o] = (out & 0xFF) as u8;
+                out >>= 8;
+            }
+        }
+    }
+}
+
+impl PatchedFixture {
+    /// Resolves the output processing against the current mode,
+    /// `None` if the fixture has nothing to process.
+    pub fn output_processor(&self) -> Option<OutputProcessor> {
+        let mode = self.mode()?;
+
+        let swap = if self.output.swap_pan_tilt {
+            let pan = self.motion_channel(mode, |k| matches!(k, CapabilityKind::Pan { .. }));
+            let tilt = self.motion_channel(mode, |k| matches!(k, CapabilityKind::Tilt { .. }));
+            pan.zip(tilt)
+        } else {
+            None
+        };
+
+        let channels = self
+            .output
+            .channels
+            .iter()
+            .filter_map(|p| {
+                let channel = self.config.channels.get(&p.channel)?;
+                let offsets = channel_offsets(mode, &p.channel, channel);
+                (!offsets.is_empty()).then(|| (offsets, p.clone()))
+            })
+            .collect::<Vec<_>>();
+
+        (swap.is_some() || !channels.is_empty()).then_some(OutputProcessor { swap, channels })
+    }
+
+    fn motion_channel(
+        &self,
+        mode: &Mode,
+        is_kind: impl Fn(&CapabilityKind) -> bool,
+    ) -> Option<Vec<usize>> {
+        mode.channels.iter().flatten().find_map(|ident| {
+            let channel = self.config.channels.get(ident)?;
+            common_channel(channel)
+                .capabilities
+                .iter()
+                .any(|c| is_kind(&c.kind))
+                .then(|| channel_offsets(mode, ident, channel))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ChannelProcessing, OutputProcessor, ResponseCurve};
+    use crate::SavePercentage;
+
+    #[test]
+    fn process_output() {
+        let processor = OutputProcessor {
+            swap: Some((vec![1, 2], vec![3, 4])),
+            channels: vec![
+                (
+                    vec![0],
+                    ChannelProcessing {
+                        curve: ResponseCurve::Square,
+                        max: SavePercentage::create(0.6),
+                        ..ChannelProcessing::new("Dimmer".to_string())
+                    },
+                ),
+                (
+                    vec![1, 2],
+                    ChannelProcessing {
+                        invert: true,
+                        ..ChannelProcessing::new("Pan".to_string())
+                    },
+                ),
+            ],
+        };
+
+        let mut values = [255, 1, 2, 0xFF, 0x00];
+        processor.apply(&mut values);
+        assert_eq!(values, [153, 0x00, 0xFF, 1, 2]);
+
+        let mut values = [128];
+        processor.apply(&mut values);
+        assert_eq!(values, [64]);
+
+        let lut = ResponseCurve::Lut(vec![
+            SavePercentage::create(0.0),
+            SavePercentage::create(1.0),
+            SavePercentage::create(0.5),
+        ]);
+        assert_eq!(lut.apply(0.25), 0.5);
+        assert_eq!(lut.apply(0.75), 0.75);
+    }
+}
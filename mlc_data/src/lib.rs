@@ -6,13 +6,19 @@ use bounded::{
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+pub use chrono;
 pub use uuid;
 
+pub mod audio;
 pub mod bounded;
 pub mod fixture;
+pub mod midi;
 pub mod misc;
 pub mod project;
 pub mod endpoints;
+pub mod recording;
+pub mod tempo;
+pub mod timecode;
 
 pub type D3Vec<T> = Vec<Vec<Vec<T>>>;
 
@@ -0,0 +1,182 @@
+//! Parsing of raw MIDI byte streams as read from MIDI ports.
+
+use serde::{Deserialize, Serialize};
+
+/// Channels are 0 based
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MidiMessage {
+    NoteOff {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    /// MIDI timecode quarter frame data byte
+    QuarterFrame(u8),
+    /// The content of a system exclusive message without the framing `F0` and `F7` bytes
+    SysEx(Vec<u8>),
+    Other,
+}
+
+/// Splits a byte stream into messages, handling running status and interleaved real time bytes
+#[derive(Debug, Default)]
+pub struct MidiParser {
+    status: Option<u8>,
+    data: Vec<u8>,
+    sysex: Option<Vec<u8>>,
+}
+
+impl MidiParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<MidiMessage> {
+        bytes.iter().filter_map(|b| self.push(*b)).collect()
+    }
+
+    pub fn push(&mut self, byte: u8) -> Option<MidiMessage> {
+        match byte {
+            // Real time messages don't interrupt other messages
+            0xF8..=0xFF => None,
+            0xF0 => {
+                self.status = None;
+                self.sysex = Some(vec![]);
+                None
+            }
+            0xF7 => self.sysex.take().map(MidiMessage::SysEx),
+            0x80..=0xEF => {
+                self.sysex = None;
+                self.status = Some(byte);
+                self.data.clear();
+                None
+            }
+            0xF1..=0xF6 => {
+                self.sysex = None;
+                self.data.clear();
+                // Tune request has no data
+                self.status = (byte != 0xF6).then_some(byte);
+                self.status.is_none().then_some(MidiMessage::Other)
+            }
+            _ => {
+                if let Some(sysex) = &mut self.sysex {
+                    sysex.push(byte);
+                    return None;
+                }
+
+                let status = self.status?;
+                self.data.push(byte);
+                if self.data.len() < data_len(status) {
+                    return None;
+                }
+
+                let message = message(status, &self.data);
+                self.data.clear();
+                // System common messages have no running status
+                if status >= 0xF0 {
+                    self.status = None;
+                }
+                Some(message)
+            }
+        }
+    }
+}
+
+fn data_len(status: u8) -> usize {
+    match status {
+        0xC0..=0xDF | 0xF1 | 0xF3 => 1,
+        _ => 2,
+    }
+}
+
+fn message(status: u8, data: &[u8]) -> MidiMessage {
+    let channel = status & 0x0F;
+    match status & 0xF0 {
+        0x80 => MidiMessage::NoteOff {
+            channel,
+            note: data[0],
+            velocity: data[1],
+        },
+        // Note on with velocity 0 is commonly used as note off
+        0x90 if data[1] == 0 => MidiMessage::NoteOff {
+            channel,
+            note: data[0],
+            velocity: 0,
+        },
+        0x90 => MidiMessage::NoteOn {
+            channel,
+            note: data[0],
+            velocity: data[1],
+        },
+        0xB0 => MidiMessage::ControlChange {
+            channel,
+            controller: data[0],
+            value: data[1],
+        },
+        0xC0 => MidiMessage::ProgramChange {
+            channel,
+            program: data[0],
+        },
+        _ if status == 0xF1 => MidiMessage::QuarterFrame(data[0]),
+        _ => MidiMessage::Other,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MidiMessage, MidiParser};
+
+    #[test]
+    fn parse_stream() {
+        let mut parser = MidiParser::new();
+        let messages = parser.parse(&[
+            0xB0, 7, 100, // control change
+            8, 0xF8, 64, // running status with an interleaved clock
+            0x91, 60, 127, 60, 0, // note on, note off by running status
+            0xF0, 0x7F, 0x7F, 0x06, 0x02, 0xF7, // sysex
+            0xF1, 0x23, // quarter frame
+            0x40, // stray data byte
+        ]);
+
+        assert_eq!(
+            messages,
+            vec![
+                MidiMessage::ControlChange {
+                    channel: 0,
+                    controller: 7,
+                    value: 100
+                },
+                MidiMessage::ControlChange {
+                    channel: 0,
+                    controller: 8,
+                    value: 64
+                },
+                MidiMessage::NoteOn {
+                    channel: 1,
+                    note: 60,
+                    velocity: 127
+                },
+                MidiMessage::NoteOff {
+                    channel: 1,
+                    note: 60,
+                    velocity: 0
+                },
+                MidiMessage::SysEx(vec![0x7F, 0x7F, 0x06, 0x02]),
+                MidiMessage::QuarterFrame(0x23),
+            ]
+        );
+    }
+}
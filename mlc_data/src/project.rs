@@ -1,8 +1,20 @@
 use chrono::{DateTime, Local};
 use std::time::Duration;
 
+pub mod audio;
+pub mod control;
+pub mod cue;
+pub mod group;
+pub mod midi;
+pub mod osc;
+pub mod schedule;
+pub mod script;
+pub mod timecode;
 pub mod universe;
 
+/// Extension of exported project archives
+pub const ARCHIVE_EXTENSION: &str = "mlcp";
+
 #[derive(PartialEq, Debug, Clone, Copy, serde::Serialize, serde::Deserialize, Default)]
 pub enum ProjectType {
     #[default]
@@ -90,4 +102,24 @@ pub struct ProjectMetadata {
 pub struct ProjectSettings {
     pub save_on_quit: bool,
     pub autosave: Option<Duration>,
+    /// Number of previous saves kept as backups
+    #[serde(default = "default_backups")]
+    pub backups: u32,
+}
+
+fn default_backups() -> u32 {
+    ProjectSettings::DEFAULT_BACKUPS
+}
+
+impl ProjectSettings {
+    pub const DEFAULT_BACKUPS: u32 = 5;
+}
+
+/// A previous save of a project
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct ProjectBackup {
+    /// File name of the backup
+    pub id: String,
+    pub created_at: DateTime<Local>,
+    pub size: u64,
 }
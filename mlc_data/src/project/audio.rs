@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::audio::analysis::AudioLevels;
+use crate::project::control::ControlAction;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioInput {
+    /// A WAV file played in real time, or a stream of raw samples like a FIFO fed by `arecord`
+    pub path: PathBuf,
+    /// Reads raw 16 bit mono samples at this rate instead of a WAV file
+    pub raw_sample_rate: Option<u32>,
+}
+
+impl AudioInput {
+    pub fn is_valid(&self) -> bool {
+        !self.path.as_os_str().is_empty() && self.raw_sample_rate != Some(0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct AudioSettings {
+    pub enabled: bool,
+    pub input: Option<AudioInput>,
+    pub mappings: Vec<AudioMapping>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AudioFeature {
+    Rms,
+    Low,
+    Mid,
+    High,
+    /// Fires trigger actions on every detected beat
+    Beat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioMapping {
+    pub feature: AudioFeature,
+    pub action: ControlAction,
+    /// Levels are multiplied with it before they are applied
+    pub gain: f32,
+}
+
+impl AudioMapping {
+    /// Beats fire triggers, levels set faders
+    pub fn is_valid(&self) -> bool {
+        (self.feature == AudioFeature::Beat) == self.action.is_trigger()
+            && !self.action.is_pattern()
+            && self.gain.is_finite()
+            && self.gain >= 0.0
+    }
+
+    /// The value for the action, `None` if it is not applied in this window
+    pub fn value(&self, levels: &AudioLevels) -> Option<f32> {
+        let level = match self.feature {
+            AudioFeature::Rms => levels.rms,
+            AudioFeature::Low => levels.bands[0],
+            AudioFeature::Mid => levels.bands[1],
+            AudioFeature::High => levels.bands[2],
+            AudioFeature::Beat => return levels.beat.then_some(1.0),
+        };
+        Some((level * self.gain).clamp(0.0, 1.0))
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use crate::project::cue::CueListId;
+use crate::project::group::GroupId;
+use crate::project::universe::{FixtureAddress, UniverseId};
+
+/// Something an external control (OSC, MIDI, ...) can trigger or set.
+/// Cue actions without a cue list refer to the first one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ControlAction {
+    CueGo(Option<CueListId>),
+    CueBack(Option<CueListId>),
+    CueRelease(Option<CueListId>),
+    GrandMaster,
+    Blackout,
+    UniverseMaster(UniverseId),
+    /// Any universe master, the universe is given by the control
+    UniverseMasters,
+    GroupMaster(GroupId),
+    Channel(FixtureAddress),
+    /// Any channel, the universe and channel are given by the control
+    Channels,
+    TapTempo,
+    /// Starts the next beat of the tempo now
+    TempoReset,
+    /// Runs the project script with the name
+    RunScript(String),
+}
+
+impl ControlAction {
+    /// Triggered actions fire on press, but not on release
+    pub fn is_trigger(&self) -> bool {
+        matches!(
+            self,
+            ControlAction::CueGo(_)
+                | ControlAction::CueBack(_)
+                | ControlAction::CueRelease(_)
+                | ControlAction::TapTempo
+                | ControlAction::TempoReset
+                | ControlAction::RunScript(_)
+        )
+    }
+
+    /// Pattern actions stand for a family of actions, the control picks the concrete one
+    pub fn is_pattern(&self) -> bool {
+        matches!(
+            self,
+            ControlAction::Channels | ControlAction::UniverseMasters
+        )
+    }
+}
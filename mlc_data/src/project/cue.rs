@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::fixture::patched::{attribute::FixtureAttribute, PatchedFixtureId};
+
+pub type CueListId = Uuid;
+pub type CueId = Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CueList {
+    pub id: CueListId,
+    pub name: String,
+    pub cues: Vec<Cue>,
+    /// A running list advances to the next cue every this many beats of the tempo clock and
+    /// starts over after the last cue
+    #[serde(default)]
+    pub beat_step: Option<u32>,
+}
+
+impl CueList {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            cues: vec![],
+            beat_step: None,
+        }
+    }
+
+    /// Whether a running list advances on the beat
+    pub fn steps_on(&self, beat: u64) -> bool {
+        self.beat_step
+            .is_some_and(|n| !self.cues.is_empty() && beat.is_multiple_of(n as u64))
+    }
+
+    /// The last cue whose timecode is not after the position, cues without timecode are skipped
+    pub fn cue_at(&self, position: Duration) -> Option<usize> {
+        self.cues
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.timecode.is_some_and(|t| t <= position))
+            .max_by_key(|(_, c)| c.timecode)
+            .map(|(i, _)| i)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Cue {
+    pub id: CueId,
+    pub name: String,
+    pub values: Vec<CueValue>,
+    /// Time on the cue list clock the cue is triggered at when chasing timecode
+    #[serde(default)]
+    pub timecode: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CueValue {
+    pub fixture: PatchedFixtureId,
+    pub attributes: Vec<FixtureAttribute>,
+}
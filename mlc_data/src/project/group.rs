@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::fixture::patched::PatchedFixtureId;
+
+pub type GroupId = Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FixtureGroup {
+    pub id: GroupId,
+    pub name: String,
+    pub fixtures: Vec<PatchedFixtureId>,
+}
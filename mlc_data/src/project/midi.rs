@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+use crate::midi::MidiMessage;
+use crate::project::control::ControlAction;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MidiSettings {
+    pub enabled: bool,
+    /// Paths of the MIDI ports to read, all available ports if empty
+    pub ports: Vec<String>,
+    /// Creates a MIDI port other applications can connect to, only on Linux
+    pub virtual_port: bool,
+    pub mappings: Vec<MidiMapping>,
+}
+
+impl Default for MidiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ports: vec![],
+            virtual_port: true,
+            mappings: vec![],
+        }
+    }
+}
+
+/// A control on a MIDI controller, channels are 0 based
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MidiControl {
+    ControlChange { channel: u8, controller: u8 },
+    Note { channel: u8, note: u8 },
+}
+
+impl MidiControl {
+    /// The control a message belongs to and its normalized value
+    pub fn from_message(message: &MidiMessage) -> Option<(Self, f32)> {
+        match *message {
+            MidiMessage::ControlChange {
+                channel,
+                controller,
+                value,
+            } => Some((
+                Self::ControlChange {
+                    channel,
+                    controller,
+                },
+                value as f32 / 127.0,
+            )),
+            MidiMessage::NoteOn {
+                channel,
+                note,
+                velocity,
+            } => Some((Self::Note { channel, note }, velocity as f32 / 127.0)),
+            MidiMessage::NoteOff { channel, note, .. } => Some((Self::Note { channel, note }, 0.0)),
+            _ => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            MidiControl::ControlChange {
+                channel,
+                controller: data,
+            }
+            | MidiControl::Note {
+                channel,
+                note: data,
+            } => channel < 16 && data < 128,
+        }
+    }
+}
+
+impl Display for MidiControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MidiControl::ControlChange {
+                channel,
+                controller,
+            } => write!(f, "CC {controller} on channel {}", channel + 1),
+            MidiControl::Note { channel, note } => {
+                write!(f, "Note {note} on channel {}", channel + 1)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MidiMapping {
+    pub control: MidiControl,
+    pub action: ControlAction,
+}
+
+impl MidiSettings {
+    /// The mapped action of a message with the value of its control
+    pub fn resolve(&self, message: &MidiMessage) -> Option<(ControlAction, f32)> {
+        let (control, value) = MidiControl::from_message(message)?;
+        self.mappings
+            .iter()
+            .find(|m| m.control == control)
+            .map(|m| (m.action.clone(), value))
+    }
+
+    /// Maps a control to an action, replacing its previous mapping
+    pub fn learn(&mut self, control: MidiControl, action: ControlAction) {
+        self.mappings.retain(|m| m.control != control);
+        self.mappings.push(MidiMapping { control, action });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MidiControl, MidiSettings};
+    use crate::midi::MidiMessage;
+    use crate::project::control::ControlAction;
+
+    #[test]
+    fn learn_and_resolve() {
+        let mut settings = MidiSettings::default();
+        let fader = MidiControl::ControlChange {
+            channel: 0,
+            controller: 7,
+        };
+        settings.learn(fader, ControlAction::UniverseMaster(1));
+        settings.learn(fader, ControlAction::GrandMaster);
+        settings.learn(
+            MidiControl::Note {
+                channel: 9,
+                note: 36,
+            },
+            ControlAction::CueGo(None),
+        );
+        assert_eq!(settings.mappings.len(), 2);
+
+        let cc = MidiMessage::ControlChange {
+            channel: 0,
+            controller: 7,
+            value: 127,
+        };
+        assert_eq!(
+            settings.resolve(&cc),
+            Some((ControlAction::GrandMaster, 1.0))
+        );
+
+        let release = MidiMessage::NoteOff {
+            channel: 9,
+            note: 36,
+            velocity: 64,
+        };
+        assert_eq!(
+            settings.resolve(&release),
+            Some((ControlAction::CueGo(None), 0.0))
+        );
+
+        let other = MidiMessage::ControlChange {
+            channel: 1,
+            controller: 7,
+            value: 0,
+        };
+        assert_eq!(settings.resolve(&other), None);
+    }
+}
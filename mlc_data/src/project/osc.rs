@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+
+use crate::project::control::ControlAction;
+use crate::project::universe::{FixtureAddress, UNIVERSE_SIZE, UniverseAddress};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OscSettings {
+    pub enabled: bool,
+    /// UDP port the OSC server listens on
+    pub port: u16,
+    /// Receiver of state changes of the mapped actions, no feedback if `None`
+    pub feedback: Option<SocketAddr>,
+    pub mappings: Vec<OscMapping>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OscMapping {
+    pub address: String,
+    pub action: ControlAction,
+}
+
+impl OscMapping {
+    pub fn new(address: impl Into<String>, action: ControlAction) -> Self {
+        Self {
+            address: address.into(),
+            action,
+        }
+    }
+}
+
+impl Default for OscSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8000,
+            feedback: None,
+            mappings: vec![
+                OscMapping::new("/mlc/cue/go", ControlAction::CueGo(None)),
+                OscMapping::new("/mlc/cue/back", ControlAction::CueBack(None)),
+                OscMapping::new("/mlc/cue/release", ControlAction::CueRelease(None)),
+                OscMapping::new("/mlc/master", ControlAction::GrandMaster),
+                OscMapping::new("/mlc/blackout", ControlAction::Blackout),
+                OscMapping::new("/mlc/universe", ControlAction::Channels),
+                OscMapping::new("/mlc/fader", ControlAction::UniverseMasters),
+                OscMapping::new("/mlc/tempo/tap", ControlAction::TapTempo),
+                OscMapping::new("/mlc/tempo/reset", ControlAction::TempoReset),
+            ],
+        }
+    }
+}
+
+impl OscSettings {
+    /// The action mapped to an address, exact mappings take precedence over pattern mappings.
+    /// [ControlAction::Channels] mappings match `<address>/<universe>/<channel>` and resolve to
+    /// the single channel, [ControlAction::UniverseMasters] mappings match `<address>/<universe>`.
+    pub fn resolve(&self, address: &str) -> Option<ControlAction> {
+        let exact = self
+            .mappings
+            .iter()
+            .find(|m| m.address == address && !m.action.is_pattern());
+        if let Some(m) = exact {
+            return Some(m.action.clone());
+        }
+
+        self.mappings.iter().find_map(|m| {
+            let rest = address.strip_prefix(&m.address)?.strip_prefix('/')?;
+            match m.action {
+                ControlAction::Channels => {
+                    let (universe, channel) = rest.split_once('/')?;
+                    let channel = channel.parse::<usize>().ok()?;
+                    if !(1..=UNIVERSE_SIZE).contains(&channel) {
+                        return None;
+                    }
+                    Some(ControlAction::Channel(FixtureAddress::new(
+                        universe.parse().ok()?,
+                        UniverseAddress::create(channel),
+                    )))
+                }
+                ControlAction::UniverseMasters => {
+                    Some(ControlAction::UniverseMaster(rest.parse().ok()?))
+                }
+                _ => None,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::OscSettings;
+    use crate::project::control::ControlAction;
+    use crate::project::universe::{FixtureAddress, UniverseAddress};
+
+    #[test]
+    fn resolve_addresses() {
+        let settings = OscSettings::default();
+
+        assert_eq!(
+            settings.resolve("/mlc/cue/go"),
+            Some(ControlAction::CueGo(None))
+        );
+        assert_eq!(
+            settings.resolve("/mlc/universe/1/12"),
+            Some(ControlAction::Channel(FixtureAddress::new(
+                1,
+                UniverseAddress::create(12)
+            )))
+        );
+        assert_eq!(settings.resolve("/mlc/universe"), None);
+        assert_eq!(settings.resolve("/mlc/universe/1/513"), None);
+        assert_eq!(settings.resolve("/mlc/universe/x/1"), None);
+        assert_eq!(settings.resolve("/mlc/universes/1/1"), None);
+        assert_eq!(
+            settings.resolve("/mlc/fader/3"),
+            Some(ControlAction::UniverseMaster(3))
+        );
+        assert_eq!(settings.resolve("/mlc/fader"), None);
+        assert_eq!(settings.resolve("/mlc/fader/3/1"), None);
+    }
+}
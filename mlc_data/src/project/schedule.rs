@@ -0,0 +1,276 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::project::control::ControlAction;
+
+/// Days searched for the next trigger
+const LOOKAHEAD_DAYS: u64 = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct ScheduleSettings {
+    pub enabled: bool,
+    /// Location for sunrise and sunset in degrees, east and north are positive
+    pub latitude: f64,
+    pub longitude: f64,
+    pub entries: Vec<ScheduleEntry>,
+    pub holidays: Vec<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleEntry {
+    pub name: String,
+    pub time: ScheduleTime,
+    /// Every day if empty
+    pub weekdays: Vec<Weekday>,
+    pub holidays: HolidayRule,
+    /// Dates the entry is skipped
+    pub exceptions: Vec<NaiveDate>,
+    pub action: ScheduleAction,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ScheduleTime {
+    /// Local time of day
+    At(NaiveTime),
+    Sunrise {
+        offset_minutes: i32,
+    },
+    Sunset {
+        offset_minutes: i32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum HolidayRule {
+    #[default]
+    Skip,
+    Include,
+    Only,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ScheduleAction {
+    /// Executes the action like an external control with the value
+    Control {
+        action: ControlAction,
+        value: Option<f32>,
+    },
+    /// Plays a DMX recording as a timeline
+    PlayRecording {
+        name: String,
+        looping: bool,
+    },
+    StopPlayback,
+}
+
+impl ScheduleSettings {
+    /// Entries due after `from` up to and including `to`, in the order they are due
+    pub fn due<Tz: TimeZone>(&self, from: &DateTime<Tz>, to: &DateTime<Tz>) -> Vec<usize> {
+        let (start, end) = (from.with_timezone(&Utc), to.with_timezone(&Utc));
+        // Sun events may fall on the previous local date
+        let first = from.date_naive().pred_opt().unwrap_or(NaiveDate::MIN);
+        let mut due = self.occurrences(first, to.date_naive(), &to.timezone());
+        due.retain(|(time, _)| start < *time && *time <= end);
+        due.into_iter().map(|(_, i)| i).collect()
+    }
+
+    /// The next entry due after the time
+    pub fn next<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<(DateTime<Utc>, usize)> {
+        let start = after.with_timezone(&Utc);
+        let today = after.date_naive();
+        let last = today.checked_add_days(Days::new(LOOKAHEAD_DAYS))?;
+        self.occurrences(today, last, &after.timezone())
+            .into_iter()
+            .find(|(time, _)| *time > start)
+    }
+
+    /// All triggers on the local dates, sorted by time
+    fn occurrences<Tz: TimeZone>(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+        tz: &Tz,
+    ) -> Vec<(DateTime<Utc>, usize)> {
+        let mut occurrences = first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .flat_map(|date| {
+                (0..self.entries.len())
+                    .filter_map(move |i| Some((self.occurrence(i, date, tz)?, i)))
+            })
+            .collect::<Vec<_>>();
+        occurrences.sort();
+        occurrences
+    }
+
+    /// When the entry triggers on the local date
+    fn occurrence<Tz: TimeZone>(
+        &self,
+        entry: usize,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> Option<DateTime<Utc>> {
+        let entry = &self.entries[entry];
+        let holiday = self.holidays.contains(&date);
+        let applies = match entry.holidays {
+            HolidayRule::Skip => !holiday,
+            HolidayRule::Include => true,
+            HolidayRule::Only => holiday,
+        };
+        if !applies
+            || entry.exceptions.contains(&date)
+            || (!entry.weekdays.is_empty() && !entry.weekdays.contains(&date.weekday()))
+        {
+            return None;
+        }
+
+        match entry.time {
+            ScheduleTime::At(time) => tz
+                .from_local_datetime(&date.and_time(time))
+                .earliest()
+                .map(|t| t.with_timezone(&Utc)),
+            ScheduleTime::Sunrise { offset_minutes } => {
+                let (rise, _) = sun_times(date, self.latitude, self.longitude)?;
+                Some(rise + chrono::Duration::minutes(offset_minutes as i64))
+            }
+            ScheduleTime::Sunset { offset_minutes } => {
+                let (_, set) = sun_times(date, self.latitude, self.longitude)?;
+                Some(set + chrono::Duration::minutes(offset_minutes as i64))
+            }
+        }
+    }
+}
+
+/// Sunrise and sunset on the date with the sunrise equation, `None` during polar day or night
+pub fn sun_times(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    const J2000: f64 = 2_451_545.0;
+    const UNIX_EPOCH: f64 = 2_440_587.5;
+    let epoch = NaiveDate::from_ymd_opt(2000, 1, 1)?;
+
+    let n = (date - epoch).num_days() as f64;
+    let mean_solar_time = n - longitude / 360.0;
+    let anomaly = (357.5291 + 0.985_600_28 * mean_solar_time)
+        .rem_euclid(360.0)
+        .to_radians();
+    let center =
+        1.9148 * anomaly.sin() + 0.02 * (2.0 * anomaly).sin() + 0.0003 * (3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly.to_degrees() + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit = J2000 + mean_solar_time + 0.0053 * anomaly.sin()
+        - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let cos_hour_angle = ((-0.833f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+    let hour_angle = cos_hour_angle.acos().to_degrees();
+
+    let to_utc = |julian: f64| {
+        DateTime::from_timestamp_millis(((julian - UNIX_EPOCH) * 86_400_000.0).round() as i64)
+    };
+    Some((
+        to_utc(transit - hour_angle / 360.0)?,
+        to_utc(transit + hour_angle / 360.0)?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        HolidayRule, ScheduleAction, ScheduleEntry, ScheduleSettings, ScheduleTime, sun_times,
+    };
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn entry(time: ScheduleTime, weekdays: Vec<Weekday>) -> ScheduleEntry {
+        ScheduleEntry {
+            name: String::new(),
+            time,
+            weekdays,
+            holidays: HolidayRule::Skip,
+            exceptions: vec![],
+            action: ScheduleAction::StopPlayback,
+        }
+    }
+
+    #[test]
+    fn sunrise_and_sunset() {
+        let (rise, set) = sun_times(date(2024, 6, 21), 52.52, 13.405).unwrap();
+        let expected_rise = Utc.with_ymd_and_hms(2024, 6, 21, 2, 43, 0).unwrap();
+        let expected_set = Utc.with_ymd_and_hms(2024, 6, 21, 19, 33, 0).unwrap();
+        assert!((rise - expected_rise).num_minutes().abs() <= 3, "{rise}");
+        assert!((set - expected_set).num_minutes().abs() <= 3, "{set}");
+
+        // No sunset at the north pole in summer
+        assert_eq!(sun_times(date(2024, 6, 21), 89.0, 0.0), None);
+    }
+
+    #[test]
+    fn due_entries() {
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let local = |d: NaiveDate, h: u32, m: u32| -> DateTime<FixedOffset> {
+            tz.from_local_datetime(&d.and_hms_opt(h, m, 0).unwrap())
+                .unwrap()
+        };
+        let eight = NaiveTime::from_hms_opt(8, 0, 0).unwrap();
+
+        let mut holiday_entry = entry(ScheduleTime::At(eight), vec![]);
+        holiday_entry.holidays = HolidayRule::Only;
+        let mut except_entry = entry(
+            ScheduleTime::Sunset {
+                offset_minutes: -30,
+            },
+            vec![],
+        );
+        except_entry.exceptions = vec![date(2025, 1, 7)];
+        except_entry.holidays = HolidayRule::Include;
+        let settings = ScheduleSettings {
+            enabled: true,
+            latitude: 52.52,
+            longitude: 13.405,
+            entries: vec![
+                entry(ScheduleTime::At(eight), vec![Weekday::Mon, Weekday::Tue]),
+                holiday_entry,
+                except_entry,
+            ],
+            holidays: vec![date(2025, 1, 6)],
+        };
+
+        // Monday 6th is a holiday, Tuesday the sunset entry is skipped
+        let monday = date(2025, 1, 6);
+        let tuesday = date(2025, 1, 7);
+        assert_eq!(
+            settings.due(&local(monday, 7, 0), &local(monday, 8, 0)),
+            [1]
+        );
+        assert_eq!(
+            settings.due(&local(tuesday, 7, 59), &local(tuesday, 8, 0)),
+            [0]
+        );
+        assert!(
+            settings
+                .due(&local(tuesday, 8, 0), &local(tuesday, 23, 0))
+                .is_empty()
+        );
+        // Sunset in Berlin is around 16:20 local time
+        assert_eq!(
+            settings.due(&local(monday, 12, 0), &local(tuesday, 8, 0)),
+            [2, 0]
+        );
+
+        let (time, i) = settings.next(&local(tuesday, 8, 0)).unwrap();
+        assert_eq!(i, 2);
+        assert_eq!(time.with_timezone(&tz).date_naive(), date(2025, 1, 8));
+    }
+}
@@ -0,0 +1,8 @@
+use serde::{Deserialize, Serialize};
+
+/// A Rhai macro run by the server, see the server documentation for the functions it can call
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Script {
+    pub name: String,
+    pub source: String,
+}
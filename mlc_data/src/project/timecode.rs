@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::project::audio::AudioInput;
+use crate::project::cue::CueListId;
+use crate::timecode::MSC_ALL_CALL;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TimecodeSettings {
+    /// Moves the chased cue list to the cue of the incoming timecode
+    pub chase: bool,
+    /// The chased cue list, the first one if `None`
+    pub cue_list: Option<CueListId>,
+    /// Added to the incoming timecode, may be negative
+    pub offset_ms: i64,
+    /// How long the clock keeps running after the timecode was lost
+    pub freewheel: Duration,
+    /// Executes MIDI show control commands addressed to the device id
+    pub msc: bool,
+    pub msc_device_id: u8,
+    /// Chases linear timecode from an audio input instead of MIDI timecode
+    #[serde(default)]
+    pub ltc: Option<AudioInput>,
+}
+
+impl Default for TimecodeSettings {
+    fn default() -> Self {
+        Self {
+            chase: false,
+            cue_list: None,
+            offset_ms: 0,
+            freewheel: Duration::from_secs(2),
+            msc: false,
+            msc_device_id: MSC_ALL_CALL,
+            ltc: None,
+        }
+    }
+}
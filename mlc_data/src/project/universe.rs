@@ -8,7 +8,7 @@ use crate::{
         bounds::{DynamicUSize, One},
         BoundedValue,
     },
-    fixture::patched::{PatchedFixture, PatchedFixtureId},
+    fixture::patched::{attribute::FixtureAttribute, PatchedFixture, PatchedFixtureId},
 };
 
 pub const UNIVERSE_SIZE: usize = 512;
@@ -16,6 +16,10 @@ pub const UNIVERSE_SIZE: usize = 512;
 #[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FixtureUniverse {
+    #[serde(default)]
+    pub id: UniverseId,
+    #[serde(default)]
+    pub name: String,
     #[serde_as(as = "[_; UNIVERSE_SIZE]")]
     pub addresses: [UniverseSlot; UNIVERSE_SIZE],
     pub fixtures: HashMap<PatchedFixtureId, PatchedFixture>,
@@ -28,7 +32,50 @@ pub enum UniverseSlot {
     Fixture(PatchedFixtureId),
 }
 
+impl FixtureUniverse {
+    pub fn new(id: UniverseId, name: String) -> Self {
+        Self {
+            id,
+            name,
+            addresses: [UniverseSlot::Unused; UNIVERSE_SIZE],
+            fixtures: HashMap::new(),
+        }
+    }
+
+    pub fn fixture_address(&self, id: &PatchedFixtureId) -> Option<UniverseAddress> {
+        self.addresses
+            .iter()
+            .position(|s| *s == UniverseSlot::Fixture(*id))
+            .map(|i| UniverseAddress::create(i + 1))
+    }
+
+    /// Converts the attribute values of all fixtures patched in this universe to dmx values.
+    /// Fixtures that are not part of this universe are ignored.
+    pub fn render_attributes<'a>(
+        &self,
+        values: impl IntoIterator<Item = (&'a PatchedFixtureId, &'a [FixtureAttribute])>,
+    ) -> Vec<(UniverseAddress, u8)> {
+        let mut out = vec![];
+        for (id, attributes) in values {
+            let (Some(fixture), Some(start)) = (self.fixtures.get(id), self.fixture_address(id))
+            else {
+                continue;
+            };
+
+            for (offset, value) in fixture.render_attributes(attributes) {
+                let index = start.take() + offset;
+                if index <= UNIVERSE_SIZE {
+                    out.push((UniverseAddress::create(index), value));
+                }
+            }
+        }
+        out
+    }
+}
+
 pub type UniverseId = u16;
+/// A channel frozen at a fixed value, overriding everything else on the output
+pub type ParkedChannel = (FixtureAddress, u8);
 pub type UniverseAddress = BoundedValue<usize, One, DynamicUSize<{ UNIVERSE_SIZE }>>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
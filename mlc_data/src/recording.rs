@@ -0,0 +1,245 @@
+//! A compact file format for recorded dmx output.
+//!
+//! After the header the file is a sequence of frames, each frame holds the time since the start
+//! of the recording and only the channels that changed since the previous frame:
+//! `time_ms: u32, count: u16, count * (universe: u16, channel: u16, value: u8)`, all little endian.
+
+use crate::project::universe::{UNIVERSE_SIZE, UniverseId};
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+pub const RECORDING_EXTENSION: &str = "mlcrec";
+
+const MAGIC: &[u8; 4] = b"MLCR";
+const VERSION: u8 = 1;
+
+pub type UniverseValues = BTreeMap<UniverseId, [u8; UNIVERSE_SIZE]>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedFrame {
+    pub time: Duration,
+    /// Universe, (0 based) channel index and new value
+    pub changes: Vec<(UniverseId, u16, u8)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DmxRecording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl DmxRecording {
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(invalid("Not a dmx recording"));
+        }
+        if header[4] != VERSION {
+            return Err(invalid("Unsupported recording version"));
+        }
+
+        let mut frames = vec![];
+        let mut frame_header = [0; 6];
+        loop {
+            match reader.read_exact(&mut frame_header) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e),
+            }
+            let time = u32::from_le_bytes([
+                frame_header[0],
+                frame_header[1],
+                frame_header[2],
+                frame_header[3],
+            ]);
+            let count = u16::from_le_bytes([frame_header[4], frame_header[5]]) as usize;
+
+            let mut data = vec![0; count * 5];
+            reader.read_exact(&mut data)?;
+            let changes = data
+                .chunks_exact(5)
+                .map(|c| {
+                    let channel = u16::from_le_bytes([c[2], c[3]]);
+                    if channel as usize >= UNIVERSE_SIZE {
+                        return Err(invalid("Channel out of range"));
+                    }
+                    Ok((u16::from_le_bytes([c[0], c[1]]), channel, c[4]))
+                })
+                .collect::<io::Result<_>>()?;
+
+            frames.push(RecordedFrame {
+                time: Duration::from_millis(time as u64),
+                changes,
+            });
+        }
+
+        Ok(Self { frames })
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map(|f| f.time).unwrap_or_default()
+    }
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Writes frames as diffs against the previously written state
+pub struct RecordingWriter<W: Write> {
+    writer: W,
+    state: UniverseValues,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self {
+            writer,
+            state: BTreeMap::new(),
+        })
+    }
+
+    /// Writes the channels that differ from the last frame, nothing if all are unchanged.
+    /// Universes are assumed to start at zero.
+    pub fn write_frame(&mut self, time: Duration, universes: &UniverseValues) -> io::Result<()> {
+        let mut changes = vec![];
+        for (universe, values) in universes {
+            let last = self.state.entry(*universe).or_insert([0; UNIVERSE_SIZE]);
+            for (i, (old, new)) in last.iter_mut().zip(values).enumerate() {
+                if old != new {
+                    *old = *new;
+                    changes.push((*universe, i as u16, *new));
+                }
+            }
+        }
+
+        for chunk in changes.chunks(u16::MAX as usize) {
+            let time = time.as_millis().min(u32::MAX as u128) as u32;
+            self.writer.write_all(&time.to_le_bytes())?;
+            self.writer.write_all(&(chunk.len() as u16).to_le_bytes())?;
+            for (universe, channel, value) in chunk {
+                self.writer.write_all(&universe.to_le_bytes())?;
+                self.writer.write_all(&channel.to_le_bytes())?;
+                self.writer.write_all(&[*value])?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Replays a recording by applying its frames up to a playback position
+#[derive(Debug, Clone)]
+pub struct RecordingPlayer {
+    recording: DmxRecording,
+    state: UniverseValues,
+    next_frame: usize,
+}
+
+impl RecordingPlayer {
+    pub fn new(recording: DmxRecording) -> Self {
+        Self {
+            recording,
+            state: BTreeMap::new(),
+            next_frame: 0,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.recording.duration()
+    }
+
+    pub fn finished(&self) -> bool {
+        self.next_frame >= self.recording.frames.len()
+    }
+
+    /// Starts over from the beginning with all channels at zero
+    pub fn rewind(&mut self) {
+        self.state.clear();
+        self.next_frame = 0;
+    }
+
+    /// Applies all frames up to `position`, returns whether any channel changed.
+    /// Positions before the last one rewind first.
+    pub fn advance_to(&mut self, position: Duration) -> bool {
+        if self.next_frame > 0 && self.recording.frames[self.next_frame - 1].time > position {
+            self.rewind();
+        }
+
+        let mut changed = false;
+        while let Some(frame) = self.recording.frames.get(self.next_frame) {
+            if frame.time > position {
+                break;
+            }
+            for (universe, channel, value) in &frame.changes {
+                self.state.entry(*universe).or_insert([0; UNIVERSE_SIZE])[*channel as usize] =
+                    *value;
+            }
+            changed = true;
+            self.next_frame += 1;
+        }
+        changed
+    }
+
+    pub fn values(&self) -> &UniverseValues {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DmxRecording, RecordingPlayer, RecordingWriter, UniverseValues};
+    use crate::project::universe::UNIVERSE_SIZE;
+    use std::time::Duration;
+
+    #[test]
+    fn record_and_play() {
+        let mut universes = UniverseValues::new();
+        universes.insert(1, [0; UNIVERSE_SIZE]);
+        universes.insert(2, [0; UNIVERSE_SIZE]);
+
+        let mut writer = RecordingWriter::new(vec![]).unwrap();
+        universes.get_mut(&1).unwrap()[0] = 255;
+        writer.write_frame(Duration::ZERO, &universes).unwrap();
+        // Unchanged frames are skipped
+        writer
+            .write_frame(Duration::from_millis(20), &universes)
+            .unwrap();
+        universes.get_mut(&1).unwrap()[0] = 0;
+        universes.get_mut(&2).unwrap()[511] = 42;
+        writer
+            .write_frame(Duration::from_millis(40), &universes)
+            .unwrap();
+        let data = writer.finish().unwrap();
+
+        assert_eq!(data.len(), 5 + (6 + 5) + (6 + 2 * 5));
+
+        let recording = DmxRecording::read(data.as_slice()).unwrap();
+        assert_eq!(recording.frames.len(), 2);
+        assert_eq!(recording.duration(), Duration::from_millis(40));
+
+        let mut player = RecordingPlayer::new(recording);
+        assert!(player.advance_to(Duration::from_millis(30)));
+        assert_eq!(player.values()[&1][0], 255);
+        assert!(!player.values().contains_key(&2));
+        assert!(!player.advance_to(Duration::from_millis(35)));
+
+        assert!(player.advance_to(Duration::from_millis(40)));
+        assert!(player.finished());
+        assert_eq!(player.values()[&1][0], 0);
+        assert_eq!(player.values()[&2][511], 42);
+
+        assert!(player.advance_to(Duration::from_millis(10)));
+        assert_eq!(player.values()[&1][0], 255);
+        assert!(!player.values().contains_key(&2));
+
+        assert!(DmxRecording::read(&b"MLCX\x01"[..]).is_err());
+    }
+}
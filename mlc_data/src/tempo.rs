@@ -0,0 +1,142 @@
+//! Tap tempo and the beat clock cue lists can step to.
+
+use std::collections::VecDeque;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+pub const BPM_RANGE: RangeInclusive<f32> = 20.0..=300.0;
+/// Multipliers are powers of two in this range
+pub const MULTIPLIER_RANGE: RangeInclusive<f32> = 0.25..=4.0;
+/// Taps further apart start a new measurement
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Taps averaged for the tempo
+const TAP_COUNT: usize = 8;
+
+#[derive(Debug, Clone, Default)]
+pub struct TapTempo {
+    taps: VecDeque<Instant>,
+}
+
+impl TapTempo {
+    /// Registers a tap, the tempo is known from the second tap on
+    pub fn tap(&mut self, now: Instant) -> Option<f32> {
+        if self
+            .taps
+            .back()
+            .is_some_and(|last| now.saturating_duration_since(*last) > TAP_TIMEOUT)
+        {
+            self.taps.clear();
+        }
+        self.taps.push_back(now);
+        if self.taps.len() > TAP_COUNT {
+            self.taps.pop_front();
+        }
+
+        let (first, last) = (self.taps.front()?, self.taps.back()?);
+        let span = last.saturating_duration_since(*first).as_secs_f32();
+        (span > 0.0).then(|| {
+            (60.0 * (self.taps.len() - 1) as f32 / span).clamp(*BPM_RANGE.start(), *BPM_RANGE.end())
+        })
+    }
+}
+
+/// Counts beats at the tempo times the multiplier, changes keep the phase continuous
+#[derive(Debug, Clone, Copy)]
+pub struct BeatClock {
+    bpm: f32,
+    multiplier: f32,
+    origin: Instant,
+    beats_at_origin: f64,
+}
+
+impl BeatClock {
+    pub fn new(bpm: f32, now: Instant) -> Self {
+        Self {
+            bpm: bpm.clamp(*BPM_RANGE.start(), *BPM_RANGE.end()),
+            multiplier: 1.0,
+            origin: now,
+            beats_at_origin: 0.0,
+        }
+    }
+
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    pub fn multiplier(&self) -> f32 {
+        self.multiplier
+    }
+
+    /// Beats since the clock was created
+    pub fn beats(&self, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.origin).as_secs_f64();
+        self.beats_at_origin + elapsed * (self.bpm * self.multiplier) as f64 / 60.0
+    }
+
+    /// Position in the current beat from 0 to 1
+    pub fn phase(&self, now: Instant) -> f32 {
+        self.beats(now).fract() as f32
+    }
+
+    pub fn set_bpm(&mut self, bpm: f32, now: Instant) {
+        self.rebase(now);
+        self.bpm = bpm.clamp(*BPM_RANGE.start(), *BPM_RANGE.end());
+    }
+
+    /// Rounds to the nearest power of two
+    pub fn set_multiplier(&mut self, multiplier: f32, now: Instant) {
+        self.rebase(now);
+        let exponent = multiplier.log2().round();
+        self.multiplier = if exponent.is_nan() {
+            1.0
+        } else {
+            exponent
+                .exp2()
+                .clamp(*MULTIPLIER_RANGE.start(), *MULTIPLIER_RANGE.end())
+        };
+    }
+
+    /// Starts the next beat now
+    pub fn reset_phase(&mut self, now: Instant) {
+        self.beats_at_origin = self.beats(now).ceil();
+        self.origin = now;
+    }
+
+    fn rebase(&mut self, now: Instant) {
+        self.beats_at_origin = self.beats(now);
+        self.origin = now;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BeatClock, TapTempo};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn tap_and_count_beats() {
+        let start = Instant::now();
+        let ms = |ms: u64| start + Duration::from_millis(ms);
+
+        let mut taps = TapTempo::default();
+        assert_eq!(taps.tap(ms(0)), None);
+        assert_eq!(taps.tap(ms(500)), Some(120.0));
+        assert_eq!(taps.tap(ms(1100)), Some(60.0 * 2.0 / 1.1));
+        // A pause starts over
+        assert_eq!(taps.tap(ms(5000)), None);
+
+        let mut clock = BeatClock::new(120.0, start);
+        assert_eq!(clock.beats(ms(1250)), 2.5);
+
+        // Tempo changes continue from the current phase
+        clock.set_bpm(60.0, ms(1250));
+        assert_eq!(clock.beats(ms(1750)), 3.0);
+        clock.set_multiplier(3.0, ms(1750));
+        assert_eq!(clock.multiplier(), 4.0);
+        assert_eq!(clock.beats(ms(1875)), 3.5);
+
+        clock.reset_phase(ms(1900));
+        assert_eq!(clock.beats(ms(1900)), 4.0);
+        assert_eq!(clock.phase(ms(1900)), 0.0);
+    }
+}
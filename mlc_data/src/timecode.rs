@@ -0,0 +1,429 @@
+//! SMPTE timecode, its transport as MIDI timecode (MTC) and MIDI show control (MSC) commands.
+
+use crate::midi::MidiMessage;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+pub mod ltc;
+
+/// Timecode without new frames for this long is considered lost
+pub const SIGNAL_TIMEOUT: Duration = Duration::from_millis(200);
+/// Larger differences to the smoothed position are a relocation and not smoothed
+const JUMP_THRESHOLD: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrameRate {
+    Fps24,
+    Fps25,
+    /// 29.97 fps drop frame
+    Fps30Drop,
+    Fps30,
+}
+
+impl FrameRate {
+    /// Nominal frames per second
+    pub fn fps(&self) -> u64 {
+        match self {
+            FrameRate::Fps24 => 24,
+            FrameRate::Fps25 => 25,
+            FrameRate::Fps30Drop | FrameRate::Fps30 => 30,
+        }
+    }
+
+    /// The rate as encoded in the hours of MTC
+    fn from_mtc(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => FrameRate::Fps24,
+            1 => FrameRate::Fps25,
+            2 => FrameRate::Fps30Drop,
+            _ => FrameRate::Fps30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timecode {
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+    pub frames: u8,
+    pub rate: FrameRate,
+}
+
+const DROP_FRAMES_PER_MINUTE: u64 = 30 * 60 - 2;
+const DROP_FRAMES_PER_10_MINUTES: u64 = DROP_FRAMES_PER_MINUTE * 10 + 2;
+
+impl Timecode {
+    /// Number of frames since 00:00:00:00
+    pub fn frame_count(&self) -> u64 {
+        let fps = self.rate.fps();
+        let minutes = self.hours as u64 * 60 + self.minutes as u64;
+        let count = (minutes * 60 + self.seconds as u64) * fps + self.frames as u64;
+        if self.rate == FrameRate::Fps30Drop {
+            // Frames 0 and 1 are skipped every minute, except every tenth
+            count - 2 * (minutes - minutes / 10)
+        } else {
+            count
+        }
+    }
+
+    pub fn from_frame_count(count: u64, rate: FrameRate) -> Self {
+        let fps = rate.fps();
+        let count = if rate == FrameRate::Fps30Drop {
+            let tens = count / DROP_FRAMES_PER_10_MINUTES;
+            let rest = count % DROP_FRAMES_PER_10_MINUTES;
+            let skipped = if rest < 2 {
+                18 * tens
+            } else {
+                18 * tens + 2 * ((rest - 2) / DROP_FRAMES_PER_MINUTE)
+            };
+            count + skipped
+        } else {
+            count
+        };
+
+        let seconds = count / fps;
+        Self {
+            hours: (seconds / 3600 % 24) as u8,
+            minutes: (seconds / 60 % 60) as u8,
+            seconds: (seconds % 60) as u8,
+            frames: (count % fps) as u8,
+            rate,
+        }
+    }
+
+    pub fn add_frames(&self, frames: u64) -> Self {
+        Self::from_frame_count(self.frame_count() + frames, self.rate)
+    }
+
+    pub fn to_duration(&self) -> Duration {
+        match self.rate {
+            FrameRate::Fps30Drop => {
+                Duration::from_nanos(self.frame_count() * 1_001_000_000_000 / 30_000)
+            }
+            rate => Duration::from_nanos(self.frame_count() * 1_000_000_000 / rate.fps()),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.hours < 24
+            && self.minutes < 60
+            && self.seconds < 60
+            && (self.frames as u64) < self.rate.fps()
+    }
+}
+
+impl Display for Timecode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let separator = if self.rate == FrameRate::Fps30Drop {
+            ';'
+        } else {
+            ':'
+        };
+        write!(
+            f,
+            "{:02}:{:02}:{:02}{separator}{:02}",
+            self.hours, self.minutes, self.seconds, self.frames
+        )
+    }
+}
+
+/// Assembles timecode from quarter frames and full frame messages
+#[derive(Debug, Default)]
+pub struct MtcDecoder {
+    pieces: [u8; 8],
+    received: u8,
+}
+
+impl MtcDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, message: &MidiMessage) -> Option<Timecode> {
+        match message {
+            MidiMessage::QuarterFrame(data) => self.quarter_frame(*data),
+            MidiMessage::SysEx(data) => self.full_frame(data),
+            _ => None,
+        }
+    }
+
+    fn quarter_frame(&mut self, data: u8) -> Option<Timecode> {
+        let piece = (data >> 4) as usize & 0b111;
+        if piece == 0 {
+            self.received = 0;
+        } else if self.received != (1 << piece) - 1 {
+            // Pieces have to arrive in order, otherwise wait for the next frame
+            self.received = 0;
+            return None;
+        }
+        self.pieces[piece] = data & 0x0F;
+        self.received |= 1 << piece;
+
+        if piece != 7 {
+            return None;
+        }
+        let p = self.pieces;
+        let timecode = Timecode {
+            hours: p[6] | (p[7] & 1) << 4,
+            minutes: p[4] | p[5] << 4,
+            seconds: p[2] | p[3] << 4,
+            frames: p[0] | p[1] << 4,
+            rate: FrameRate::from_mtc(p[7] >> 1),
+        };
+        // The eight quarter frames span two frames and describe the time of the first one
+        timecode.is_valid().then(|| timecode.add_frames(2))
+    }
+
+    fn full_frame(&mut self, data: &[u8]) -> Option<Timecode> {
+        let [0x7F, _, 0x01, 0x01, hours, minutes, seconds, frames] = *data else {
+            return None;
+        };
+        self.received = 0;
+
+        let timecode = Timecode {
+            hours: hours & 0x1F,
+            minutes,
+            seconds,
+            frames,
+            rate: FrameRate::from_mtc(hours >> 5),
+        };
+        timecode.is_valid().then_some(timecode)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MscCommand {
+    Go,
+    Stop,
+    Resume,
+    Other(u8),
+}
+
+/// A MIDI show control command, cue numbers and lists are kept as sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MscMessage {
+    pub device_id: u8,
+    pub command: MscCommand,
+    pub cue: Option<String>,
+    pub list: Option<String>,
+}
+
+/// Device id addressing all devices
+pub const MSC_ALL_CALL: u8 = 0x7F;
+
+impl MscMessage {
+    pub fn parse(sysex: &[u8]) -> Option<Self> {
+        let [0x7F, device_id, 0x02, _format, command, ref data @ ..] = *sysex else {
+            return None;
+        };
+
+        let command = match command {
+            0x01 => MscCommand::Go,
+            0x02 => MscCommand::Stop,
+            0x03 => MscCommand::Resume,
+            c => MscCommand::Other(c),
+        };
+        let mut fields = data
+            .split(|b| *b == 0)
+            .map(|f| (!f.is_empty()).then(|| String::from_utf8_lossy(f).to_string()));
+
+        Some(Self {
+            device_id,
+            command,
+            cue: fields.next().flatten(),
+            list: fields.next().flatten(),
+        })
+    }
+
+    pub fn addresses(&self, device_id: u8) -> bool {
+        self.device_id == device_id || self.device_id == MSC_ALL_CALL || device_id == MSC_ALL_CALL
+    }
+}
+
+/// Follows incoming timecode and keeps running for the freewheel time when it is lost
+#[derive(Debug, Clone)]
+pub struct TimecodeChase {
+    offset_ms: i64,
+    freewheel: Duration,
+    last: Option<(Timecode, Duration, Instant)>,
+}
+
+impl TimecodeChase {
+    pub fn new(offset_ms: i64, freewheel: Duration) -> Self {
+        Self {
+            offset_ms,
+            freewheel,
+            last: None,
+        }
+    }
+
+    /// Locks to the timecode whose position, e.g. after smoothing, is given at the instant
+    pub fn sync(&mut self, timecode: Timecode, position: Duration, at: Instant) {
+        self.last = Some((timecode, position, at));
+    }
+
+    pub fn timecode(&self) -> Option<Timecode> {
+        self.last.map(|(t, _, _)| t)
+    }
+
+    /// The chased position including the offset, `None` without signal after freewheeling
+    pub fn position(&self, now: Instant) -> Option<Duration> {
+        let (_, position, at) = self.last?;
+        let elapsed = now.saturating_duration_since(at);
+        if elapsed > SIGNAL_TIMEOUT + self.freewheel {
+            return None;
+        }
+
+        let position = (position + elapsed).as_millis() as i64 + self.offset_ms;
+        Some(Duration::from_millis(position.max(0) as u64))
+    }
+
+    pub fn freewheeling(&self, now: Instant) -> bool {
+        self.position(now).is_some()
+            && self
+                .last
+                .is_some_and(|(_, _, at)| now.saturating_duration_since(at) > SIGNAL_TIMEOUT)
+    }
+}
+
+/// Smooths the jitter of a timecode position against a steady local clock
+#[derive(Debug, Clone, Default)]
+pub struct JitterFilter {
+    offset: Option<f64>,
+}
+
+impl JitterFilter {
+    /// The smoothed position for a received `position` at the `local` time
+    pub fn update(&mut self, position: Duration, local: Duration) -> Duration {
+        let offset = position.as_secs_f64() - local.as_secs_f64();
+        match self.offset {
+            Some(smoothed) if (offset - smoothed).abs() < JUMP_THRESHOLD => {
+                let smoothed = smoothed + (offset - smoothed) * 0.1;
+                self.offset = Some(smoothed);
+                Duration::from_secs_f64((local.as_secs_f64() + smoothed).max(0.0))
+            }
+            _ => {
+                self.offset = Some(offset);
+                position
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FrameRate, MscCommand, MscMessage, MtcDecoder, Timecode, TimecodeChase};
+    use crate::midi::MidiParser;
+    use std::time::{Duration, Instant};
+
+    fn tc(hours: u8, minutes: u8, seconds: u8, frames: u8, rate: FrameRate) -> Timecode {
+        Timecode {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            rate,
+        }
+    }
+
+    #[test]
+    fn drop_frame_counting() {
+        let before = tc(0, 0, 59, 29, FrameRate::Fps30Drop);
+        assert_eq!(before.add_frames(1), tc(0, 1, 0, 2, FrameRate::Fps30Drop));
+        assert_eq!(
+            tc(0, 9, 59, 29, FrameRate::Fps30Drop).add_frames(1),
+            tc(0, 10, 0, 0, FrameRate::Fps30Drop)
+        );
+        assert_eq!(tc(1, 0, 0, 0, FrameRate::Fps30Drop).frame_count(), 107_892);
+        assert_eq!(
+            tc(1, 0, 0, 0, FrameRate::Fps30Drop).to_duration(),
+            Duration::from_nanos(3_599_996_400_000)
+        );
+        assert_eq!(
+            tc(0, 0, 1, 12, FrameRate::Fps25).to_duration(),
+            Duration::from_millis(1480)
+        );
+        assert_eq!(
+            tc(1, 2, 3, 4, FrameRate::Fps30Drop).to_string(),
+            "01:02:03;04"
+        );
+    }
+
+    #[test]
+    fn decode_mtc_stream() {
+        // 01:02:03:04 at 25 fps as quarter frames, with a clock byte in between
+        let stream = [
+            0xF1, 0x04, 0xF1, 0x10, 0xF1, 0x23, 0xF8, 0xF1, 0x30, 0xF1, 0x42, 0xF1, 0x50, 0xF1,
+            0x61, 0xF1, 0x72, // Full frame locating to 10:00:00:00 at 30 fps
+            0xF0, 0x7F, 0x7F, 0x01, 0x01, 0x6A, 0x00, 0x00, 0x00, 0xF7,
+            // An incomplete frame is dropped
+            0xF1, 0x00, 0xF1, 0x10, 0xF1, 0x72,
+        ];
+
+        let mut parser = MidiParser::new();
+        let mut decoder = MtcDecoder::new();
+        let decoded = parser
+            .parse(&stream)
+            .iter()
+            .filter_map(|m| decoder.push(m))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            decoded,
+            vec![
+                tc(1, 2, 3, 6, FrameRate::Fps25),
+                tc(10, 0, 0, 0, FrameRate::Fps30)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_msc() {
+        // GO cue 5 in list 2, STOP for all devices, RESUME without cue
+        let stream = [
+            0xF0, 0x7F, 0x01, 0x02, 0x01, 0x01, b'5', 0x00, b'2', 0xF7, 0xF0, 0x7F, 0x7F, 0x02,
+            0x01, 0x02, 0xF7, 0xF0, 0x7F, 0x01, 0x02, 0x01, 0x03, 0xF7,
+        ];
+
+        let messages = MidiParser::new()
+            .parse(&stream)
+            .into_iter()
+            .filter_map(|m| match m {
+                crate::midi::MidiMessage::SysEx(data) => MscMessage::parse(&data),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].command, MscCommand::Go);
+        assert_eq!(messages[0].cue.as_deref(), Some("5"));
+        assert_eq!(messages[0].list.as_deref(), Some("2"));
+        assert!(messages[0].addresses(1));
+        assert!(!messages[0].addresses(2));
+        assert_eq!(messages[1].command, MscCommand::Stop);
+        assert!(messages[1].addresses(2));
+        assert_eq!(messages[2].command, MscCommand::Resume);
+        assert_eq!(messages[2].cue, None);
+    }
+
+    #[test]
+    fn chase_freewheels() {
+        let start = Instant::now();
+        let mut chase = TimecodeChase::new(-500, Duration::from_secs(1));
+        assert_eq!(chase.position(start), None);
+
+        let timecode = tc(0, 0, 10, 0, FrameRate::Fps25);
+        chase.sync(timecode, timecode.to_duration(), start);
+        assert_eq!(chase.position(start), Some(Duration::from_millis(9500)));
+        assert!(!chase.freewheeling(start));
+
+        let later = start + Duration::from_millis(800);
+        assert_eq!(chase.position(later), Some(Duration::from_millis(10_300)));
+        assert!(chase.freewheeling(later));
+
+        assert_eq!(chase.position(start + Duration::from_secs(2)), None);
+        assert!(!chase.freewheeling(start + Duration::from_secs(2)));
+    }
+}
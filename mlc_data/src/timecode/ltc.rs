@@ -0,0 +1,273 @@
+//! Decoding of SMPTE linear timecode from audio samples.
+//!
+//! Each frame is 80 bits in biphase mark code: every bit starts with a level change and ones
+//! change the level again in the middle of the bit. The frame ends with a fixed sync word.
+
+use crate::timecode::{FrameRate, Timecode};
+
+const FRAME_BITS: u32 = 80;
+/// Bits 64 to 79, least significant bit first
+const SYNC_WORD: u16 = 0xBFFC;
+/// Levels closer to zero don't change the signal state
+const HYSTERESIS: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LtcFrame {
+    /// The timecode of the frame starting after the decoded one
+    pub timecode: Timecode,
+    /// Sample at which the frame ended
+    pub sample: u64,
+}
+
+pub struct LtcDecoder {
+    sample_rate: u32,
+    sample: u64,
+    high: bool,
+    since_transition: u32,
+    /// Estimated samples per bit
+    bit_period: f32,
+    half_bit: bool,
+    bits: u128,
+    /// Bits received since the last error
+    received: u32,
+}
+
+impl LtcDecoder {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            sample: 0,
+            high: false,
+            since_transition: 0,
+            // Between 24 and 30 fps, adapted to the signal
+            bit_period: sample_rate as f32 / (27.0 * FRAME_BITS as f32),
+            half_bit: false,
+            bits: 0,
+            received: 0,
+        }
+    }
+
+    /// Samples processed so far
+    pub fn position(&self) -> u64 {
+        self.sample
+    }
+
+    pub fn push(&mut self, samples: &[f32]) -> Vec<LtcFrame> {
+        let mut frames = vec![];
+        for sample in samples {
+            self.sample += 1;
+            self.since_transition += 1;
+
+            let high = if *sample > HYSTERESIS {
+                true
+            } else if *sample < -HYSTERESIS {
+                false
+            } else {
+                self.high
+            };
+            if high == self.high {
+                continue;
+            }
+            self.high = high;
+
+            let interval = self.since_transition as f32;
+            self.since_transition = 0;
+            if let Some(frame) = self.transition(interval) {
+                frames.push(frame);
+            }
+        }
+        frames
+    }
+
+    fn transition(&mut self, interval: f32) -> Option<LtcFrame> {
+        if interval > self.bit_period * 2.0 || interval < self.bit_period * 0.25 {
+            // A gap or noise, start over
+            self.reset();
+            if interval > self.bit_period * 2.0 && interval < self.bit_period * 2.5 {
+                self.bit_period = interval / 2.0;
+            }
+            return None;
+        }
+
+        let bit = if interval > self.bit_period * 0.75 {
+            if self.half_bit {
+                self.reset();
+                return None;
+            }
+            self.adapt(interval);
+            false
+        } else if self.half_bit {
+            self.half_bit = false;
+            self.adapt(interval * 2.0);
+            true
+        } else {
+            self.half_bit = true;
+            return None;
+        };
+
+        self.bits = (self.bits >> 1) | ((bit as u128) << (FRAME_BITS - 1));
+        self.received += 1;
+        if self.received < FRAME_BITS || (self.bits >> 64) as u16 != SYNC_WORD {
+            return None;
+        }
+
+        let timecode = self.decode_frame()?;
+        self.bits = 0;
+        self.received = 0;
+        Some(LtcFrame {
+            timecode: timecode.add_frames(1),
+            sample: self.sample,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.half_bit = false;
+        self.bits = 0;
+        self.received = 0;
+    }
+
+    fn adapt(&mut self, period: f32) {
+        self.bit_period += (period - self.bit_period) * 0.1;
+    }
+
+    fn decode_frame(&self) -> Option<Timecode> {
+        let field = |start: u32, len: u32| ((self.bits >> start) & ((1 << len) - 1)) as u8;
+        let drop_frame = field(10, 1) == 1;
+
+        let fps = self.sample_rate as f32 / (self.bit_period * FRAME_BITS as f32);
+        let rate = if drop_frame {
+            FrameRate::Fps30Drop
+        } else if fps < 24.5 {
+            FrameRate::Fps24
+        } else if fps < 27.5 {
+            FrameRate::Fps25
+        } else {
+            FrameRate::Fps30
+        };
+
+        let timecode = Timecode {
+            hours: field(48, 4) + field(56, 2) * 10,
+            minutes: field(32, 4) + field(40, 3) * 10,
+            seconds: field(16, 4) + field(24, 3) * 10,
+            frames: field(0, 4) + field(8, 2) * 10,
+            rate,
+        };
+        timecode.is_valid().then_some(timecode)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FRAME_BITS, LtcDecoder, SYNC_WORD};
+    use crate::audio::{AudioSource, WavReader, write_wav};
+    use crate::timecode::{FrameRate, JitterFilter, Timecode};
+    use std::time::Duration;
+
+    fn frame_bits(timecode: &Timecode) -> u128 {
+        let bcd = |value: u8, start: u32, tens_bits: u32| {
+            (value as u128 % 10) << start
+                | ((value as u128 / 10) & ((1 << tens_bits) - 1)) << (start + 8)
+        };
+        bcd(timecode.frames, 0, 2)
+            | ((timecode.rate == FrameRate::Fps30Drop) as u128) << 10
+            | bcd(timecode.seconds, 16, 3)
+            | bcd(timecode.minutes, 32, 3)
+            | bcd(timecode.hours, 48, 2)
+            | (SYNC_WORD as u128) << 64
+    }
+
+    /// Encodes frames in biphase mark code with a little deterministic noise
+    fn encode(start: Timecode, frames: u64, fps: f64, sample_rate: u32) -> Vec<f32> {
+        let bit_samples = sample_rate as f64 / (fps * FRAME_BITS as f64);
+        let mut samples = vec![];
+        let mut level = 0.5;
+        let mut time = 0.0;
+        let mut noise = 12345u32;
+
+        let mut push_until = |samples: &mut Vec<f32>, end: f64, level: f32| {
+            while (samples.len() as f64) < end {
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                samples.push(level + (noise >> 16) as f32 / 65536.0 * 0.1 - 0.05);
+            }
+        };
+
+        for i in 0..frames {
+            let bits = frame_bits(&start.add_frames(i));
+            for bit in 0..FRAME_BITS {
+                level = -level;
+                if bits >> bit & 1 == 1 {
+                    push_until(&mut samples, time + bit_samples / 2.0, level);
+                    level = -level;
+                }
+                time += bit_samples;
+                push_until(&mut samples, time, level);
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn decode_wav() {
+        let start = Timecode {
+            hours: 1,
+            minutes: 0,
+            seconds: 59,
+            frames: 27,
+            rate: FrameRate::Fps30Drop,
+        };
+        let mut samples = vec![0.0; 100];
+        samples.extend(encode(start, 6, 29.97, 48_000));
+        let mut wav = vec![];
+        write_wav(&mut wav, 48_000, &samples).unwrap();
+
+        let mut reader = WavReader::new(wav.as_slice()).unwrap();
+        let mut decoder = LtcDecoder::new(reader.sample_rate());
+        let mut buf = [0.0; 480];
+        let mut frames = vec![];
+        while let Ok(n @ 1..) = reader.read(&mut buf) {
+            frames.extend(decoder.push(&buf[..n]));
+        }
+
+        let timecodes = frames
+            .iter()
+            .map(|f| f.timecode.to_string())
+            .collect::<Vec<_>>();
+        // The first frame is needed to lock onto the signal, the last one ends with the next
+        assert_eq!(
+            timecodes,
+            ["01:00:59;29", "01:01:00;02", "01:01:00;03", "01:01:00;04"]
+        );
+
+        let start = Timecode {
+            hours: 10,
+            minutes: 20,
+            seconds: 30,
+            frames: 24,
+            rate: FrameRate::Fps25,
+        };
+        let samples = encode(start, 3, 25.0, 44_100)
+            .into_iter()
+            .map(|s| -s)
+            .collect::<Vec<_>>();
+        let frames = LtcDecoder::new(44_100).push(&samples);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].timecode.to_string(), "10:20:31:00");
+    }
+
+    #[test]
+    fn smooth_jitter() {
+        let mut filter = JitterFilter::default();
+        let frame = Duration::from_millis(40);
+        for i in 0..50u32 {
+            let jitter = if i % 2 == 0 { 8 } else { 0 };
+            filter.update(frame * i + Duration::from_millis(jitter), frame * i);
+        }
+        let position = filter.update(frame * 50, frame * 50);
+        let error = position.as_secs_f64() - (frame * 50).as_secs_f64();
+        assert!((0.0..0.006).contains(&error), "{error}");
+
+        // Jumps are followed immediately
+        let located = filter.update(Duration::from_secs(60), frame * 51);
+        assert_eq!(located, Duration::from_secs(60));
+    }
+}
@@ -1,15 +1,21 @@
-use crate::screens::{BLUEPRINTS_CHANGED, SETTINGS_CHANGED, UNIVERSE_LIST_CHANGED};
+use crate::screens::{
+    BLUEPRINTS_CHANGED, CUE_LISTS_CHANGED, ENDPOINTS_CHANGED, GROUPS_CHANGED, PARKED_CHANGED,
+    SETTINGS_CHANGED, UNIVERSE_LIST_CHANGED,
+};
 use crate::utils::{navigate, Branding, IconButton, Screen};
 use connect::{connect_url, use_service};
 use dioxus::desktop::{LogicalSize, WindowBuilder};
 use dioxus::prelude::*;
 use dioxus::{desktop::Config, logger::tracing::error};
 use dioxus_free_icons::icons::ld_icons::{
-    LdCloudUpload, LdCog, LdLightbulb, LdPencil, LdSave, LdTabletSmartphone,
+    LdActivity, LdCloudUpload, LdCog, LdLightbulb, LdPencil, LdPower, LdRedo2, LdSave,
+    LdTabletSmartphone, LdUndo2,
 };
 use log::{info, warn};
 use mlc_communication::services::general::{GeneralService, GeneralServiceIdent, Info};
 use mlc_communication::services::general::{ProjectInfo, View as SView};
+use mlc_communication::services::project::{EditHistory, ProjectService, ProjectServiceIdent};
+use mlc_data::misc::ErrIgnore;
 use screens::{Configure, Program, Projects, Show};
 use std::{
     net::Ipv4Addr,
@@ -181,6 +187,7 @@ fn ProjectLayout() -> Element {
     let mut status_msg = use_signal(|| "Idling".to_string());
 
     let gen_client = use_service::<GeneralServiceIdent>()?;
+    let prj_client = use_service::<ProjectServiceIdent>()?;
 
     use_resource(move || async move {
         let is_valid = gen_client.read().is_valid_view(SView::Edit).await;
@@ -236,6 +243,20 @@ fn ProjectLayout() -> Element {
                             ProjectInfo::SettingsChanged => {
                                 SETTINGS_CHANGED.update();
                             }
+                            ProjectInfo::CueListsChanged => {
+                                CUE_LISTS_CHANGED.update();
+                            }
+                            ProjectInfo::GroupsChanged => {
+                                GROUPS_CHANGED.update();
+                            }
+                            ProjectInfo::ParkedChanged => {
+                                PARKED_CHANGED.update();
+                            }
+                            ProjectInfo::EndpointsChanged => {
+                                ENDPOINTS_CHANGED.update();
+                            }
+                            // No screen shows the output processing of fixtures
+                            ProjectInfo::FixtureOutputChanged { .. } => {}
                         }
                     }
                 }
@@ -248,6 +269,46 @@ fn ProjectLayout() -> Element {
         }
     });
 
+    let mut blackout = use_signal(|| false);
+    use_future(move || async move {
+        let mut masters = if let Ok(masters) = gen_client().masters().await {
+            masters
+        } else {
+            error!("Failed to receive masters sub");
+            return;
+        };
+
+        loop {
+            if let Ok(m) = masters.borrow_and_update() {
+                blackout.set(m.blackout);
+            }
+            if masters.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut bpm = use_signal(|| 120.0);
+    use_future(move || async move {
+        let mut tempo = if let Ok(tempo) = gen_client().tempo().await {
+            tempo
+        } else {
+            error!("Failed to receive tempo sub");
+            return;
+        };
+
+        loop {
+            if let Ok(t) = tempo.borrow_and_update() {
+                if bpm() != t.bpm * t.multiplier {
+                    bpm.set(t.bpm * t.multiplier);
+                }
+            }
+            if tempo.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+
     use_future(move || async move {
         loop {
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -257,6 +318,25 @@ fn ProjectLayout() -> Element {
         }
     });
 
+    let mut history = use_signal(EditHistory::default);
+    use_future(move || async move {
+        let mut sub = if let Ok(sub) = prj_client().history().await {
+            sub
+        } else {
+            error!("Failed to receive history sub");
+            return;
+        };
+
+        loop {
+            if let Ok(h) = sub.borrow_and_update() {
+                history.set(h.clone());
+            }
+            if sub.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+
     let r: Route = use_route();
     let extra_actions = match r {
         Route::Configure {} => rsx! {
@@ -319,6 +399,40 @@ fn ProjectLayout() -> Element {
                 }
                 div { class: "actions",
                     {extra_actions}
+                    IconButton {
+                        class: if history.read().undo.is_empty() { "disabled" },
+                        icon: LdUndo2,
+                        onclick: move |_| async move {
+                            match prj_client.read().undo().await {
+                                Ok(label) => ToastInfo::info("Undone", label).post(),
+                                Err(e) => ToastInfo::warn("Nothing undone", e.to_string()).post(),
+                            }
+                        },
+                    }
+                    IconButton {
+                        class: if history.read().redo.is_empty() { "disabled" },
+                        icon: LdRedo2,
+                        onclick: move |_| async move {
+                            match prj_client.read().redo().await {
+                                Ok(label) => ToastInfo::info("Redone", label).post(),
+                                Err(e) => ToastInfo::warn("Nothing redone", e.to_string()).post(),
+                            }
+                        },
+                    }
+                    IconButton {
+                        icon: LdActivity,
+                        text: format!("{:.0}", bpm()),
+                        onclick: move |_| async move {
+                            gen_client.read().tap_tempo().await.debug_ignore();
+                        },
+                    }
+                    IconButton {
+                        class: if blackout() { "blackout" },
+                        icon: LdPower,
+                        onclick: move |_| async move {
+                            gen_client.read().set_blackout(!blackout()).await.debug_ignore();
+                        },
+                    }
                     IconButton {
                         icon: LdSave,
                         onclick: move |_| async move {
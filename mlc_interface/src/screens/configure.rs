@@ -6,18 +6,26 @@ use crate::utils::{
 };
 use crate::ADD_FIXTURE_MODAL;
 use dioxus::prelude::*;
-use dioxus_free_icons::icons::ld_icons::{LdLamp, LdPencil, LdPencilRuler, LdRoute, LdSearch};
+use dioxus_free_icons::icons::ld_icons::{
+    LdLamp, LdPencil, LdPencilRuler, LdPlus, LdRoute, LdSearch, LdTrash,
+};
 use futures::StreamExt;
 use itertools::Itertools;
-use mlc_communication::services::project::{ProjectService, ProjectServiceIdent};
+use mlc_communication::services::endpoints::{
+    ArtNetNode, EndpointService, EndpointServiceIdent, RdmUid,
+};
+use mlc_communication::services::project::{ProjectService, ProjectServiceIdent, UniverseHead};
+use mlc_data::endpoints::{EndpointConfig, EndpointSpeed};
 use mlc_data::fixture::blueprint::{Channel, FixtureBlueprint};
 use mlc_data::misc::ErrIgnore;
 use mlc_data::project::universe::{UniverseAddress, UniverseId, UNIVERSE_SIZE};
 use mlc_data::project::ProjectType;
+use std::net::Ipv4Addr;
 use std::time::Duration;
 use tokio::select;
 
 pub static BLUEPRINTS_CHANGED: SignalNotify = SignalNotify::create();
+pub static GROUPS_CHANGED: SignalNotify = SignalNotify::create();
 
 #[component]
 pub fn Configure() -> Element {
@@ -381,15 +389,55 @@ enum Fcc {
 }
 
 pub const UNIVERSE_LIST_CHANGED: SignalNotify = SignalNotify::create();
+pub const PARKED_CHANGED: SignalNotify = SignalNotify::create();
 
 #[component]
 fn FaderPanel(prj: SClient<ProjectServiceIdent>) -> Element {
-    let ul = use_resource(move || async move { prj.read().universe_list().await }).rtc_suspend()?;
+    let ul = use_resource(move || async move {
+        let _ = UNIVERSE_LIST_CHANGED.read();
+        prj.read().universe_list().await
+    })
+    .rtc_suspend()?;
 
-    let tabs = use_signal(move || MappedVecTabs::new(ul, 1));
+    let list = ul.clone();
+    let mut tabs = use_signal(move || {
+        let initial = ul.read().first().cloned().unwrap_or(UniverseHead {
+            id: 1,
+            name: String::new(),
+        });
+        MappedVecTabs::new(ul, initial)
+    });
+
+    // Keep the selection in sync with renamed or removed universes
+    use_effect(move || {
+        let list = list.read();
+        let current = tabs.peek().get();
+        let next = list
+            .iter()
+            .find(|u| u.id == current.id)
+            .or(list.first())
+            .cloned();
+        if let Some(next) = next.filter(|n| *n != current) {
+            tabs.write().set(next);
+        }
+    });
 
     let mut data = use_signal(|| [0_u8; UNIVERSE_SIZE]);
 
+    let parked = use_resource(move || async move {
+        let _ = PARKED_CHANGED.read();
+        prj.read().parked_list().await
+    })
+    .rtc_suspend()?;
+    let parked = use_memo(move || {
+        let u = tabs.read().get().id;
+        let mut channels = [false; UNIVERSE_SIZE];
+        for (a, _) in parked.read().iter().filter(|(a, _)| a.universe() == u) {
+            channels[a.address().take() - 1] = true;
+        }
+        channels
+    });
+
     let value_setter = use_coroutine(move |mut rx: UnboundedReceiver<Fcc>| async move {
         let mut recv = None;
         let mut send = None;
@@ -420,7 +468,7 @@ fn FaderPanel(prj: SClient<ProjectServiceIdent>) -> Element {
     });
 
     use_effect(move || {
-        let u = tabs.read().get();
+        let u = tabs.read().get().id;
         value_setter.send(Fcc::SwitchUniverse(u));
     });
 
@@ -434,7 +482,7 @@ fn FaderPanel(prj: SClient<ProjectServiceIdent>) -> Element {
                 class: "faders",
                 for i in 0..UNIVERSE_SIZE {
                     div {
-                        class: "fader-c",
+                        class: if parked.read()[i] { "fader-c parked" } else { "fader-c" },
                         span {
                             class: "value",
                             {format!("{:0>3}",data.read()[i].clone())}
@@ -459,6 +507,7 @@ fn FaderPanel(prj: SClient<ProjectServiceIdent>) -> Element {
 
 const ENDPOINT_MAPPING_MODAL: Symbol = Symbol::create("endpoint-mapping");
 pub const SETTINGS_CHANGED: SignalNotify = SignalNotify::create();
+pub const ENDPOINTS_CHANGED: SignalNotify = SignalNotify::create();
 
 #[component]
 fn Settings(prj: SClient<ProjectServiceIdent>) -> Element {
@@ -468,7 +517,12 @@ fn Settings(prj: SClient<ProjectServiceIdent>) -> Element {
     })
     .rtc_suspend()?;
 
-    let meta = use_resource(move || async move { prj.read().get_meta().await }).rtc_suspend()?;
+    // Renaming the open project changes its metadata
+    let meta = use_resource(move || async move {
+        let _ = SETTINGS_CHANGED.read();
+        prj.read().get_meta().await
+    })
+    .rtc_suspend()?;
 
     let name = meta.clone().map(|m| &m.name);
     let file = meta.clone().map(|m| &m.file_name);
@@ -480,6 +534,7 @@ fn Settings(prj: SClient<ProjectServiceIdent>) -> Element {
 
     let save_on_quit = settings.clone().map(|s| &s.save_on_quit);
     let autosave = settings.clone().map(|s| &s.autosave);
+    let backups = settings.clone().map(|s| &s.backups);
 
     let mem_sets = use_signal(move || settings);
 
@@ -563,6 +618,27 @@ fn Settings(prj: SClient<ProjectServiceIdent>) -> Element {
                     }
                 }
             }
+
+            h3 {
+                "Backups"
+            }
+
+            input {
+                r#type: "number",
+                min: 0,
+                value: backups(),
+                onchange: move |v|  {
+                    let Ok(new_val) = v.value().parse() else {
+                        ToastInfo::error("Invalid number of backups", v.value()).post();
+                        return;
+                    };
+                    let mut sett = mem_sets.read().read().clone();
+                    sett.backups = new_val;
+                    spawn(async move {
+                        prj.read().update_settings(sett).await.ignore();
+                    });
+                }
+            }
         }
 
         div {
@@ -580,14 +656,177 @@ fn Settings(prj: SClient<ProjectServiceIdent>) -> Element {
         Modal {
             title: "Endpoint Mappings",
             ident: ENDPOINT_MAPPING_MODAL,
-            variant: ModalVariant::OkCancel,
+            variant: ModalVariant::Ok,
             icon: LdRoute,
-            oktext: "Save",
-            onexit: move |a| async move{
-                if a == ModalResult::Success {
-                    // if let Err(e) = prj.read().save_endpoint_mappings().await {
-                    //     ToastInfo::error("Failed to save endpoint mappings!", e.to_string()).post();
-                    // }
+            EndpointMappingEditor { prj }
+        }
+    }
+}
+
+#[component]
+fn EndpointMappingEditor(prj: SClient<ProjectServiceIdent>) -> Element {
+    let ul = use_resource(move || async move {
+        let _ = UNIVERSE_LIST_CHANGED.read();
+        prj.read().universe_list().await
+    })
+    .rtc_suspend()?;
+    let mapping = use_resource(move || async move {
+        let _ = ENDPOINTS_CHANGED.read();
+        prj.read().endpoint_mapping().await
+    })
+    .rtc_suspend()?;
+
+    let current = mapping.clone();
+    let next_sacn = use_memo(move || {
+        current
+            .read()
+            .endpoints
+            .values()
+            .flatten()
+            .filter_map(|c| match c {
+                EndpointConfig::Sacn { universe, .. } => Some(*universe),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+            + 1
+    });
+
+    let add = move |universe: UniverseId, config: EndpointConfig| async move {
+        if let Err(e) = prj.read().endpoint_add(universe, config).await {
+            ToastInfo::error("Failed to add endpoint!", e.to_string()).post();
+        }
+    };
+
+    rsx! {
+        div { class: "endpointMapping",
+            for u in ul.read().iter().cloned() {
+                div { class: "epm-universe",
+                    h3 { {u.get_name()} }
+                    for (i, config) in mapping.read().endpoints.get(&u.id).cloned().unwrap_or_default().into_iter().enumerate() {
+                        div { class: "epm-endpoint",
+                            span { {config.to_string()} }
+                            IconButton {
+                                icon: LdTrash,
+                                onclick: move |_| async move {
+                                    if let Err(e) = prj.read().endpoint_remove(u.id, i).await {
+                                        ToastInfo::error("Failed to remove endpoint!", e.to_string()).post();
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    div { class: "epm-add",
+                        IconButton {
+                            icon: LdPlus,
+                            text: "Logger",
+                            onclick: move |_| add(u.id, EndpointConfig::Logger)
+                        }
+                        IconButton {
+                            icon: LdPlus,
+                            text: "Simulator",
+                            onclick: move |_| add(u.id, EndpointConfig::Simulator)
+                        }
+                        IconButton {
+                            icon: LdPlus,
+                            text: "sACN",
+                            onclick: move |_| add(u.id, EndpointConfig::sacn(next_sacn(), EndpointSpeed::Medium))
+                        }
+                    }
+                }
+            }
+            ArtNetDiscovery { prj, universes: ul.read().clone() }
+        }
+    }
+}
+
+#[component]
+fn ArtNetDiscovery(prj: SClient<ProjectServiceIdent>, universes: Vec<UniverseHead>) -> Element {
+    let endpoints = use_service::<EndpointServiceIdent>()?;
+    let mut nodes = use_signal(Vec::<ArtNetNode>::new);
+    let mut devices = use_signal(|| None::<(Ipv4Addr, u16, Vec<RdmUid>)>);
+    let mut searching = use_signal(|| false);
+    // The universe discovered ports are used for, the first one if none was picked
+    let mut target = use_signal(|| None::<UniverseId>);
+    let target_id = target().or(universes.first().map(|u| u.id));
+
+    let discover = move |_| async move {
+        searching.set(true);
+        match endpoints.read().artnet_nodes().await {
+            Ok(found) => nodes.set(found),
+            Err(e) => ToastInfo::error("Art-Net discovery failed!", e.to_string()).post(),
+        }
+        searching.set(false);
+    };
+
+    let list_devices = move |ip: Ipv4Addr, universe: u16| async move {
+        match endpoints.read().artnet_rdm_devices(ip, universe).await {
+            Ok(uids) => devices.set(Some((ip, universe, uids))),
+            Err(e) => ToastInfo::error("Listing RDM devices failed!", e.to_string()).post(),
+        }
+    };
+
+    let use_port = move |universe: Option<UniverseId>, ip: Ipv4Addr, port_address: u16| async move {
+        let Some(universe) = universe else {
+            ToastInfo::error("Failed to add endpoint!", "There is no universe").post();
+            return;
+        };
+        let config = EndpointConfig::artnet(ip, port_address, EndpointSpeed::Medium);
+        if let Err(e) = prj.read().endpoint_add(universe, config).await {
+            ToastInfo::error("Failed to add endpoint!", e.to_string()).post();
+        }
+    };
+
+    rsx! {
+        div { class: "epm-discovery",
+            h3 { "Art-Net nodes" }
+            div { class: "epm-add",
+                span { "Use ports for" }
+                select {
+                    onchange: move |e| target.set(e.value().parse().ok()),
+                    for u in universes.iter() {
+                        option {
+                            value: "{u.id}",
+                            selected: Some(u.id) == target_id,
+                            {u.get_name()}
+                        }
+                    }
+                }
+            }
+            IconButton {
+                icon: LdSearch,
+                text: if searching() { "Searching..." } else { "Discover" },
+                onclick: move |e| {
+                    if !searching() {
+                        spawn(discover(e));
+                    }
+                }
+            }
+            for node in nodes.read().iter().cloned() {
+                div { class: "epm-endpoint",
+                    span { "{node.short_name} ({node.ip})" }
+                    div { class: "epm-add",
+                        for port in node.ports.iter().filter(|p| p.output).copied() {
+                            IconButton {
+                                icon: LdPlus,
+                                text: format!("Use {}", port.universe),
+                                onclick: move |_| use_port(target_id, node.ip, port.universe)
+                            }
+                            IconButton {
+                                icon: LdSearch,
+                                text: format!("RDM {}", port.universe),
+                                onclick: move |_| list_devices(node.ip, port.universe)
+                            }
+                        }
+                    }
+                }
+            }
+            if let Some((ip, universe, uids)) = devices() {
+                div { class: "epm-rdm",
+                    span { "RDM devices of {ip} port {universe}: {uids.len()}" }
+                    for uid in uids {
+                        span { {uid.to_string()} }
+                    }
                 }
             }
         }
@@ -1,5 +1,7 @@
 use dioxus::prelude::*;
-use crate::utils::Panel;
+use crate::utils::{Panel, SignalNotify};
+
+pub const CUE_LISTS_CHANGED: SignalNotify = SignalNotify::create();
 
 #[component]
 pub fn Program() -> Element {
@@ -6,23 +6,30 @@ use crate::utils::{
 use dioxus::logger::tracing::{info, warn};
 use dioxus::prelude::*;
 use dioxus_free_icons::icons::ld_icons::{
-    LdFileArchive, LdFileJson, LdLightbulb, LdPen, LdPencilRuler, LdPlus, LdSave, LdTrash,
-    LdTriangleAlert,
+    LdCopy, LdFileArchive, LdFileDown, LdFileJson, LdFilePen, LdFileUp, LdHistory, LdLightbulb,
+    LdPen, LdPencilRuler, LdPlus, LdSave, LdTrash, LdTriangleAlert,
 };
 use dioxus_free_icons::Icon;
+use directories::UserDirs;
 use log::error;
 use mlc_communication::services::general::{
     GeneralService, GeneralServiceIdent, Info, View as GenView,
 };
 use mlc_communication::services::project_selection::{
-    ProjectIdent, ProjectSelectionService, ProjectSelectionServiceClient,
-    ProjectSelectionServiceIdent,
+    ImportOptions, ProjectIdent, ProjectSelectionService, ProjectSelectionServiceClient,
+    ProjectSelectionServiceError, ProjectSelectionServiceIdent,
+};
+use mlc_data::project::{
+    ProjectBackup, ProjectMetadata, ProjectType, ToFileName, ARCHIVE_EXTENSION,
 };
-use mlc_data::project::{ProjectMetadata, ProjectType, ToFileName};
 
 const PROJECTS_CSS: Asset = asset!("/assets/projects.css");
 
 const CREATE_PROJECT: Symbol = Symbol::create("create-project");
+const RENAME_PROJECT: Symbol = Symbol::create("rename-project");
+const EXPORT_PROJECT: Symbol = Symbol::create("export-project");
+const IMPORT_PROJECT: Symbol = Symbol::create("import-project");
+const RESTORE_BACKUP: Symbol = Symbol::create("restore-backup");
 #[component]
 pub fn Projects() -> Element {
     let m_client = use_resource(async || {
@@ -93,6 +100,13 @@ pub fn Projects() -> Element {
     let service_suspend = service.suspend()?;
     let s2 = service_suspend.clone();
     let s3 = service_suspend.clone();
+    let s4 = service_suspend.clone();
+    let s5 = service_suspend.clone();
+    let s6 = service_suspend.clone();
+    let s7 = service_suspend.clone();
+    let s8 = service_suspend.clone();
+    let s9 = service_suspend.clone();
+    let s10 = service_suspend.clone();
 
     let mut projects = use_resource::<Vec<ProjectMetadata>, _>(move || {
         let s2 = s2.clone();
@@ -110,6 +124,19 @@ pub fn Projects() -> Element {
     let mut new_project_type = use_signal(|| ProjectType::Json);
     let is_json = use_memo(move || new_project_type.read().eq(&ProjectType::Json));
 
+    let mut rename_ident = use_signal(ProjectIdent::new);
+    let mut rename_name = use_signal(String::new);
+
+    let mut export_ident = use_signal(ProjectIdent::new);
+    let mut export_path = use_signal(String::new);
+    let mut import_path = use_signal(String::new);
+    let mut import_name = use_signal(String::new);
+    let mut import_ignore_version = use_signal(|| false);
+
+    let mut backup_ident = use_signal(ProjectIdent::new);
+    let mut backups = use_signal(Vec::<ProjectBackup>::new);
+    let mut backup_selected = use_signal(String::new);
+
     let file_name = use_memo(move || {
         format!(
             "{}.{}",
@@ -125,6 +152,12 @@ pub fn Projects() -> Element {
                 Branding {}
                 h1 { "Project Explorer" }
                 div { class: "actions",
+                    IconButton {
+                        icon: LdFileUp,
+                        onclick: async |_| {
+                            IMPORT_PROJECT.open().await;
+                        },
+                    }
                     IconButton {
                         icon: LdPlus,
                         onclick: async |_| {
@@ -157,12 +190,256 @@ pub fn Projects() -> Element {
                                     ToastInfo::info("Project not found", "Requested project could not be opened because it could not be located on disk!").post();
                                     projects.restart();
                                 }
+                                Err(ProjectSelectionServiceError::ProjectCorrupt { ident, error, backup: Some(backup) }) => {
+                                    ToastInfo::error("Project is corrupt", format!("{error}, restoring a backup is suggested")).post();
+                                    *backups.write() = s3.read().backups(ident.clone()).await.unwrap_or_default();
+                                    *backup_ident.write() = ident;
+                                    *backup_selected.write() = backup;
+                                    RESTORE_BACKUP.open().await;
+                                }
                                 Err(e) => {
                                     ToastInfo::error("Failed to open project", e.to_string()).post();
                                     projects.restart();
                                 }
                             }
                         }
+                    },
+                    onrename: move |p: ProjectMetadata| async move {
+                        *rename_ident.write() = p.file_name;
+                        *rename_name.write() = p.name;
+                        RENAME_PROJECT.open().await;
+                    },
+                    onexport: move |ident: ProjectIdent| async move {
+                        let Some(dirs) = UserDirs::new() else {
+                            ToastInfo::error("Failed to export project", "No home folder found to export to").post();
+                            return;
+                        };
+                        let dir = dirs.download_dir().unwrap_or(dirs.home_dir());
+                        let file = dir.join(format!("{ident}.{ARCHIVE_EXTENSION}"));
+                        *export_path.write() = file.to_string_lossy().to_string();
+                        *export_ident.write() = ident;
+                        EXPORT_PROJECT.open().await;
+                    },
+                    onbackups: move |ident: ProjectIdent| {
+                        let s9 = s9.clone();
+                        async move {
+                            match s9.read().backups(ident.clone()).await {
+                                Ok(list) => {
+                                    *backup_selected.write() = list.first().map(|b| b.id.clone()).unwrap_or_default();
+                                    *backups.write() = list;
+                                    *backup_ident.write() = ident;
+                                    RESTORE_BACKUP.open().await;
+                                }
+                                Err(e) => {
+                                    ToastInfo::error("Failed to list backups", e.to_string()).post();
+                                }
+                            }
+                        }
+                    },
+                    onduplicate: move |p: ProjectMetadata| {
+                        let s4 = s4.clone();
+                        async move {
+                            match s4.read().duplicate(p.file_name, format!("{} Copy", p.name)).await {
+                                Ok(_) => projects.restart(),
+                                Err(e) => {
+                                    ToastInfo::error("Failed to duplicate project", e.to_string()).post();
+                                }
+                            }
+                        }
+                    },
+                    ondelete: move |ident| {
+                        let s5 = s5.clone();
+                        async move {
+                            match s5.read().delete(ident).await {
+                                Ok(()) => {
+                                    ToastInfo::info("Deleted Project", "The project was moved to the trash folder").post();
+                                    projects.restart();
+                                }
+                                Err(e) => {
+                                    ToastInfo::error("Failed to delete project", e.to_string()).post();
+                                }
+                            }
+                        }
+                    },
+                }
+            }
+
+            Modal {
+                title: "Rename Project",
+                ident: RENAME_PROJECT.clone(),
+                icon: LdFilePen,
+                variant: ModalVariant::OkCancel,
+                onexit: move |r| {
+                    let s6 = s6.clone();
+                    async move {
+                        if r == ModalResult::Cancel {
+                            return;
+                        }
+                        let ident = rename_ident.read().clone();
+                        let name = rename_name.read().clone();
+                        match s6.read().rename(ident, name).await {
+                            Ok(_) => projects.restart(),
+                            Err(e) => {
+                                ToastInfo::error("Failed to rename project", e.to_string()).post();
+                            }
+                        }
+                    }
+                },
+
+                oktext: "Rename".to_string(),
+                label {
+                    "Project Name: "
+                    input {
+                        r#type: "text",
+                        value: rename_name().clone(),
+                        oninput: move |v| *rename_name.write() = v.value(),
+                    }
+                }
+            }
+
+            Modal {
+                title: "Export Project",
+                ident: EXPORT_PROJECT.clone(),
+                icon: LdFileDown,
+                variant: ModalVariant::OkCancel,
+                onexit: move |r| {
+                    let s7 = s7.clone();
+                    async move {
+                        if r == ModalResult::Cancel {
+                            return;
+                        }
+                        let ident = export_ident.read().clone();
+                        let path = export_path.read().clone();
+                        let result = match s7.read().export(ident).await {
+                            Ok(data) => tokio::fs::write(&path, data).await.map_err(|e| e.to_string()),
+                            Err(e) => Err(e.to_string()),
+                        };
+                        match result {
+                            Ok(()) => ToastInfo::info("Exported Project", format!("Saved to {path}")).post(),
+                            Err(e) => ToastInfo::error("Failed to export project", e).post(),
+                        }
+                    }
+                },
+
+                oktext: "Export".to_string(),
+                label {
+                    "Archive File: "
+                    input {
+                        r#type: "text",
+                        value: export_path().clone(),
+                        oninput: move |v| *export_path.write() = v.value(),
+                    }
+                }
+            }
+
+            Modal {
+                title: "Import Project",
+                ident: IMPORT_PROJECT.clone(),
+                icon: LdFileUp,
+                variant: ModalVariant::OkCancel,
+                onexit: move |r| {
+                    let s8 = s8.clone();
+                    async move {
+                        if r == ModalResult::Cancel {
+                            return;
+                        }
+                        let path = import_path.read().clone();
+                        let data = match tokio::fs::read(&path).await {
+                            Ok(data) => data,
+                            Err(e) => {
+                                ToastInfo::error("Failed to read archive", e.to_string()).post();
+                                return;
+                            }
+                        };
+                        let name = import_name.read().clone();
+                        let options = ImportOptions {
+                            name: (!name.is_empty()).then_some(name),
+                            ignore_version: *import_ignore_version.read(),
+                        };
+                        match s8.read().import(data, options).await {
+                            Ok(_) => {
+                                ToastInfo::info("Imported Project", "The project was imported").post();
+                                projects.restart();
+                            }
+                            Err(e @ (ProjectSelectionServiceError::ProjectExists(_)
+                            | ProjectSelectionServiceError::VersionMismatch { .. })) => {
+                                ToastInfo::warn("Project not imported", e.to_string()).post();
+                            }
+                            Err(e) => {
+                                ToastInfo::error("Failed to import project", e.to_string()).post();
+                            }
+                        }
+                    }
+                },
+
+                oktext: "Import".to_string(),
+                label {
+                    "Archive File: "
+                    input {
+                        r#type: "text",
+                        value: import_path().clone(),
+                        oninput: move |v| *import_path.write() = v.value(),
+                    }
+                }
+                label {
+                    "Name (optional): "
+                    input {
+                        r#type: "text",
+                        value: import_name().clone(),
+                        oninput: move |v| *import_name.write() = v.value(),
+                    }
+                }
+                label {
+                    "Ignore Version: "
+                    input {
+                        r#type: "checkbox",
+                        checked: import_ignore_version(),
+                        onchange: move |v| *import_ignore_version.write() = v.value() == "true",
+                    }
+                }
+            }
+
+            Modal {
+                title: "Restore Backup",
+                ident: RESTORE_BACKUP.clone(),
+                icon: LdHistory,
+                variant: ModalVariant::OkCancel,
+                onexit: move |r| {
+                    let s10 = s10.clone();
+                    async move {
+                        let backup = backup_selected.read().clone();
+                        if r == ModalResult::Cancel || backup.is_empty() {
+                            return;
+                        }
+                        let ident = backup_ident.read().clone();
+                        match s10.read().restore_backup(ident, backup).await {
+                            Ok(()) => {
+                                ToastInfo::info("Restored Backup", "The previous save was kept as a backup").post();
+                                projects.restart();
+                            }
+                            Err(e) => {
+                                ToastInfo::error("Failed to restore backup", e.to_string()).post();
+                            }
+                        }
+                    }
+                },
+
+                oktext: "Restore".to_string(),
+                if backups.read().is_empty() {
+                    p { "There are no backups of this project yet" }
+                }
+                for backup in backups.read().iter().cloned() {
+                    label {
+                        input {
+                            r#type: "radio",
+                            name: "backup",
+                            checked: *backup_selected.read() == backup.id,
+                            onchange: {
+                                let id = backup.id.clone();
+                                move |_| *backup_selected.write() = id.clone()
+                            },
+                        }
+                        {format!(" {} ({} KiB)", backup.created_at.format("%d.%m.%y %H:%M:%S"), backup.size.div_ceil(1024))}
                     }
                 }
             }
@@ -224,14 +501,39 @@ pub fn Projects() -> Element {
 fn ProjectList(
     projects: MappedSignal<Vec<ProjectMetadata>>,
     onopen: EventHandler<ProjectIdent>,
+    onrename: EventHandler<ProjectMetadata>,
+    onexport: EventHandler<ProjectIdent>,
+    onbackups: EventHandler<ProjectIdent>,
+    onduplicate: EventHandler<ProjectMetadata>,
+    ondelete: EventHandler<ProjectIdent>,
 ) -> Element {
     rsx! {
         div { class: "projectList",
             for p in projects().into_iter() {
                 ProjectListItem {
                     item: p.clone(),
-                    onopen: move |_| {
-                        onopen.call(p.file_name.clone());
+                    onopen: {
+                        let ident = p.file_name.clone();
+                        move |_| onopen.call(ident.clone())
+                    },
+                    onrename: {
+                        let p = p.clone();
+                        move |_| onrename.call(p.clone())
+                    },
+                    onexport: {
+                        let ident = p.file_name.clone();
+                        move |_| onexport.call(ident.clone())
+                    },
+                    onbackups: {
+                        let ident = p.file_name.clone();
+                        move |_| onbackups.call(ident.clone())
+                    },
+                    onduplicate: {
+                        let p = p.clone();
+                        move |_| onduplicate.call(p.clone())
+                    },
+                    ondelete: move |_| {
+                        ondelete.call(p.file_name.clone());
                     }
                 }
             }
@@ -240,7 +542,15 @@ fn ProjectList(
 }
 
 #[component]
-fn ProjectListItem(item: ProjectMetadata, onopen: EventHandler) -> Element {
+fn ProjectListItem(
+    item: ProjectMetadata,
+    onopen: EventHandler,
+    onrename: EventHandler,
+    onexport: EventHandler,
+    onbackups: EventHandler,
+    onduplicate: EventHandler,
+    ondelete: EventHandler,
+) -> Element {
     rsx! {
         div {
             class: "project",
@@ -286,11 +596,35 @@ fn ProjectListItem(item: ProjectMetadata, onopen: EventHandler) -> Element {
                         onopen.call(());
                     },
                 }
+                IconButton {
+                    icon: LdFilePen,
+                    onclick: move |_| {
+                        onrename.call(());
+                    },
+                }
+                IconButton {
+                    icon: LdFileDown,
+                    onclick: move |_| {
+                        onexport.call(());
+                    },
+                }
+                IconButton {
+                    icon: LdHistory,
+                    onclick: move |_| {
+                        onbackups.call(());
+                    },
+                }
+                IconButton {
+                    icon: LdCopy,
+                    onclick: move |_| {
+                        onduplicate.call(());
+                    },
+                }
                 IconButton {
                     icon: LdTrash,
                     class: "delete",
-                    onclick: async |_| {
-                        info!("Deleting");
+                    onclick: move |_| {
+                        ondelete.call(());
                     },
                 }
             }
@@ -6,6 +6,7 @@ use dioxus::logger::tracing::info;
 use dioxus::{document::eval, prelude::*};
 use dioxus_free_icons::{icons::ld_icons::LdX, Icon, IconShape};
 use mlc_communication::remoc::rch::mpsc::{Receiver, RecvError};
+use mlc_communication::services::project::UniverseHead;
 use uuid::Uuid;
 
 #[component]
@@ -278,6 +279,16 @@ impl TabItem for u16 {
     }
 }
 
+impl TabItem for UniverseHead {
+    fn get_name(&self) -> String {
+        if self.name.is_empty() {
+            self.id.to_string()
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
 #[component]
 pub fn Tabs<T: TabController + 'static>(
     controller: Signal<T>,
@@ -0,0 +1,216 @@
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use mlc_communication::remoc::rch::watch;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::audio::{AudioService, AudioServiceError, AudioState};
+use mlc_data::audio::analysis::{AudioAnalyzer, AudioLevels};
+use mlc_data::audio::{AudioSource, RawPcmReader, WavReader};
+use mlc_data::project::audio::{AudioInput, AudioSettings};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::select;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+/// Level changes below this are not applied to faders
+const LEVEL_THRESHOLD: f32 = 0.01;
+
+#[rtc::async_trait]
+impl AudioService for ServiceImpl {
+    async fn state(&self) -> Result<watch::Receiver<AudioState>, AudioServiceError> {
+        Ok(self.audio_state.subscribe())
+    }
+}
+
+/// Reads an audio input, WAV files are paced to real time
+pub struct InputReader {
+    source: Box<dyn AudioSource + Send>,
+    realtime: bool,
+    start: Instant,
+    samples: u64,
+}
+
+impl InputReader {
+    pub fn open(input: &AudioInput) -> io::Result<Self> {
+        let file = BufReader::new(File::open(&input.path)?);
+        let source: Box<dyn AudioSource + Send> = match input.raw_sample_rate {
+            Some(rate) => Box::new(RawPcmReader::new(file, rate)),
+            None => Box::new(WavReader::new(file)?),
+        };
+        Ok(Self {
+            source,
+            realtime: input.raw_sample_rate.is_some(),
+            start: Instant::now(),
+            samples: 0,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    pub fn read(&mut self, buf: &mut [f32]) -> io::Result<usize> {
+        let len = self.source.read(buf)?;
+        self.samples += len as u64;
+        if !self.realtime
+            && let Some(wait) = self
+                .instant(self.samples)
+                .checked_duration_since(Instant::now())
+        {
+            thread::sleep(wait);
+        }
+        Ok(len)
+    }
+
+    /// When the sample was played
+    pub fn instant(&self, sample: u64) -> Instant {
+        let time =
+            |samples: u64| Duration::from_secs_f64(samples as f64 / self.sample_rate() as f64);
+        if self.realtime {
+            let now = Instant::now();
+            now.checked_sub(time(self.samples.saturating_sub(sample)))
+                .unwrap_or(now)
+        } else {
+            self.start + time(sample)
+        }
+    }
+}
+
+/// Processes an audio input on a separate thread until dropped
+pub struct InputThread {
+    stop: Arc<AtomicBool>,
+}
+
+impl InputThread {
+    /// `init` creates the processing for the sample rate of the input, which stops the thread
+    /// by returning false
+    pub fn spawn<I, P>(input: AudioInput, init: I) -> Self
+    where
+        I: FnOnce(u32) -> P + Send + 'static,
+        P: FnMut(&InputReader, &[f32]) -> bool,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        thread::spawn(move || {
+            if let Err(e) = process_input(&input, &thread_stop, init) {
+                warn!("Reading audio from {} failed: {e}", input.path.display());
+            }
+        });
+        Self { stop }
+    }
+}
+
+impl Drop for InputThread {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn process_input<P: FnMut(&InputReader, &[f32]) -> bool>(
+    input: &AudioInput,
+    stop: &AtomicBool,
+    init: impl FnOnce(u32) -> P,
+) -> io::Result<()> {
+    let mut reader = InputReader::open(input)?;
+    let mut process = init(reader.sample_rate());
+    let mut buf = vec![0.0; (reader.sample_rate() as usize / 100).max(1)];
+    info!("Reading audio from {}", input.path.display());
+
+    while !stop.load(Ordering::Relaxed) {
+        let len = reader.read(&mut buf)?;
+        if len == 0 {
+            info!("The audio input {} ended", input.path.display());
+            break;
+        }
+        if !process(&reader, &buf[..len]) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub struct AudioAnalysisService;
+
+impl MlcServiceSimple for AudioAnalysisService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_analysis(
+            res.service_obj.clone(),
+            res.shutdown.clone(),
+            res.adapt_notifier.clone(),
+        )
+    }
+}
+
+async fn run_analysis(
+    service_obj: AServiceImpl,
+    shutdown_handler: ShutdownHandler,
+    adapt_notifier: AdaptNotifier,
+) {
+    loop {
+        let settings = service_obj.project.read().await.audio.clone();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let input = settings.input.clone().filter(|_| settings.enabled);
+        let _reader = input.clone().map(|input| {
+            InputThread::spawn(input, move |sample_rate| {
+                let mut analyzer = AudioAnalyzer::new(sample_rate);
+                move |_: &InputReader, samples: &[f32]| {
+                    analyzer
+                        .push(samples)
+                        .into_iter()
+                        .all(|levels| tx.send(levels).is_ok())
+                }
+            })
+        });
+        service_obj.audio_state.send_replace(AudioState {
+            input: input.map(|i| i.path.display().to_string()),
+            levels: AudioLevels::default(),
+        });
+
+        let mut applied = vec![None; settings.mappings.len()];
+        loop {
+            select! {
+                _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                    return;
+                }
+                _ = adapt_notifier.wait(AdaptScopes::CONTROL | AdaptScopes::PROJECT) => {
+                    break;
+                }
+                Some(levels) = rx.recv() => {
+                    service_obj.audio_state.send_modify(|s| s.levels = levels);
+                    apply_mappings(&service_obj, &settings, &levels, &mut applied).await;
+                }
+            }
+        }
+    }
+}
+
+/// Fires triggers on beats and sets faders to the levels
+async fn apply_mappings(
+    service_obj: &AServiceImpl,
+    settings: &AudioSettings,
+    levels: &AudioLevels,
+    applied: &mut [Option<f32>],
+) {
+    for (mapping, applied) in settings.mappings.iter().zip(applied.iter_mut()) {
+        let Some(value) = mapping.value(levels) else {
+            continue;
+        };
+        if !mapping.action.is_trigger()
+            && applied.is_some_and(|a: f32| (a - value).abs() < LEVEL_THRESHOLD)
+        {
+            continue;
+        }
+        *applied = Some(value);
+
+        if let Err(e) = service_obj
+            .execute_control(&mapping.action, Some(value))
+            .await
+        {
+            warn!("Audio control {:?} failed: {e}", mapping.action);
+        }
+    }
+}
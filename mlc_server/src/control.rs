@@ -0,0 +1,104 @@
+use crate::ServiceImpl;
+use crate::universe::{RuntimeCommand, UniverseUpdate};
+use mlc_communication::services::general::Info;
+use mlc_communication::services::programmer::{ProgrammerService, ProgrammerServiceError};
+use mlc_communication::services::script::ScriptServiceError;
+use mlc_data::SavePercentage;
+use mlc_data::project::control::ControlAction;
+use mlc_data::project::group::GroupId;
+use mlc_data::project::universe::UniverseId;
+
+/// Why an action of an external control failed
+#[derive(Debug, thiserror::Error)]
+pub enum ControlError {
+    #[error(transparent)]
+    Cue(#[from] ProgrammerServiceError),
+
+    #[error(transparent)]
+    Script(#[from] ScriptServiceError),
+}
+
+impl ServiceImpl {
+    /// Executes an action of an external control. `value` is the normalized control value,
+    /// triggers fire when it is missing or at least half, `None` toggles the blackout.
+    pub async fn execute_control(
+        &self,
+        action: &ControlAction,
+        value: Option<f32>,
+    ) -> Result<(), ControlError> {
+        let value = value.map(|v| v.clamp(0.0, 1.0));
+        if action.is_trigger() && value.is_some_and(|v| v < 0.5) {
+            return Ok(());
+        }
+
+        match action {
+            ControlAction::CueGo(list) => Ok(self.cue_go(*list).await?),
+            ControlAction::CueBack(list) => Ok(self.cue_back(*list).await?),
+            ControlAction::CueRelease(list) => Ok(self.cue_release(*list).await?),
+            ControlAction::GrandMaster => {
+                if let Some(v) = value {
+                    self.update_masters(|m| m.grand_master = SavePercentage::create(v));
+                }
+                Ok(())
+            }
+            ControlAction::Blackout => {
+                self.update_masters(|m| m.blackout = value.map_or(!m.blackout, |v| v >= 0.5));
+                Ok(())
+            }
+            ControlAction::UniverseMaster(universe) => {
+                if let Some(v) = value {
+                    self.set_universe_level(*universe, v);
+                }
+                Ok(())
+            }
+            ControlAction::GroupMaster(group) => {
+                if let Some(v) = value {
+                    self.set_group_level(*group, v);
+                }
+                Ok(())
+            }
+            ControlAction::Channel(address) => {
+                if let Some(v) = value {
+                    self.universe_runtime
+                        .cmd(RuntimeCommand::UpdateData(UniverseUpdate::Single {
+                            update: (address.clone(), (v * 255.0).round() as u8),
+                        }));
+                }
+                Ok(())
+            }
+            ControlAction::Channels | ControlAction::UniverseMasters => Ok(()),
+            ControlAction::TapTempo => {
+                self.tap();
+                Ok(())
+            }
+            ControlAction::TempoReset => {
+                self.update_tempo(|clock, now| clock.reset_phase(now));
+                Ok(())
+            }
+            ControlAction::RunScript(name) => {
+                let result = self.run_script(name.clone()).await;
+                if let Err(e) = &result {
+                    self.send_info(Info::Warning {
+                        title: "Failed to run script".to_string(),
+                        msg: e.to_string(),
+                    });
+                }
+                Ok(result?)
+            }
+        }
+    }
+
+    pub fn set_universe_level(&self, universe: UniverseId, level: f32) {
+        self.update_masters(|m| {
+            m.universes.retain(|(u, _)| *u != universe);
+            m.universes.push((universe, SavePercentage::create(level)));
+        });
+    }
+
+    pub fn set_group_level(&self, group: GroupId, level: f32) {
+        self.update_masters(|m| {
+            m.groups.retain(|(g, _)| *g != group);
+            m.groups.push((group, SavePercentage::create(level)));
+        });
+    }
+}
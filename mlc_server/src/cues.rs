@@ -0,0 +1,336 @@
+//! Playback of the cue lists, the current cues are rendered into their own runtime layer.
+
+use crate::ServiceImpl;
+use crate::programmer::render_values;
+use crate::project::Project;
+use crate::universe::{LayerFrame, RuntimeCommand, RuntimeLayer};
+use mlc_communication::services::programmer::{CuePlaybackState, ProgrammerServiceError};
+use mlc_data::project::cue::CueListId;
+use mlc_data::project::universe::UniverseId;
+use std::collections::HashMap;
+
+/// Steps to the next cue, the last cue stays active
+pub fn go(current: Option<usize>, len: usize) -> Option<usize> {
+    match current {
+        Some(i) => Some((i + 1).min(len - 1)),
+        None => Some(0),
+    }
+}
+
+/// Steps to the previous cue, a released list starts at the first cue
+pub fn back(current: Option<usize>, _len: usize) -> Option<usize> {
+    Some(current.map_or(0, |i| i.saturating_sub(1)))
+}
+
+impl ServiceImpl {
+    /// Releases all cue lists
+    pub fn reset_cue_playback(&self) {
+        self.cue_playback.send_replace(CuePlaybackState::default());
+        self.universe_runtime
+            .cmd(RuntimeCommand::ClearLayer(RuntimeLayer::Cues));
+    }
+
+    /// Moves a cue list to the cue chosen by `step` from the current cue and the number of cues,
+    /// `None` releases it.
+    pub async fn step_cue_list(
+        &self,
+        cue_list: Option<CueListId>,
+        step: impl FnOnce(Option<usize>, usize) -> Option<usize>,
+    ) -> Result<(), ProgrammerServiceError> {
+        let p = self
+            .validate_project()
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        let list = match cue_list {
+            Some(id) => p
+                .cue_lists
+                .iter()
+                .find(|l| l.id == id)
+                .ok_or(ProgrammerServiceError::UnknownCueList(id))?,
+            None => p
+                .cue_lists
+                .first()
+                .ok_or(ProgrammerServiceError::NoCueLists)?,
+        };
+
+        let current = self.cue_playback.borrow().current(list.id);
+        let next = if list.cues.is_empty() {
+            if current.is_none() {
+                return Err(ProgrammerServiceError::EmptyCueList(list.id));
+            }
+            None
+        } else {
+            step(current, list.cues.len())
+        };
+
+        self.cue_playback.send_modify(|s| {
+            s.active.retain(|(l, _)| *l != list.id);
+            if let Some(i) = next {
+                s.active.push((list.id, i));
+            }
+        });
+
+        self.update_cue_layer(&p);
+        Ok(())
+    }
+
+    pub fn update_cue_layer(&self, project: &Project) {
+        let frames = render_cues(&self.cue_playback.borrow(), project);
+        self.universe_runtime.cmd(RuntimeCommand::SetLayer {
+            layer: RuntimeLayer::Cues,
+            frames,
+        });
+    }
+}
+
+/// Renders the current cues of the running cue lists, later lists override earlier ones
+pub fn render_cues(
+    state: &CuePlaybackState,
+    project: &Project,
+) -> HashMap<UniverseId, Box<LayerFrame>> {
+    let values = state
+        .active
+        .iter()
+        .filter_map(|(list, i)| {
+            project
+                .cue_lists
+                .iter()
+                .find(|l| l.id == *list)?
+                .cues
+                .get(*i)
+        })
+        .flat_map(|cue| &cue.values)
+        .map(|v| (&v.fixture, v.attributes.as_slice()))
+        .collect::<Vec<_>>();
+
+    render_values(&values, project)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{back, go, render_cues};
+    use crate::endpoints::EndpointsManagerService;
+    use crate::misc::AdaptScopes;
+    use crate::programmer::LayerRefreshService;
+    use crate::project::create_default_project;
+    use crate::test_util::{dimmer, fixture, patch, service_obj};
+    use crate::{AServiceImpl, MlcServiceResourcesBuilder};
+    use mlc_communication::services::programmer::{
+        CuePlaybackState, ProgrammerService, ProgrammerServiceError,
+    };
+    use mlc_data::SavePercentage;
+    use mlc_data::endpoints::EndpointConfig;
+    use mlc_data::fixture::patched::PatchedFixtureId;
+    use mlc_data::fixture::patched::attribute::FixtureAttribute;
+    use mlc_data::project::cue::{Cue, CueList, CueValue};
+    use mlc_data::project::universe::{FixtureAddress, UniverseAddress, UniverseSlot};
+    use mlc_data::uuid::Uuid;
+    use std::time::Duration;
+
+    /// A cue setting the intensity of the fixture
+    fn cue(fixture: PatchedFixtureId, level: f32) -> Cue {
+        Cue {
+            id: Uuid::new_v4(),
+            name: String::new(),
+            values: vec![CueValue {
+                fixture,
+                attributes: vec![FixtureAttribute::Intensity(SavePercentage::create(level))],
+            }],
+            timecode: None,
+        }
+    }
+
+    #[test]
+    fn step_cues() {
+        assert_eq!(go(None, 3), Some(0));
+        assert_eq!(go(Some(1), 3), Some(2));
+        assert_eq!(go(Some(2), 3), Some(2));
+        assert_eq!(back(None, 3), Some(0));
+        assert_eq!(back(Some(2), 3), Some(1));
+        assert_eq!(back(Some(0), 3), Some(0));
+    }
+
+    #[test]
+    fn render_active_cues() {
+        let mut project = create_default_project();
+        let dimmers = || fixture(vec![("Dimmer", vec![dimmer()])]);
+        let first = patch(&mut project, 1, 1, dimmers());
+        let second = patch(&mut project, 1, 2, dimmers());
+        let mut base = CueList::new("Base");
+        base.cues = vec![cue(first, 1.0), cue(second, 1.0)];
+        let mut top = CueList::new("Top");
+        top.cues = vec![cue(first, 0.2)];
+        project.cue_lists = vec![base.clone(), top.clone()];
+
+        // Later lists override earlier ones
+        let state = |active| CuePlaybackState { active };
+        let frames = render_cues(&state(vec![(base.id, 0), (top.id, 0)]), &project);
+        assert_eq!(frames[&1][..2], [Some(51), None]);
+        let frames = render_cues(&state(vec![(top.id, 0), (base.id, 1)]), &project);
+        assert_eq!(frames[&1][..2], [Some(51), Some(255)]);
+        let frames = render_cues(&state(vec![(top.id, 0), (base.id, 0)]), &project);
+        assert_eq!(frames[&1][..2], [Some(255), None]);
+
+        // Unknown lists and cues render nothing
+        let frames = render_cues(&state(vec![(Uuid::new_v4(), 0), (top.id, 1)]), &project);
+        assert!(frames.is_empty());
+    }
+
+    #[tokio::test]
+    async fn step_cue_lists() {
+        let mut project = create_default_project();
+        let id = patch(
+            &mut project,
+            1,
+            1,
+            fixture(vec![("Dimmer", vec![dimmer()])]),
+        );
+        project.cue_lists.clear();
+        let (service_obj, _runtime) = service_obj(project);
+        let result = service_obj.cue_go(None).await;
+        assert!(matches!(result, Err(ProgrammerServiceError::NoCueLists)));
+
+        let empty = CueList::new("Empty");
+        let mut list = CueList::new("List");
+        list.cues = vec![cue(id, 1.0), cue(id, 0.5)];
+        service_obj.project.write().await.cue_lists = vec![empty.clone(), list.clone()];
+        let result = service_obj.cue_go(None).await;
+        assert!(matches!(result, Err(ProgrammerServiceError::EmptyCueList(l)) if l == empty.id));
+        let unknown = Uuid::new_v4();
+        let result = service_obj.cue_back(Some(unknown)).await;
+        assert!(matches!(result, Err(ProgrammerServiceError::UnknownCueList(l)) if l == unknown));
+
+        let current = || service_obj.cue_playback.borrow().current(list.id);
+        service_obj.cue_back(Some(list.id)).await.unwrap();
+        assert_eq!(current(), Some(0));
+        service_obj.cue_go(Some(list.id)).await.unwrap();
+        assert_eq!(current(), Some(1));
+        service_obj.cue_release(Some(list.id)).await.unwrap();
+        assert_eq!(current(), None);
+
+        // A running list whose cues were deleted can still be released
+        service_obj.cue_go(Some(list.id)).await.unwrap();
+        service_obj.project.write().await.cue_lists[1].cues.clear();
+        service_obj.cue_release(Some(list.id)).await.unwrap();
+        assert_eq!(current(), None);
+        assert!(service_obj.cue_go(Some(list.id)).await.is_err());
+    }
+
+    /// Waits until the simulator endpoint received the value for the channel
+    async fn simulated(service_obj: &AServiceImpl, address: usize, value: u8) {
+        let address = FixtureAddress::new(1, UniverseAddress::create(address));
+        for _ in 0..100 {
+            if service_obj.simulator.value(address.clone()) == Some(value) {
+                return;
+            }
+            // Makes sure the runtime picked up the universes once it is running
+            service_obj.adapt_notifier.notify(AdaptScopes::UNIVERSES);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!(
+            "Channel {address} is {:?} instead of {value}",
+            service_obj.simulator.value(address.clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn cues_reach_endpoints() {
+        let mut project = create_default_project();
+        let id = patch(
+            &mut project,
+            1,
+            10,
+            fixture(vec![("Dimmer", vec![dimmer()])]),
+        );
+        project
+            .endpoint_mapping
+            .endpoints
+            .insert(1, vec![EndpointConfig::Simulator]);
+
+        let (service_obj, runtime) = service_obj(project);
+        let mut services = MlcServiceResourcesBuilder::new(
+            service_obj.clone(),
+            service_obj.shutdown.clone(),
+            service_obj.adapt_notifier.clone(),
+        );
+        services.add_service(runtime);
+        services.add_service(EndpointsManagerService);
+
+        service_obj.select(vec![id]).await.unwrap();
+        for level in [0.5, 1.0] {
+            let level = FixtureAttribute::Intensity(SavePercentage::create(level));
+            service_obj.set_attribute(level).await.unwrap();
+            service_obj.record(None, String::new()).await.unwrap();
+        }
+        service_obj.clear().await.unwrap();
+        simulated(&service_obj, 10, 0).await;
+
+        service_obj.cue_go(None).await.unwrap();
+        simulated(&service_obj, 10, 127).await;
+        service_obj.cue_go(None).await.unwrap();
+        simulated(&service_obj, 10, 255).await;
+        // The last cue stays active
+        service_obj.cue_go(None).await.unwrap();
+        simulated(&service_obj, 10, 255).await;
+        service_obj.cue_back(None).await.unwrap();
+        simulated(&service_obj, 10, 127).await;
+        service_obj.cue_release(None).await.unwrap();
+        simulated(&service_obj, 10, 0).await;
+
+        service_obj.shutdown.shutdown();
+        services.wait().await;
+    }
+
+    #[tokio::test]
+    async fn layers_follow_patch() {
+        let mut project = create_default_project();
+        let dimmers = || fixture(vec![("Dimmer", vec![dimmer()])]);
+        let programmed = patch(&mut project, 1, 10, dimmers());
+        let cued = patch(&mut project, 1, 11, dimmers());
+        let mut list = CueList::new("List");
+        list.cues = vec![cue(cued, 1.0)];
+        project.cue_lists = vec![list];
+        project
+            .endpoint_mapping
+            .endpoints
+            .insert(1, vec![EndpointConfig::Simulator]);
+
+        let (service_obj, runtime) = service_obj(project);
+        let mut services = MlcServiceResourcesBuilder::new(
+            service_obj.clone(),
+            service_obj.shutdown.clone(),
+            service_obj.adapt_notifier.clone(),
+        );
+        services.add_service(runtime);
+        services.add_service(EndpointsManagerService);
+        services.add_service(LayerRefreshService);
+
+        service_obj.select(vec![programmed]).await.unwrap();
+        let level = FixtureAttribute::Intensity(SavePercentage::create(1.0));
+        service_obj.set_attribute(level).await.unwrap();
+        service_obj.cue_go(None).await.unwrap();
+        simulated(&service_obj, 10, 255).await;
+        simulated(&service_obj, 11, 255).await;
+
+        // Both fixtures move to new addresses
+        {
+            let mut p = service_obj.project.write().await;
+            let mut moved = vec![];
+            for id in [programmed, cued] {
+                moved.push(p.universes[0].fixtures.remove(&id).unwrap());
+            }
+            p.universes[0].addresses[9..11].fill(UniverseSlot::Unused);
+            patch(&mut p, 1, 20, moved.remove(0));
+            patch(&mut p, 1, 21, moved.remove(0));
+        }
+        service_obj.adapt_notifier.notify(AdaptScopes::PATCH);
+        simulated(&service_obj, 20, 255).await;
+        simulated(&service_obj, 21, 255).await;
+        simulated(&service_obj, 10, 0).await;
+        simulated(&service_obj, 11, 0).await;
+
+        service_obj.shutdown.shutdown();
+        services.wait().await;
+    }
+}
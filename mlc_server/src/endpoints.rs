@@ -1,16 +1,90 @@
+use crate::endpoints::driver_artnet::{ArtNetConfig, ArtNetDriver};
 use crate::endpoints::driver_log::LogDriver;
-use crate::endpoints::driver_sacn::SacnDriver;
+use crate::endpoints::driver_sacn::{SacnConfig, SacnDriver};
+use crate::endpoints::driver_simulator::SimulatorDriver;
+use crate::endpoints::stats::{StatsCollector, StatsHandle};
 use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
 use crate::universe::{UniverseUpdate, UniverseUpdateSubscriber};
-use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
 use futures::stream::FuturesUnordered;
 use futures::StreamExt;
-use mlc_data::endpoints::{EndpointConfig, EndpointMapping};
+use mlc_communication::remoc::rch::watch;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::endpoints::{
+    ArtNetNode, EndpointService, EndpointServiceError, EndpointStats, RdmUid, SimulatedFrame,
+};
+use mlc_data::endpoints::{EndpointConfig, EndpointMapping, EndpointSpeed};
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::universe::{FixtureAddress, UniverseId};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::select;
 use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::Notify;
+use tokio::time::Interval;
 
+mod artnet;
+mod driver_artnet;
 mod driver_log;
 mod driver_sacn;
+mod driver_simulator;
+mod stats;
+
+pub use driver_simulator::SimulatorStore;
+
+#[rtc::async_trait]
+impl EndpointService for ServiceImpl {
+    async fn stats(&self) -> Result<watch::Receiver<Vec<EndpointStats>>, EndpointServiceError> {
+        Ok(self.endpoint_stats.subscribe())
+    }
+
+    async fn simulator_frames(&self) -> Result<Vec<SimulatedFrame>, EndpointServiceError> {
+        Ok(self.simulator.frames())
+    }
+
+    async fn simulator_frame(
+        &self,
+        universe: UniverseId,
+    ) -> Result<Option<SimulatedFrame>, EndpointServiceError> {
+        Ok(self.simulator.frame(universe))
+    }
+
+    async fn simulator_value(
+        &self,
+        address: FixtureAddress,
+    ) -> Result<Option<u8>, EndpointServiceError> {
+        Ok(self.simulator.value(address))
+    }
+
+    async fn artnet_nodes(&self) -> Result<Vec<ArtNetNode>, EndpointServiceError> {
+        let discover = async {
+            let socket = artnet::bind_controller().await?;
+            let broadcast = SocketAddr::from((Ipv4Addr::BROADCAST, artnet::ARTNET_PORT));
+            artnet::discover_nodes(&socket, broadcast, artnet::DISCOVERY_TIMEOUT).await
+        };
+        discover
+            .await
+            .map_err(|e| EndpointServiceError::DiscoveryFailed(e.to_string()))
+    }
+
+    async fn artnet_rdm_devices(
+        &self,
+        node: Ipv4Addr,
+        universe: u16,
+    ) -> Result<Vec<RdmUid>, EndpointServiceError> {
+        let discover = async {
+            let socket = artnet::bind_controller().await?;
+            let node = SocketAddr::from((node, artnet::ARTNET_PORT));
+            artnet::rdm_devices(&socket, node, universe, artnet::DISCOVERY_TIMEOUT).await
+        };
+        discover
+            .await
+            .map_err(|e| EndpointServiceError::DiscoveryFailed(e.to_string()))
+    }
+}
 
 pub struct EndpointsManagerService;
 
@@ -33,19 +107,38 @@ async fn start_endpoint_manager(
 
     let mut drivers = DriverCollection {
         log: LogDriver::new(),
-        artnet: LogDriver::new(),
+        simulator: SimulatorDriver::new(service_obj.simulator.clone()),
+        artnet: ArtNetDriver::new(),
         sacn: SacnDriver::new(),
         usb: LogDriver::new(),
     };
 
-    loop {
-        adapt_endpoints(service_obj.clone(), &mapping, &mut drivers).await;
-        select! {
-            _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
-                break;
-            }
-            _ = adapt_notifier.wait(AdaptScopes::ENDPOINTS) => {
-                mapping = service_obj.project.read().await.endpoint_mapping.clone();
+    let mut stats = StatsCollector::new();
+    let mut stats_interval = tokio::time::interval(Duration::from_secs(1));
+
+    let mut applied = vec![];
+
+    'o: loop {
+        adapt_endpoints(
+            service_obj.clone(),
+            &mapping,
+            &mut applied,
+            &mut drivers,
+            &mut stats,
+        )
+        .await;
+        loop {
+            select! {
+                _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                    break 'o;
+                }
+                _ = adapt_notifier.wait(AdaptScopes::ENDPOINTS) => {
+                    mapping = service_obj.project.read().await.endpoint_mapping.clone();
+                    break;
+                }
+                _ = stats_interval.tick() => {
+                    service_obj.endpoint_stats.send(stats.snapshot()).ignore();
+                }
             }
         }
     }
@@ -55,7 +148,8 @@ async fn start_endpoint_manager(
 
 struct DriverCollection {
     log: LogDriver,
-    artnet: LogDriver,
+    simulator: SimulatorDriver,
+    artnet: ArtNetDriver,
     sacn: SacnDriver,
     usb: LogDriver,
 }
@@ -63,61 +157,329 @@ struct DriverCollection {
 impl DriverCollection {
     async fn stop_all(&mut self) {
         self.log.stop_all().await;
+        self.simulator.stop_all().await;
         self.artnet.stop_all().await;
         self.sacn.stop_all().await;
         self.usb.stop_all().await;
     }
 
-    async fn apply_config(&mut self, sub: UniverseUpdateSubscriber, config: &EndpointConfig) {
+    async fn apply_config(
+        &mut self,
+        sub: UniverseUpdateSubscriber,
+        config: &EndpointConfig,
+        stats: StatsHandle,
+    ) {
+        match config {
+            EndpointConfig::Logger => {
+                self.log.apply_config(sub, (), stats).await;
+            }
+            EndpointConfig::Simulator => {
+                self.simulator.apply_config(sub, (), stats).await;
+            }
+            EndpointConfig::ArtNet { .. } => {
+                if let Some(artnet) = ArtNetConfig::from_endpoint(config) {
+                    self.artnet.apply_config(sub, artnet, stats).await;
+                }
+            }
+            EndpointConfig::Sacn { .. } => {
+                if let Some(sacn) = SacnConfig::from_endpoint(config) {
+                    self.sacn.apply_config(sub, sacn, stats).await;
+                }
+            }
+            EndpointConfig::Usb { .. } => {
+                self.usb.apply_config(sub, (), stats).await;
+            }
+        }
+    }
+
+    async fn remove_config(&mut self, universe: UniverseId, config: &EndpointConfig) {
         match config {
             EndpointConfig::Logger => {
-                self.log.apply_config(sub, ()).await;
+                self.log.remove_config(universe, ()).await;
             }
-            EndpointConfig::ArtNet => {
-                self.artnet.apply_config(sub, ()).await;
+            EndpointConfig::Simulator => {
+                self.simulator.remove_config(universe, ()).await;
             }
-            EndpointConfig::Sacn { speed, universe } => {
-                self.sacn.apply_config(sub, (*speed, *universe)).await;
+            EndpointConfig::ArtNet { .. } => {
+                if let Some(artnet) = ArtNetConfig::from_endpoint(config) {
+                    self.artnet.remove_config(universe, artnet).await;
+                }
+            }
+            EndpointConfig::Sacn { .. } => {
+                if let Some(sacn) = SacnConfig::from_endpoint(config) {
+                    self.sacn.remove_config(universe, sacn).await;
+                }
             }
             EndpointConfig::Usb { .. } => {
-                self.usb.apply_config(sub, ()).await;
+                self.usb.remove_config(universe, ()).await;
             }
         }
     }
 }
 
+/// Brings the running endpoints in line with the mapping,
+/// endpoints that did not change keep running untouched.
 async fn adapt_endpoints(
     service_obj: AServiceImpl,
     mapping: &EndpointMapping,
+    applied: &mut Vec<(UniverseId, EndpointConfig)>,
     drivers: &mut DriverCollection,
+    stats: &mut StatsCollector,
 ) {
-    drivers.stop_all().await;
+    let target = mapping
+        .endpoints
+        .iter()
+        .flat_map(|(u, configs)| configs.iter().map(|c| (*u, c.clone())))
+        .collect::<Vec<_>>();
 
-    for (universe, configs) in &mapping.endpoints {
-        for config in configs {
-            drivers
-                .apply_config(
-                    service_obj.universe_runtime.subscribe_universe(*universe),
-                    config,
-                )
-                .await;
+    let mut added = target.clone();
+    for endpoint in applied.drain(..) {
+        match added.iter().position(|e| *e == endpoint) {
+            Some(i) => {
+                added.remove(i);
+            }
+            None => {
+                let (universe, config) = endpoint;
+                drivers.remove_config(universe, &config).await;
+                stats.unregister(universe, &config);
+            }
         }
     }
+
+    for (universe, config) in &added {
+        drivers
+            .apply_config(
+                service_obj.universe_runtime.subscribe_universe(*universe),
+                config,
+                stats.register(*universe, config),
+            )
+            .await;
+    }
+
+    *applied = target;
+}
+
+enum DriverCommand<C> {
+    Add {
+        sub: UniverseUpdateSubscriber,
+        config: C,
+        stats: StatsHandle,
+    },
+    Remove {
+        universe: UniverseId,
+        config: C,
+    },
 }
 
 trait EndpointDriver<C> {
     async fn stop_all(&mut self);
-    async fn apply_config(&mut self, sub: UniverseUpdateSubscriber, config: C);
+    async fn apply_config(&mut self, sub: UniverseUpdateSubscriber, config: C, stats: StatsHandle);
+    async fn remove_config(&mut self, universe: UniverseId, config: C);
+}
+
+/// Runs a driver without per output settings until it is shut down, `on_update` receives the
+/// updates of the subscribed universes and `on_removed` is called once a universe has no endpoint
+/// of the driver left
+async fn run_subscriptions(
+    mut rx: Receiver<DriverCommand<()>>,
+    shutdown: Arc<Notify>,
+    mut on_update: impl FnMut(UniverseId, &UniverseUpdate),
+    mut on_removed: impl FnMut(UniverseId),
+) {
+    let mut subs: Vec<UniverseUpdateSubscriber> = vec![];
+    let mut stats: Vec<StatsHandle> = vec![];
+
+    'o: loop {
+        select! {
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(DriverCommand::Add { sub, stats: handle, .. }) => {
+                        subs.push(sub);
+                        stats.push(handle);
+                    }
+                    Some(DriverCommand::Remove { universe, .. }) => {
+                        if let Some(i) = subs.iter().position(|s| s.universe() == universe) {
+                            subs.remove(i);
+                            stats.remove(i);
+                        }
+                        if !subs.iter().any(|s| s.universe() == universe) {
+                            on_removed(universe);
+                        }
+                    }
+                    None => break 'o,
+                }
+            }
+            _ = shutdown.notified() => {
+                break 'o;
+            }
+            Some((i, m)) = await_subs(&mut subs) => {
+                match m {
+                    Ok(u) => {
+                        on_update(subs[i].universe(), &u);
+                        stats[i].sent();
+                    }
+                    Err(e) => {
+                        tracing::error!("Error getting universe update: {}", e);
+                        stats[i].error(e);
+                    }
+                }
+            }
+        }
+    }
 }
 
+/// Waits for the next update of any subscriber, together with the index of the subscriber
 async fn await_subs(
     subs: &mut [UniverseUpdateSubscriber],
-) -> Option<Result<UniverseUpdate, RecvError>> {
+) -> Option<(usize, Result<UniverseUpdate, RecvError>)> {
     // info!("Waiting for universe updates");
 
     let mut f = FuturesUnordered::new();
-    for sub in subs {
-        f.push(sub.recv());
+    for (i, sub) in subs.iter_mut().enumerate() {
+        f.push(async move { (i, sub.recv().await) });
+    }
+    f.next().await
+}
+
+/// Waits for the next tick of any interval, together with the speed of the interval
+async fn await_intervals(
+    intervals: &mut HashMap<EndpointSpeed, Interval>,
+) -> Option<EndpointSpeed> {
+    let mut f = FuturesUnordered::new();
+    for (speed, interval) in intervals {
+        f.push(async move {
+            interval.tick().await;
+            *speed
+        });
     }
     f.next().await
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        DriverCollection, LogDriver, SacnDriver, SimulatorDriver, StatsCollector, adapt_endpoints,
+    };
+    use crate::endpoints::driver_artnet::ArtNetDriver;
+    use crate::misc::AdaptScopes;
+    use crate::project::create_default_project;
+    use crate::test_util::service_obj;
+    use crate::universe::{RuntimeCommand, UniverseUpdate};
+    use crate::{AServiceImpl, MlcServiceResourcesBuilder};
+    use mlc_data::endpoints::{EndpointConfig, EndpointMapping};
+    use mlc_data::project::universe::{FixtureAddress, FixtureUniverse, UniverseAddress};
+    use std::time::Duration;
+
+    /// Sets the first channel of the universe until the simulator endpoint received it
+    async fn simulate(service_obj: &AServiceImpl, universe: u16, value: u8) {
+        let address = FixtureAddress::new(universe, UniverseAddress::create(1));
+        for _ in 0..100 {
+            let update = UniverseUpdate::Single {
+                update: (address.clone(), value),
+            };
+            service_obj
+                .universe_runtime
+                .cmd(RuntimeCommand::UpdateData(update));
+            if service_obj.simulator.value(address.clone()) == Some(value) {
+                return;
+            }
+            // Makes sure the runtime picked up the universes once it is running
+            service_obj.adapt_notifier.notify(AdaptScopes::UNIVERSES);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("The simulator did not receive {value} on universe {universe}");
+    }
+
+    fn mapping(endpoints: Vec<(u16, Vec<EndpointConfig>)>) -> EndpointMapping {
+        EndpointMapping {
+            endpoints: endpoints.into_iter().collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn adapt_by_diff() {
+        let mut project = create_default_project();
+        project
+            .universes
+            .push(FixtureUniverse::new(2, "Universe 2".to_string()));
+        let (service_obj, runtime) = service_obj(project);
+        let mut services = MlcServiceResourcesBuilder::new(
+            service_obj.clone(),
+            service_obj.shutdown.clone(),
+            service_obj.adapt_notifier.clone(),
+        );
+        services.add_service(runtime);
+
+        let mut drivers = DriverCollection {
+            log: LogDriver::new(),
+            simulator: SimulatorDriver::new(service_obj.simulator.clone()),
+            artnet: ArtNetDriver::new(),
+            sacn: SacnDriver::new(),
+            usb: LogDriver::new(),
+        };
+        let mut stats = StatsCollector::new();
+        let mut applied = vec![];
+        let endpoints = |stats: &mut StatsCollector| {
+            let mut endpoints = stats
+                .snapshot()
+                .into_iter()
+                .map(|s| (s.universe, s.endpoint, s.packets_sent))
+                .collect::<Vec<_>>();
+            endpoints.sort_by_key(|(u, e, _)| (*u, e.to_string()));
+            endpoints
+        };
+
+        let first = mapping(vec![
+            (1, vec![EndpointConfig::Simulator, EndpointConfig::Logger]),
+            (2, vec![EndpointConfig::Simulator]),
+        ]);
+        adapt_endpoints(
+            service_obj.clone(),
+            &first,
+            &mut applied,
+            &mut drivers,
+            &mut stats,
+        )
+        .await;
+        simulate(&service_obj, 1, 10).await;
+        simulate(&service_obj, 2, 10).await;
+        let before = endpoints(&mut stats);
+        assert_eq!(before.len(), 3);
+        let (_, _, sent) = before
+            .iter()
+            .find(|(u, e, _)| *u == 1 && *e == EndpointConfig::Simulator)
+            .unwrap();
+        assert!(*sent > 0);
+
+        let second = mapping(vec![
+            (1, vec![EndpointConfig::Simulator]),
+            (2, vec![EndpointConfig::Logger]),
+        ]);
+        adapt_endpoints(
+            service_obj.clone(),
+            &second,
+            &mut applied,
+            &mut drivers,
+            &mut stats,
+        )
+        .await;
+        simulate(&service_obj, 1, 20).await;
+        let after = endpoints(&mut stats);
+        let configs = after
+            .iter()
+            .map(|(u, e, _)| (*u, e.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            configs,
+            [(1, EndpointConfig::Simulator), (2, EndpointConfig::Logger)]
+        );
+        // The unchanged endpoint kept running with its counters
+        assert!(after[0].2 > *sent);
+        // The removed simulator dropped its universe
+        assert_eq!(service_obj.simulator.frame(2), None);
+
+        drivers.stop_all().await;
+        service_obj.shutdown.shutdown();
+        services.wait().await;
+    }
+}
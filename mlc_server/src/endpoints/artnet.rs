@@ -0,0 +1,336 @@
+use mlc_communication::services::endpoints::{ArtNetNode, ArtNetPort, RdmUid};
+use mlc_data::project::universe::UNIVERSE_SIZE;
+use std::collections::BTreeSet;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+pub const ARTNET_PORT: u16 = 6454;
+/// Nodes have to answer an ArtPoll within this time
+pub const DISCOVERY_TIMEOUT: Duration = Duration::from_secs(3);
+
+const ID: &[u8; 8] = b"Art-Net\0";
+const PROTOCOL_VERSION: u16 = 14;
+
+const OP_POLL: u16 = 0x2000;
+const OP_POLL_REPLY: u16 = 0x2100;
+const OP_DMX: u16 = 0x5000;
+const OP_TOD_REQUEST: u16 = 0x8000;
+const OP_TOD_DATA: u16 = 0x8100;
+
+const POLL_REPLY_MIN_LEN: usize = 207;
+const TOD_DATA_HEADER_LEN: usize = 28;
+
+fn header(op: u16) -> Vec<u8> {
+    let mut packet = ID.to_vec();
+    packet.extend_from_slice(&op.to_le_bytes());
+    packet.extend_from_slice(&PROTOCOL_VERSION.to_be_bytes());
+    packet
+}
+
+fn opcode(packet: &[u8]) -> Option<u16> {
+    (packet.len() >= 10 && packet.starts_with(ID))
+        .then(|| u16::from_le_bytes([packet[8], packet[9]]))
+}
+
+fn string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+pub fn poll_packet() -> Vec<u8> {
+    let mut packet = header(OP_POLL);
+    // Flags: no reply on change, diagnostics off; DiagPriority
+    packet.extend_from_slice(&[0, 0]);
+    packet
+}
+
+/// DMX data for a Port-Address, the sequence lets nodes reorder packets, 0 disables that
+pub fn dmx_packet(port_address: u16, sequence: u8, data: &[u8; UNIVERSE_SIZE]) -> Vec<u8> {
+    let mut packet = header(OP_DMX);
+    let [net, sub_uni] = port_address.to_be_bytes();
+    // Sequence, Physical, SubUni, Net
+    packet.extend_from_slice(&[sequence, 0, sub_uni, net & 0x7F]);
+    packet.extend_from_slice(&(UNIVERSE_SIZE as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+/// Requests the full table of devices for a single Port-Address
+pub fn tod_request_packet(universe: u16) -> Vec<u8> {
+    let mut packet = header(OP_TOD_REQUEST);
+    packet.extend_from_slice(&[0; 9]);
+    let [net, address] = universe.to_be_bytes();
+    // Net, Command TodFull, AdCount
+    packet.extend_from_slice(&[net & 0x7F, 0, 1]);
+    let mut addresses = [0; 32];
+    addresses[0] = address;
+    packet.extend_from_slice(&addresses);
+    packet
+}
+
+pub fn parse_poll_reply(packet: &[u8]) -> Option<ArtNetNode> {
+    if opcode(packet)? != OP_POLL_REPLY || packet.len() < POLL_REPLY_MIN_LEN {
+        return None;
+    }
+
+    let ip = Ipv4Addr::new(packet[10], packet[11], packet[12], packet[13]);
+    let (net, sub) = (packet[18] & 0x7F, packet[19] & 0x0F);
+    let num_ports = (u16::from_be_bytes([packet[172], packet[173]]) as usize).min(4);
+
+    let ports = (0..num_ports)
+        .map(|i| {
+            let port_type = packet[174 + i];
+            let sw = if port_type & 0x80 != 0 {
+                packet[190 + i]
+            } else {
+                packet[186 + i]
+            };
+            ArtNetPort {
+                universe: (net as u16) << 8 | (sub as u16) << 4 | (sw & 0x0F) as u16,
+                input: port_type & 0x40 != 0,
+                output: port_type & 0x80 != 0,
+            }
+        })
+        .collect();
+
+    Some(ArtNetNode {
+        ip,
+        short_name: string(&packet[26..44]),
+        long_name: string(&packet[44..108]),
+        ports,
+    })
+}
+
+struct TodData {
+    universe: u16,
+    total: usize,
+    uids: Vec<RdmUid>,
+}
+
+fn parse_tod_data(packet: &[u8]) -> Option<TodData> {
+    if opcode(packet)? != OP_TOD_DATA || packet.len() < TOD_DATA_HEADER_LEN {
+        return None;
+    }
+
+    let count = packet[27] as usize;
+    let uids = packet[TOD_DATA_HEADER_LEN..]
+        .chunks_exact(6)
+        .take(count)
+        .map(|uid| RdmUid {
+            manufacturer: u16::from_be_bytes([uid[0], uid[1]]),
+            device: u32::from_be_bytes([uid[2], uid[3], uid[4], uid[5]]),
+        })
+        .collect();
+
+    Some(TodData {
+        universe: ((packet[21] & 0x7F) as u16) << 8 | packet[23] as u16,
+        total: u16::from_be_bytes([packet[24], packet[25]]) as usize,
+        uids,
+    })
+}
+
+/// Binds the Art-Net port to receive broadcast replies,
+/// falls back to any port if another application is using it.
+pub async fn bind_controller() -> io::Result<UdpSocket> {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, ARTNET_PORT)).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            tracing::warn!("Art-Net port is in use ({e}), replies may be missed");
+            UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?
+        }
+    };
+    socket.set_broadcast(true)?;
+    Ok(socket)
+}
+
+/// Binds any port to send DMX data, broadcast is allowed for endpoints sending to every node
+pub async fn bind_output() -> io::Result<UdpSocket> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket.set_broadcast(true)?;
+    Ok(socket)
+}
+
+/// Receives packets until the timeout, `handle` returns `true` once nothing more is expected
+async fn receive_until(
+    socket: &UdpSocket,
+    timeout: Duration,
+    mut handle: impl FnMut(&[u8]) -> bool,
+) -> io::Result<()> {
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0; 1024];
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+        let (len, _) = received?;
+        if handle(&buf[..len]) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub async fn discover_nodes(
+    socket: &UdpSocket,
+    target: SocketAddr,
+    timeout: Duration,
+) -> io::Result<Vec<ArtNetNode>> {
+    socket.send_to(&poll_packet(), target).await?;
+
+    let mut nodes: Vec<ArtNetNode> = vec![];
+    receive_until(socket, timeout, |packet| {
+        if let Some(node) = parse_poll_reply(packet) {
+            // Nodes with more than four ports answer once per group of ports
+            match nodes.iter_mut().find(|n| n.ip == node.ip) {
+                Some(known) => known.ports.extend(node.ports),
+                None => nodes.push(node),
+            }
+        }
+        false
+    })
+    .await?;
+
+    Ok(nodes)
+}
+
+pub async fn rdm_devices(
+    socket: &UdpSocket,
+    node: SocketAddr,
+    universe: u16,
+    timeout: Duration,
+) -> io::Result<Vec<RdmUid>> {
+    socket.send_to(&tod_request_packet(universe), node).await?;
+
+    let mut uids = BTreeSet::new();
+    receive_until(socket, timeout, |packet| {
+        let Some(data) = parse_tod_data(packet).filter(|d| d.universe == universe) else {
+            return false;
+        };
+        uids.extend(data.uids);
+        uids.len() >= data.total
+    })
+    .await?;
+
+    Ok(uids.into_iter().collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn poll_reply(ip: [u8; 4], name: &str, universes: &[u8]) -> Vec<u8> {
+        let mut packet = header(OP_POLL_REPLY);
+        packet.resize(239, 0);
+        packet[10..14].copy_from_slice(&ip);
+        packet[18] = 1;
+        packet[19] = 2;
+        packet[26..26 + name.len()].copy_from_slice(name.as_bytes());
+        packet[44..44 + name.len()].copy_from_slice(name.as_bytes());
+        packet[173] = universes.len() as u8;
+        for (i, u) in universes.iter().enumerate() {
+            packet[174 + i] = 0x80;
+            packet[190 + i] = *u;
+        }
+        packet
+    }
+
+    fn tod_data(universe: u16, total: u16, uids: &[(u16, u32)]) -> Vec<u8> {
+        let mut packet = header(OP_TOD_DATA);
+        packet.resize(TOD_DATA_HEADER_LEN, 0);
+        packet[21] = (universe >> 8) as u8;
+        packet[23] = universe as u8;
+        packet[24..26].copy_from_slice(&total.to_be_bytes());
+        packet[27] = uids.len() as u8;
+        for (manufacturer, device) in uids {
+            packet.extend_from_slice(&manufacturer.to_be_bytes());
+            packet.extend_from_slice(&device.to_be_bytes());
+        }
+        packet
+    }
+
+    /// Answers like a two port node with two RDM devices split over two packets
+    async fn responder() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0; 1024];
+            loop {
+                let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+                let replies = match opcode(&buf[..len]) {
+                    Some(OP_POLL) => vec![poll_reply([10, 0, 0, 7], "Pixel Node", &[3, 4])],
+                    Some(OP_TOD_REQUEST) => vec![
+                        tod_data(0x0123, 2, &[(0x7FF0, 1)]),
+                        tod_data(0x0123, 2, &[(0x4D4C, 0xC0FFEE)]),
+                    ],
+                    _ => vec![],
+                };
+                for reply in replies {
+                    socket.send_to(&reply, from).await.unwrap();
+                }
+            }
+        });
+        addr
+    }
+
+    #[test]
+    fn dmx_packet_layout() {
+        let mut data = [0; UNIVERSE_SIZE];
+        data[0] = 255;
+        data[511] = 7;
+        let packet = dmx_packet(0x0123, 42, &data);
+        assert_eq!(packet.len(), 18 + UNIVERSE_SIZE);
+        assert_eq!(opcode(&packet), Some(OP_DMX));
+        // Protocol version, sequence, physical, SubUni, Net, length
+        assert_eq!(&packet[10..18], &[0, 14, 42, 0, 0x23, 0x01, 0x02, 0x00]);
+        assert_eq!(packet[18], 255);
+        assert_eq!(packet[18 + 511], 7);
+    }
+
+    #[tokio::test]
+    async fn discover_local_node() {
+        let node = responder().await;
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let nodes = discover_nodes(&socket, node, Duration::from_millis(200))
+            .await
+            .unwrap();
+        assert_eq!(
+            nodes,
+            vec![ArtNetNode {
+                ip: Ipv4Addr::new(10, 0, 0, 7),
+                short_name: "Pixel Node".to_string(),
+                long_name: "Pixel Node".to_string(),
+                ports: vec![
+                    ArtNetPort {
+                        universe: 0x0123,
+                        input: false,
+                        output: true,
+                    },
+                    ArtNetPort {
+                        universe: 0x0124,
+                        input: false,
+                        output: true,
+                    },
+                ],
+            }]
+        );
+
+        let devices = rdm_devices(&socket, node, 0x0123, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(
+            devices,
+            vec![
+                RdmUid {
+                    manufacturer: 0x4D4C,
+                    device: 0xC0FFEE,
+                },
+                RdmUid {
+                    manufacturer: 0x7FF0,
+                    device: 1,
+                },
+            ]
+        );
+        assert_eq!(devices[0].to_string(), "4D4C:00C0FFEE");
+    }
+}
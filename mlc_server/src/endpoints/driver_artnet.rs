@@ -0,0 +1,273 @@
+use crate::endpoints::artnet::{self, ARTNET_PORT};
+use crate::endpoints::stats::StatsHandle;
+use crate::endpoints::{DriverCommand, EndpointDriver, await_intervals, await_subs};
+use crate::universe::{UniverseUpdate, UniverseUpdateSubscriber};
+use mlc_data::endpoints::{EndpointConfig, EndpointSpeed};
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::universe::{UNIVERSE_SIZE, UniverseId};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::select;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::JoinHandle;
+use tokio::time::Interval;
+
+/// Settings of a single Art-Net output
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtNetConfig {
+    node: Ipv4Addr,
+    port_address: u16,
+    speed: EndpointSpeed,
+}
+
+impl ArtNetConfig {
+    pub fn from_endpoint(config: &EndpointConfig) -> Option<Self> {
+        let EndpointConfig::ArtNet {
+            node,
+            port_address,
+            speed,
+        } = config
+        else {
+            return None;
+        };
+
+        Some(Self {
+            node: *node,
+            port_address: *port_address,
+            speed: *speed,
+        })
+    }
+}
+
+struct Output {
+    universe: UniverseId,
+    config: ArtNetConfig,
+    stats: StatsHandle,
+    /// Sequence number of the last packet, counting from 1 and skipping 0
+    sequence: u8,
+}
+
+pub struct ArtNetDriver {
+    handle: Option<JoinHandle<()>>,
+    tx: Sender<DriverCommand<ArtNetConfig>>,
+    shutdown_notify: Arc<Notify>,
+    /// Port the nodes listen on
+    port: u16,
+}
+
+impl ArtNetDriver {
+    pub fn new() -> Self {
+        let (tx, _) = tokio::sync::mpsc::channel::<DriverCommand<ArtNetConfig>>(1);
+        Self {
+            handle: None,
+            tx,
+            shutdown_notify: Arc::new(Notify::new()),
+            port: ARTNET_PORT,
+        }
+    }
+}
+
+impl EndpointDriver<ArtNetConfig> for ArtNetDriver {
+    async fn stop_all(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.shutdown_notify.notify_one();
+            let _ = handle.await;
+        }
+    }
+
+    async fn apply_config(
+        &mut self,
+        sub: UniverseUpdateSubscriber,
+        config: ArtNetConfig,
+        stats: StatsHandle,
+    ) {
+        self.handle.get_or_insert_with(|| {
+            let (tx, rx) = tokio::sync::mpsc::channel::<DriverCommand<ArtNetConfig>>(10);
+            self.tx = tx;
+            tokio::spawn(artnet_runner(rx, self.shutdown_notify.clone(), self.port))
+        });
+        if let Err(e) = self
+            .tx
+            .send(DriverCommand::Add { sub, config, stats })
+            .await
+        {
+            tracing::error!("Art-Net driver is not running");
+            if let DriverCommand::Add { stats, .. } = e.0 {
+                stats.error("Driver is not running");
+            }
+        }
+    }
+
+    async fn remove_config(&mut self, universe: UniverseId, config: ArtNetConfig) {
+        if self.handle.is_some() {
+            self.tx
+                .send(DriverCommand::Remove { universe, config })
+                .await
+                .debug_ignore();
+        }
+    }
+}
+
+fn write_update(values: &mut [u8; UNIVERSE_SIZE], update: UniverseUpdate) {
+    match update {
+        UniverseUpdate::Single { update } => {
+            values[update.0.address().take() - 1] = update.1;
+        }
+        UniverseUpdate::Many { updates } => {
+            for update in updates {
+                values[update.0.address().take() - 1] = update.1;
+            }
+        }
+        UniverseUpdate::Entire { values: entire, .. } => {
+            values.copy_from_slice(&*entire);
+        }
+    }
+}
+
+async fn artnet_runner(
+    mut rx: Receiver<DriverCommand<ArtNetConfig>>,
+    shutdown: Arc<Notify>,
+    port: u16,
+) {
+    let socket = artnet::bind_output().await;
+    if let Err(e) = &socket {
+        tracing::error!("Failed to bind the Art-Net output socket: {e}");
+    }
+    let mut subs: Vec<UniverseUpdateSubscriber> = vec![];
+    let mut outputs: Vec<Output> = vec![];
+    let mut update_timers: HashMap<EndpointSpeed, Interval> = HashMap::new();
+    let mut cache: HashMap<UniverseId, [u8; UNIVERSE_SIZE]> = HashMap::new();
+
+    'o: loop {
+        select! {
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(DriverCommand::Add { sub, config, stats }) => {
+                        if let Err(e) = &socket {
+                            stats.error(format!("Art-Net socket could not be bound: {e}"));
+                            continue;
+                        }
+                        update_timers
+                            .entry(config.speed)
+                            .or_insert_with(|| tokio::time::interval(config.speed.duration()));
+                        cache.entry(sub.universe()).or_insert([0; UNIVERSE_SIZE]);
+                        outputs.push(Output {
+                            universe: sub.universe(),
+                            config,
+                            stats,
+                            sequence: 0,
+                        });
+                        subs.push(sub);
+                    }
+                    Some(DriverCommand::Remove { universe, config }) => {
+                        let Some(i) = outputs
+                            .iter()
+                            .position(|o| o.universe == universe && o.config == config)
+                        else {
+                            continue;
+                        };
+                        outputs.remove(i);
+                        if !outputs.iter().any(|o| o.config.speed == config.speed) {
+                            update_timers.remove(&config.speed);
+                        }
+                        if let Some(i) = subs.iter().position(|s| s.universe() == universe) {
+                            subs.remove(i);
+                        }
+                        if !subs.iter().any(|s| s.universe() == universe) {
+                            cache.remove(&universe);
+                        }
+                    }
+                    None => break 'o,
+                }
+            }
+            _ = shutdown.notified() => {
+                break 'o;
+            }
+            Some(speed) = await_intervals(&mut update_timers) => {
+                let Ok(socket) = &socket else {
+                    continue;
+                };
+                for output in outputs.iter_mut().filter(|o| o.config.speed == speed) {
+                    let Some(data) = cache.get(&output.universe) else {
+                        continue;
+                    };
+                    output.sequence = output.sequence.checked_add(1).unwrap_or(1);
+                    let packet = artnet::dmx_packet(
+                        output.config.port_address,
+                        output.sequence,
+                        data,
+                    );
+                    let target = SocketAddr::from((output.config.node, port));
+                    match socket.send_to(&packet, target).await {
+                        Ok(_) => output.stats.sent(),
+                        Err(e) => output.stats.error(e),
+                    }
+                }
+            }
+            Some((i, m)) = await_subs(&mut subs) => {
+                match m {
+                    Ok(u) => {
+                        if let Some(values) = cache.get_mut(&subs[i].universe()) {
+                            write_update(values, u);
+                        }
+                    }
+                    Err(e) => {
+                        tracing::error!("Error getting universe update: {}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ArtNetConfig, ArtNetDriver};
+    use crate::endpoints::EndpointDriver;
+    use crate::endpoints::stats::StatsCollector;
+    use crate::universe::UniverseRuntimeService;
+    use mlc_data::endpoints::{EndpointConfig, EndpointSpeed};
+    use mlc_data::project::universe::UNIVERSE_SIZE;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn send_to_node() {
+        let node = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut driver = ArtNetDriver {
+            port: node.local_addr().unwrap().port(),
+            ..ArtNetDriver::new()
+        };
+        let (_, runtime) = UniverseRuntimeService::create();
+        let mut stats = StatsCollector::new();
+
+        let endpoint =
+            EndpointConfig::artnet(Ipv4Addr::LOCALHOST, 0x0102, EndpointSpeed::Custom(10));
+        let config = ArtNetConfig::from_endpoint(&endpoint).unwrap();
+        let handle = stats.register(1, &endpoint);
+        driver
+            .apply_config(runtime.subscribe_universe(1), config.clone(), handle)
+            .await;
+
+        let mut buf = [0; 1024];
+        let mut sequences = vec![];
+        for _ in 0..2 {
+            let read = node.recv_from(&mut buf);
+            let (len, _) = tokio::time::timeout(Duration::from_secs(1), read)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(len, 18 + UNIVERSE_SIZE);
+            // SubUni and Net of the Port-Address
+            assert_eq!(&buf[14..16], &[0x02, 0x01]);
+            sequences.push(buf[12]);
+        }
+        assert_eq!(sequences, [1, 2]);
+
+        driver.remove_config(1, config).await;
+        driver.stop_all().await;
+    }
+}
@@ -1,10 +1,9 @@
-use crate::endpoints::{await_subs, EndpointDriver};
-use crate::universe::{UniverseUpdate, UniverseUpdateSubscriber};
-use futures::stream::FuturesUnordered;
-use futures::StreamExt;
+use crate::endpoints::stats::StatsHandle;
+use crate::endpoints::{run_subscriptions, DriverCommand, EndpointDriver};
+use crate::universe::UniverseUpdateSubscriber;
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::universe::UniverseId;
 use std::sync::Arc;
-use tokio::select;
-use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Notify;
@@ -13,13 +12,13 @@ use tracing::info;
 
 pub struct LogDriver {
     handle: Option<JoinHandle<()>>,
-    tx: Sender<UniverseUpdateSubscriber>,
+    tx: Sender<DriverCommand<()>>,
     shutdown_notify: Arc<Notify>,
 }
 
 impl LogDriver {
     pub fn new() -> Self {
-        let (tx, _) = tokio::sync::mpsc::channel::<UniverseUpdateSubscriber>(1);
+        let (tx, _) = tokio::sync::mpsc::channel::<DriverCommand<()>>(1);
         Self {
             handle: None,
             tx,
@@ -36,37 +35,41 @@ impl EndpointDriver<()> for LogDriver {
         }
     }
 
-    async fn apply_config(&mut self, sub: UniverseUpdateSubscriber, _config: ()) {
+    async fn apply_config(
+        &mut self,
+        sub: UniverseUpdateSubscriber,
+        config: (),
+        stats: StatsHandle,
+    ) {
         self.handle.get_or_insert_with(|| {
-            let (tx, rx) = tokio::sync::mpsc::channel::<UniverseUpdateSubscriber>(10);
+            let (tx, rx) = tokio::sync::mpsc::channel::<DriverCommand<()>>(10);
             self.tx = tx;
             tokio::spawn(log_runner(rx, self.shutdown_notify.clone()))
         });
-        self.tx.send(sub).await.expect("Why not!");
+        if let Err(e) = self.tx.send(DriverCommand::Add { sub, config, stats }).await {
+            tracing::error!("Log driver is not running");
+            if let DriverCommand::Add { stats, .. } = e.0 {
+                stats.error("Driver is not running");
+            }
+        }
     }
-}
 
-async fn log_runner(mut rx: Receiver<UniverseUpdateSubscriber>, shutdown: Arc<Notify>) {
-    let mut subs = vec![];
-
-    'o: loop {
-        select! {
-            sub = rx.recv() => {
-                subs.push(sub.unwrap());
-            }
-            _ = shutdown.notified() => {
-                break 'o;
-            }
-            Some(m) = await_subs(&mut subs) => {
-                match m {
-                    Ok(u) => {
-                        info!("Universe update: {:?}", u);
-                    }
-                    Err(e) => {
-                        tracing::error!("Error getting universe update: {}", e);
-                    }
-                }
-            }
+    async fn remove_config(&mut self, universe: UniverseId, config: ()) {
+        if self.handle.is_some() {
+            self.tx
+                .send(DriverCommand::Remove { universe, config })
+                .await
+                .debug_ignore();
         }
     }
 }
+
+async fn log_runner(rx: Receiver<DriverCommand<()>>, shutdown: Arc<Notify>) {
+    run_subscriptions(
+        rx,
+        shutdown,
+        |_, u| info!("Universe update: {:?}", u),
+        |_| {},
+    )
+    .await;
+}
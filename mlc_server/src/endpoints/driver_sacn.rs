@@ -1,30 +1,133 @@
-use crate::endpoints::{await_subs, EndpointDriver};
+use crate::endpoints::stats::StatsHandle;
+use crate::endpoints::{await_intervals, await_subs, DriverCommand, EndpointDriver};
 use crate::universe::{UniverseUpdate, UniverseUpdateSubscriber};
-use futures::stream::FuturesUnordered;
-use futures::StreamExt;
-use mlc_data::endpoints::EndpointSpeed;
+use mlc_data::endpoints::{EndpointConfig, EndpointSpeed, DEFAULT_SACN_SOURCE_NAME};
+use mlc_data::misc::ErrIgnore;
 use mlc_data::project::universe::{UniverseId, UNIVERSE_SIZE};
-use std::collections::HashMap;
+use sacn::source::SacnSource;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::select;
-use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::Notify;
 use tokio::task::JoinHandle;
 use tokio::time::Interval;
-use tracing::info;
+
+/// Settings of a single sACN output universe
+#[derive(Debug, Clone, PartialEq)]
+pub struct SacnConfig {
+    universe: u16,
+    speed: EndpointSpeed,
+    priority: u8,
+    source: SourceKey,
+    unicast: Vec<SocketAddr>,
+    sync_universe: Option<u16>,
+}
+
+/// Name and preview mode are set per source, endpoints differing in them need their own
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SourceKey {
+    name: String,
+    preview: bool,
+}
+
+impl SacnConfig {
+    pub fn from_endpoint(config: &EndpointConfig) -> Option<Self> {
+        let EndpointConfig::Sacn {
+            universe,
+            speed,
+            priority,
+            source_name,
+            unicast,
+            preview,
+            sync_universe,
+        } = config
+        else {
+            return None;
+        };
+
+        Some(Self {
+            universe: *universe,
+            speed: *speed,
+            priority: *priority,
+            source: SourceKey {
+                name: source_name
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_SACN_SOURCE_NAME.to_string()),
+                preview: *preview,
+            },
+            unicast: unicast.clone(),
+            sync_universe: *sync_universe,
+        })
+    }
+
+    /// Unicast destinations or `None` for multicast
+    fn destinations(&self) -> Vec<Option<SocketAddr>> {
+        if self.unicast.is_empty() {
+            vec![None]
+        } else {
+            self.unicast.iter().copied().map(Some).collect()
+        }
+    }
+}
+
+/// Start code of dmx data, used for the stream termination packets
+const DMX_START_CODE: u8 = 0;
+
+type SacnResult<T> = sacn::error::errors::Result<T>;
+
+/// The calls of the runner on a sACN source, the tests record them instead of sending
+trait SacnOutput: Send {
+    fn register_universe(&mut self, universe: u16) -> SacnResult<()>;
+    fn send(
+        &mut self,
+        universe: u16,
+        data: &[u8],
+        priority: u8,
+        dst: Option<SocketAddr>,
+        sync_universe: Option<u16>,
+    ) -> SacnResult<()>;
+    fn send_sync_packet(&mut self, universe: u16, dst: Option<SocketAddr>) -> SacnResult<()>;
+    /// Sends the stream termination packets and deregisters the universe
+    fn terminate_stream(&mut self, universe: u16) -> SacnResult<()>;
+}
+
+impl SacnOutput for SacnSource {
+    fn register_universe(&mut self, universe: u16) -> SacnResult<()> {
+        SacnSource::register_universe(self, universe)
+    }
+
+    fn send(
+        &mut self,
+        universe: u16,
+        data: &[u8],
+        priority: u8,
+        dst: Option<SocketAddr>,
+        sync_universe: Option<u16>,
+    ) -> SacnResult<()> {
+        SacnSource::send(self, &[universe], data, Some(priority), dst, sync_universe)
+    }
+
+    fn send_sync_packet(&mut self, universe: u16, dst: Option<SocketAddr>) -> SacnResult<()> {
+        SacnSource::send_sync_packet(self, universe, dst)
+    }
+
+    fn terminate_stream(&mut self, universe: u16) -> SacnResult<()> {
+        SacnSource::terminate_stream(self, universe, DMX_START_CODE)
+    }
+}
 
 pub struct SacnDriver {
     handle: Option<JoinHandle<()>>,
-    tx: Sender<(UniverseUpdateSubscriber, (EndpointSpeed, u16))>,
+    tx: Sender<DriverCommand<SacnConfig>>,
     shutdown_notify: Arc<Notify>,
 }
 
 impl SacnDriver {
     pub fn new() -> Self {
-        let (tx, _) =
-            tokio::sync::mpsc::channel::<(UniverseUpdateSubscriber, (EndpointSpeed, u16))>(1);
+        let (tx, _) = tokio::sync::mpsc::channel::<DriverCommand<SacnConfig>>(1);
         Self {
             handle: None,
             tx,
@@ -33,61 +136,190 @@ impl SacnDriver {
     }
 }
 
-impl EndpointDriver<(EndpointSpeed, u16)> for SacnDriver {
+impl EndpointDriver<SacnConfig> for SacnDriver {
     async fn stop_all(&mut self) {
         if let Some(handle) = self.handle.take() {
-            handle.abort();
+            self.shutdown_notify.notify_one();
+            let _ = handle.await;
         }
     }
 
-    async fn apply_config(&mut self, sub: UniverseUpdateSubscriber, config: (EndpointSpeed, u16)) {
+    async fn apply_config(
+        &mut self,
+        sub: UniverseUpdateSubscriber,
+        config: SacnConfig,
+        stats: StatsHandle,
+    ) {
         self.handle.get_or_insert_with(|| {
-            let (tx, rx) =
-                tokio::sync::mpsc::channel::<(UniverseUpdateSubscriber, (EndpointSpeed, u16))>(10);
+            let (tx, rx) = tokio::sync::mpsc::channel::<DriverCommand<SacnConfig>>(10);
             self.tx = tx;
-            tokio::spawn(sacn_runner(rx, self.shutdown_notify.clone()))
+            tokio::spawn(sacn_runner(rx, self.shutdown_notify.clone(), create_source))
         });
-        self.tx.send((sub, config)).await.expect("Why not!");
+        if let Err(e) = self.tx.send(DriverCommand::Add { sub, config, stats }).await {
+            tracing::error!("sACN driver is not running");
+            if let DriverCommand::Add { stats, .. } = e.0 {
+                stats.error("Driver is not running");
+            }
+        }
+    }
+
+    async fn remove_config(&mut self, universe: UniverseId, config: SacnConfig) {
+        if self.handle.is_some() {
+            self.tx
+                .send(DriverCommand::Remove { universe, config })
+                .await
+                .debug_ignore();
+        }
     }
 }
 
-async fn sacn_runner(
-    mut rx: Receiver<(UniverseUpdateSubscriber, (EndpointSpeed, u16))>,
+fn create_source(key: &SourceKey) -> SacnResult<SacnSource> {
+    let mut source = SacnSource::new_v4(&key.name)?;
+    source.set_is_sending_discovery(true);
+    source.set_preview_mode(key.preview)?;
+    Ok(source)
+}
+
+async fn sacn_runner<S: SacnOutput>(
+    mut rx: Receiver<DriverCommand<SacnConfig>>,
     shutdown: Arc<Notify>,
+    create_source: impl Fn(&SourceKey) -> SacnResult<S>,
 ) {
-    let mut subs = vec![];
-    let mut mapping: HashMap<u16, UniverseId> = HashMap::new();
+    let mut subs: Vec<UniverseUpdateSubscriber> = vec![];
+    let mut sources: HashMap<SourceKey, S> = HashMap::new();
+    let mut mapping: HashMap<u16, (UniverseId, SacnConfig, StatsHandle)> = HashMap::new();
     let mut update_intervals: HashMap<EndpointSpeed, Vec<u16>> = HashMap::new();
     let mut update_timers: HashMap<EndpointSpeed, Interval> = HashMap::new();
     let mut cache: HashMap<UniverseId, [u8; UNIVERSE_SIZE + 1]> = HashMap::new();
 
-    let mut dmx_source = sacn::source::SacnSource::new_v4("MLC Controller").unwrap();
-    dmx_source.set_is_sending_discovery(true);
-
     'o: loop {
         select! {
-            sub = rx.recv() => {
-                let (sub, (speed, id)) = sub.unwrap();
-                update_intervals.entry(speed).or_default().push(id);
-                *mapping.entry(id).or_insert(0) = sub.universe();
-                cache.entry(sub.universe()).or_insert_with(|| [0; UNIVERSE_SIZE + 1]);
-                update_timers.entry(speed).or_insert_with(|| tokio::time::interval(speed.duration()));
-                dmx_source.register_universe(id).unwrap();
-
-                subs.push(sub);
+            cmd = rx.recv() => {
+                match cmd {
+                    Some(DriverCommand::Add { sub, config, stats }) => {
+                        let id = config.universe;
+                        let source = match sources.entry(config.source.clone()) {
+                            Entry::Occupied(e) => e.into_mut(),
+                            Entry::Vacant(e) => match create_source(e.key()) {
+                                Ok(source) => e.insert(source),
+                                Err(err) => {
+                                    tracing::error!("Failed to create sACN source: {err}");
+                                    stats.error(format!("sACN source could not be created: {err}"));
+                                    continue;
+                                }
+                            },
+                        };
+                        let registered = source.register_universe(id).and_then(|_| {
+                            config.sync_universe.map_or(Ok(()), |s| source.register_universe(s))
+                        });
+                        if let Err(e) = registered {
+                            tracing::error!("Failed to register sACN universe {id}: {e}");
+                            stats.error(e);
+                            continue;
+                        }
+
+                        update_intervals.entry(config.speed).or_default().push(id);
+                        update_timers
+                            .entry(config.speed)
+                            .or_insert_with(|| tokio::time::interval(config.speed.duration()));
+                        cache.entry(sub.universe()).or_insert_with(|| [0; UNIVERSE_SIZE + 1]);
+                        mapping.insert(id, (sub.universe(), config, stats));
+
+                        subs.push(sub);
+                    }
+                    Some(DriverCommand::Remove { universe, config }) => {
+                        let id = config.universe;
+                        let applied = mapping.get(&id);
+                        if !applied.is_some_and(|(u, c, _)| *u == universe && *c == config) {
+                            continue;
+                        }
+                        mapping.remove(&id);
+                        if let Some(source) = sources.get_mut(&config.source) {
+                            if let Err(e) = source.terminate_stream(id) {
+                                tracing::error!("Failed to terminate sACN universe {id}: {e}");
+                            }
+                            // Terminating deregisters the universe, it may still be a sync universe
+                            let syncs = mapping
+                                .values()
+                                .filter(|(_, c, _)| c.source == config.source)
+                                .filter_map(|(_, c, _)| c.sync_universe);
+                            for sync in syncs {
+                                source.register_universe(sync).debug_ignore();
+                            }
+                        }
+                        if !mapping.values().any(|(_, c, _)| c.source == config.source) {
+                            sources.remove(&config.source);
+                        }
+
+                        if let Some(ids) = update_intervals.get_mut(&config.speed) {
+                            ids.retain(|i| *i != id);
+                            if ids.is_empty() {
+                                update_intervals.remove(&config.speed);
+                                update_timers.remove(&config.speed);
+                            }
+                        }
+                        if let Some(i) = subs.iter().position(|s| s.universe() == universe) {
+                            subs.remove(i);
+                        }
+                        if !subs.iter().any(|s| s.universe() == universe) {
+                            cache.remove(&universe);
+                        }
+                    }
+                    None => break 'o,
+                }
             }
             _ = shutdown.notified() => {
                 break 'o;
             }
-            Some(speed) = await_times(&mut update_timers) => {
-                let to_update = &update_intervals[&speed];
-                for id in to_update {
-                    let universe = mapping[id];
-                    let data = &cache[&universe];
-                    dmx_source.send(&[*id], data, None, None, None).unwrap();
+            Some(speed) = await_intervals(&mut update_timers) => {
+                let mut syncs: HashMap<(SourceKey, u16), HashSet<Option<SocketAddr>>> =
+                    HashMap::new();
+
+                for id in update_intervals.get(&speed).into_iter().flatten() {
+                    let Some((universe, config, stats)) = mapping.get(id) else {
+                        continue;
+                    };
+                    let (Some(data), Some(source)) =
+                        (cache.get(universe), sources.get_mut(&config.source))
+                    else {
+                        continue;
+                    };
+
+                    let destinations = config.destinations();
+                    let mut result = Ok(());
+                    for dst in &destinations {
+                        if let Err(e) =
+                            source.send(*id, data, config.priority, *dst, config.sync_universe)
+                        {
+                            result = Err(e);
+                        }
+                    }
+                    match result {
+                        Ok(_) => stats.sent(),
+                        Err(e) => stats.error(e),
+                    }
+
+                    if let Some(sync) = config.sync_universe {
+                        syncs
+                            .entry((config.source.clone(), sync))
+                            .or_default()
+                            .extend(destinations);
+                    }
+                }
+
+                // The receivers hold the data back until the sync packet of its universe arrives
+                for ((key, sync), destinations) in syncs {
+                    let Some(source) = sources.get_mut(&key) else {
+                        continue;
+                    };
+                    for dst in destinations {
+                        if let Err(e) = source.send_sync_packet(sync, dst) {
+                            tracing::error!("Failed to send sACN sync packet for {sync}: {e}");
+                        }
+                    }
                 }
             }
-            Some(m) = await_subs(&mut subs) => {
+            Some((_, m)) = await_subs(&mut subs) => {
                 match m {
                     Ok(u) => {
                         match u {
@@ -105,7 +337,7 @@ async fn sacn_runner(
                             }
                             UniverseUpdate::Entire{ universe, values } => {
                                 if let Some(universe) = cache.get_mut(&universe) {
-                                    universe[0..].copy_from_slice(&*values);
+                                    universe[1..].copy_from_slice(&*values);
                                 }
                             }
                         }
@@ -117,17 +349,269 @@ async fn sacn_runner(
             }
         }
     }
+
+    // Terminate every stream properly instead of letting the receivers time out
+    for (id, (_, config, _)) in &mapping {
+        if let Some(source) = sources.get_mut(&config.source)
+            && let Err(e) = source.terminate_stream(*id)
+        {
+            tracing::error!("Failed to terminate sACN universe {id}: {e}");
+        }
+    }
 }
 
-async fn await_times(subs: &mut HashMap<EndpointSpeed, Interval>) -> Option<EndpointSpeed> {
-    // info!("Waiting for universe updates");
+#[cfg(test)]
+mod test {
+    use super::{SacnConfig, SacnOutput, SacnResult, sacn_runner};
+    use crate::endpoints::DriverCommand;
+    use crate::endpoints::stats::StatsCollector;
+    use crate::universe::{UniverseRuntimeController, UniverseRuntimeService};
+    use mlc_data::endpoints::{EndpointConfig, EndpointSpeed};
+    use mlc_data::project::universe::UniverseId;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::sync::Notify;
+    use tokio::sync::mpsc::Sender;
+    use tokio::task::JoinHandle;
 
-    let mut f = FuturesUnordered::new();
-    for (speed, interval) in subs {
-        f.push(async move {
-            let _ = interval.tick().await;
-            speed.clone()
-        });
+    #[derive(Debug, Clone, PartialEq)]
+    enum Call {
+        Register(u16),
+        Send {
+            universe: u16,
+            dst: Option<SocketAddr>,
+            sync: Option<u16>,
+        },
+        Sync {
+            universe: u16,
+            dst: Option<SocketAddr>,
+        },
+        Terminate(u16),
+    }
+
+    /// The calls of all sources in order
+    #[derive(Debug, Clone, Default)]
+    struct Calls(Arc<Mutex<Vec<Call>>>);
+
+    impl Calls {
+        fn push(&self, call: Call) -> SacnResult<()> {
+            self.0.lock().unwrap().push(call);
+            Ok(())
+        }
+
+        /// Waits until a call was recorded and returns all calls until then
+        async fn wait_for(&self, call: Call) -> Vec<Call> {
+            for _ in 0..200 {
+                let calls = self.0.lock().unwrap().clone();
+                if calls.contains(&call) {
+                    return calls;
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            panic!("{call:?} was not called");
+        }
+    }
+
+    impl SacnOutput for Calls {
+        fn register_universe(&mut self, universe: u16) -> SacnResult<()> {
+            self.push(Call::Register(universe))
+        }
+
+        fn send(
+            &mut self,
+            universe: u16,
+            _: &[u8],
+            _: u8,
+            dst: Option<SocketAddr>,
+            sync: Option<u16>,
+        ) -> SacnResult<()> {
+            self.push(Call::Send {
+                universe,
+                dst,
+                sync,
+            })
+        }
+
+        fn send_sync_packet(&mut self, universe: u16, dst: Option<SocketAddr>) -> SacnResult<()> {
+            self.push(Call::Sync { universe, dst })
+        }
+
+        fn terminate_stream(&mut self, universe: u16) -> SacnResult<()> {
+            self.push(Call::Terminate(universe))
+        }
+    }
+
+    /// A runner recording the calls of its sources
+    struct Runner {
+        calls: Calls,
+        tx: Sender<DriverCommand<SacnConfig>>,
+        runtime: UniverseRuntimeController,
+        stats: StatsCollector,
+        shutdown: Arc<Notify>,
+        handle: JoinHandle<()>,
+    }
+
+    impl Runner {
+        fn start() -> Self {
+            let calls = Calls::default();
+            let (tx, rx) = tokio::sync::mpsc::channel(10);
+            let shutdown = Arc::new(Notify::new());
+            let sources = calls.clone();
+            let handle = tokio::spawn(sacn_runner(rx, shutdown.clone(), move |_| {
+                Ok(sources.clone())
+            }));
+            Self {
+                calls,
+                tx,
+                runtime: UniverseRuntimeService::create().1,
+                stats: StatsCollector::new(),
+                shutdown,
+                handle,
+            }
+        }
+
+        async fn add(&mut self, universe: UniverseId, endpoint: &EndpointConfig) {
+            let cmd = DriverCommand::Add {
+                sub: self.runtime.subscribe_universe(universe),
+                config: SacnConfig::from_endpoint(endpoint).unwrap(),
+                stats: self.stats.register(universe, endpoint),
+            };
+            self.tx.send(cmd).await.unwrap();
+        }
+
+        async fn remove(&self, universe: UniverseId, endpoint: &EndpointConfig) {
+            let config = SacnConfig::from_endpoint(endpoint).unwrap();
+            let cmd = DriverCommand::Remove { universe, config };
+            self.tx.send(cmd).await.unwrap();
+        }
+
+        async fn stop(self) -> Vec<Call> {
+            self.shutdown.notify_one();
+            self.handle.await.unwrap();
+            self.calls.0.lock().unwrap().clone()
+        }
+    }
+
+    fn sacn(universe: u16, f: impl FnOnce(&mut Option<String>, &mut u8)) -> EndpointConfig {
+        let mut config = EndpointConfig::sacn(universe, EndpointSpeed::Custom(5));
+        if let EndpointConfig::Sacn {
+            source_name,
+            priority,
+            ..
+        } = &mut config
+        {
+            f(source_name, priority);
+        }
+        config
+    }
+
+    fn sent(universe: u16) -> Call {
+        Call::Send {
+            universe,
+            dst: None,
+            sync: None,
+        }
+    }
+
+    /// An endpoint synchronized by the sync universe, sending to the receivers or multicast
+    fn synced(universe: u16, sync: u16, receivers: Vec<SocketAddr>) -> EndpointConfig {
+        let mut config = sacn(universe, |_, _| {});
+        if let EndpointConfig::Sacn {
+            unicast,
+            sync_universe,
+            ..
+        } = &mut config
+        {
+            *unicast = receivers;
+            *sync_universe = Some(sync);
+        }
+        config
+    }
+
+    #[tokio::test]
+    async fn send_sync_packets() {
+        let sync = |dst| Call::Sync { universe: 7, dst };
+        let a = "127.0.0.1:5568".parse().unwrap();
+        let b = "127.0.0.2:5568".parse().unwrap();
+        let mut runner = Runner::start();
+        let unicast = synced(1, 7, vec![a, b]);
+        runner.add(1, &unicast).await;
+        runner.add(1, &synced(2, 7, vec![])).await;
+        runner.add(1, &sacn(3, |_, _| {})).await;
+        runner.calls.wait_for(Call::Register(3)).await;
+        runner.calls.0.lock().unwrap().clear();
+        runner.calls.wait_for(sync(None)).await;
+
+        runner.remove(1, &unicast).await;
+        let calls = runner.calls.wait_for(Call::Terminate(1)).await;
+        let terminated = calls.iter().position(|c| *c == Call::Terminate(1)).unwrap();
+        // The sync universe is still used by the multicast endpoint
+        assert_eq!(calls.get(terminated + 1), Some(&Call::Register(7)));
+
+        let ticks = &calls[..terminated];
+        let count = |call: Call| ticks.iter().filter(|c| **c == call).count();
+        let synced_send = |universe, dst| Call::Send {
+            universe,
+            dst,
+            sync: Some(7),
+        };
+        // The data goes out before the sync packets of the same tick
+        let first_sync = ticks
+            .iter()
+            .position(|c| matches!(c, Call::Sync { .. }))
+            .unwrap();
+        assert!(ticks[..first_sync].contains(&synced_send(1, Some(a))));
+        assert!(ticks[..first_sync].contains(&synced_send(2, None)));
+        // One sync packet per destination and tick, shared by the synchronized universes
+        let n = count(sent(3));
+        assert!(n > 0);
+        assert_eq!(count(synced_send(1, Some(b))), n);
+        assert_eq!(count(synced_send(2, None)), n);
+        for dst in [Some(a), Some(b), None] {
+            assert_eq!(count(sync(dst)), n);
+        }
+        let syncs = ticks.iter().filter(|c| matches!(c, Call::Sync { .. }));
+        assert_eq!(syncs.count(), 3 * n);
+
+        runner.calls.0.lock().unwrap().clear();
+        runner.calls.wait_for(sync(None)).await;
+        let calls = runner.stop().await;
+        assert!(!calls.contains(&sync(Some(a))));
+    }
+
+    #[tokio::test]
+    async fn terminate_removed_streams() {
+        let mut runner = Runner::start();
+        let first = sacn(1, |_, _| {});
+        let second = sacn(2, |name, _| *name = Some("Other".to_string()));
+        // Both on the same universe of MLC
+        runner.add(1, &first).await;
+        runner.add(1, &second).await;
+        runner.calls.wait_for(sent(1)).await;
+
+        // Only the exact endpoint is removed
+        runner.remove(2, &first).await;
+        runner
+            .remove(1, &sacn(1, |_, priority| *priority = 50))
+            .await;
+        runner.add(2, &sacn(3, |_, _| {})).await;
+        // The commands are handled in order, the removes are done once the add is
+        runner.calls.wait_for(Call::Register(3)).await;
+        runner.calls.0.lock().unwrap().clear();
+        runner.calls.wait_for(sent(1)).await;
+
+        runner.remove(1, &first).await;
+        runner.calls.wait_for(Call::Terminate(1)).await;
+        runner.calls.0.lock().unwrap().clear();
+        runner.calls.wait_for(sent(2)).await;
+        runner.calls.wait_for(sent(3)).await;
+
+        // The remaining streams are terminated on shutdown
+        let calls = runner.stop().await;
+        assert!(!calls.contains(&sent(1)));
+        assert!(!calls.contains(&Call::Terminate(1)));
+        assert!(calls.contains(&Call::Terminate(2)));
+        assert!(calls.contains(&Call::Terminate(3)));
     }
-    f.next().await
 }
@@ -0,0 +1,187 @@
+use crate::endpoints::stats::StatsHandle;
+use crate::endpoints::{DriverCommand, EndpointDriver, run_subscriptions};
+use crate::universe::{UniverseUpdate, UniverseUpdateSubscriber};
+use mlc_communication::services::endpoints::SimulatedFrame;
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::universe::{FixtureAddress, UNIVERSE_SIZE, UniverseId};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+use tokio::sync::Notify;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::task::JoinHandle;
+
+/// The last frames received by the simulator endpoints, shared with the endpoint service
+#[derive(Debug, Clone, Default)]
+pub struct SimulatorStore(Arc<Mutex<BTreeMap<UniverseId, SimulatedFrame>>>);
+
+impl SimulatorStore {
+    fn frames_mut(&self) -> MutexGuard<'_, BTreeMap<UniverseId, SimulatedFrame>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn apply(&self, universe: UniverseId, update: &UniverseUpdate) {
+        let mut frames = self.frames_mut();
+        let frame = frames.entry(universe).or_insert_with(|| SimulatedFrame {
+            universe,
+            values: vec![0; UNIVERSE_SIZE],
+            updated: SystemTime::now(),
+            updates: 0,
+        });
+
+        match update {
+            UniverseUpdate::Single { update } => {
+                frame.values[update.0.address().take() - 1] = update.1;
+            }
+            UniverseUpdate::Many { updates } => {
+                for update in updates {
+                    frame.values[update.0.address().take() - 1] = update.1;
+                }
+            }
+            UniverseUpdate::Entire { values, .. } => {
+                frame.values.copy_from_slice(&values[..]);
+            }
+        }
+        frame.updated = SystemTime::now();
+        frame.updates += 1;
+    }
+
+    pub fn remove(&self, universe: UniverseId) {
+        self.frames_mut().remove(&universe);
+    }
+
+    pub fn frame(&self, universe: UniverseId) -> Option<SimulatedFrame> {
+        self.frames_mut().get(&universe).cloned()
+    }
+
+    pub fn frames(&self) -> Vec<SimulatedFrame> {
+        self.frames_mut().values().cloned().collect()
+    }
+
+    /// The last value sent to a channel, `None` if its universe is not simulated
+    pub fn value(&self, address: FixtureAddress) -> Option<u8> {
+        self.frames_mut()
+            .get(&address.universe())
+            .map(|f| f.values[address.address().take() - 1])
+    }
+}
+
+pub struct SimulatorDriver {
+    store: SimulatorStore,
+    handle: Option<JoinHandle<()>>,
+    tx: Sender<DriverCommand<()>>,
+    shutdown_notify: Arc<Notify>,
+}
+
+impl SimulatorDriver {
+    pub fn new(store: SimulatorStore) -> Self {
+        let (tx, _) = tokio::sync::mpsc::channel::<DriverCommand<()>>(1);
+        Self {
+            store,
+            handle: None,
+            tx,
+            shutdown_notify: Arc::new(Notify::new()),
+        }
+    }
+}
+
+impl EndpointDriver<()> for SimulatorDriver {
+    async fn stop_all(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            self.shutdown_notify.notify_one();
+            let _ = handle.await;
+        }
+    }
+
+    async fn apply_config(
+        &mut self,
+        sub: UniverseUpdateSubscriber,
+        config: (),
+        stats: StatsHandle,
+    ) {
+        self.handle.get_or_insert_with(|| {
+            let (tx, rx) = tokio::sync::mpsc::channel::<DriverCommand<()>>(10);
+            self.tx = tx;
+            tokio::spawn(simulator_runner(
+                rx,
+                self.store.clone(),
+                self.shutdown_notify.clone(),
+            ))
+        });
+        if let Err(e) = self
+            .tx
+            .send(DriverCommand::Add { sub, config, stats })
+            .await
+        {
+            tracing::error!("Simulator driver is not running");
+            if let DriverCommand::Add { stats, .. } = e.0 {
+                stats.error("Driver is not running");
+            }
+        }
+    }
+
+    async fn remove_config(&mut self, universe: UniverseId, config: ()) {
+        if self.handle.is_some() {
+            self.tx
+                .send(DriverCommand::Remove { universe, config })
+                .await
+                .debug_ignore();
+        }
+    }
+}
+
+async fn simulator_runner(
+    rx: Receiver<DriverCommand<()>>,
+    store: SimulatorStore,
+    shutdown: Arc<Notify>,
+) {
+    run_subscriptions(
+        rx,
+        shutdown,
+        |universe, u| store.apply(universe, u),
+        |universe| store.remove(universe),
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::SimulatorStore;
+    use crate::universe::UniverseUpdate;
+    use mlc_data::project::universe::{FixtureAddress, UNIVERSE_SIZE, UniverseAddress};
+
+    #[test]
+    fn simulate_universe() {
+        let store = SimulatorStore::default();
+        let address = |channel| FixtureAddress::new(1, UniverseAddress::create(channel));
+
+        let mut values = Box::new([0; UNIVERSE_SIZE]);
+        values[11] = 255;
+        store.apply(
+            1,
+            &UniverseUpdate::Entire {
+                universe: 1,
+                values,
+            },
+        );
+        assert_eq!(store.value(address(12)), Some(255));
+        assert_eq!(
+            store.value(FixtureAddress::new(2, UniverseAddress::create(1))),
+            None
+        );
+
+        store.apply(
+            1,
+            &UniverseUpdate::Many {
+                updates: vec![(address(12), 0), (address(512), 7)],
+            },
+        );
+        let frame = store.frame(1).unwrap();
+        assert_eq!(frame.values[11], 0);
+        assert_eq!(frame.values[511], 7);
+        assert_eq!(frame.updates, 2);
+
+        store.remove(1);
+        assert!(store.frames().is_empty());
+    }
+}
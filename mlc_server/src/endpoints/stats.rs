@@ -0,0 +1,136 @@
+use mlc_communication::services::endpoints::EndpointStats;
+use mlc_data::endpoints::EndpointConfig;
+use mlc_data::project::universe::UniverseId;
+use std::fmt::Display;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Instant;
+
+/// Counters of a single endpoint, shared between its driver and the collector
+#[derive(Debug, Clone)]
+pub struct StatsHandle(Arc<Mutex<Counters>>);
+
+#[derive(Debug)]
+struct Counters {
+    stats: EndpointStats,
+    last_packets: u64,
+}
+
+impl StatsHandle {
+    fn counters(&self) -> MutexGuard<'_, Counters> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn sent(&self) {
+        self.counters().stats.packets_sent += 1;
+    }
+
+    pub fn error(&self, error: impl Display) {
+        let mut counters = self.counters();
+        counters.stats.send_errors += 1;
+        counters.stats.last_error = Some(error.to_string());
+    }
+}
+
+#[derive(Debug)]
+pub struct StatsCollector {
+    endpoints: Vec<StatsHandle>,
+    last_snapshot: Instant,
+}
+
+impl StatsCollector {
+    pub fn new() -> Self {
+        Self {
+            endpoints: vec![],
+            last_snapshot: Instant::now(),
+        }
+    }
+
+    /// Stops reporting an endpoint, its handle may still be held by the driver
+    pub fn unregister(&mut self, universe: UniverseId, endpoint: &EndpointConfig) {
+        if let Some(i) = self.endpoints.iter().position(|h| {
+            let counters = h.counters();
+            counters.stats.universe == universe && counters.stats.endpoint == *endpoint
+        }) {
+            self.endpoints.remove(i);
+        }
+    }
+
+    pub fn register(&mut self, universe: UniverseId, endpoint: &EndpointConfig) -> StatsHandle {
+        let handle = StatsHandle(Arc::new(Mutex::new(Counters {
+            stats: EndpointStats {
+                universe,
+                endpoint: endpoint.clone(),
+                packets_sent: 0,
+                send_errors: 0,
+                fps: 0.0,
+                target_fps: endpoint.speed().map(|s| s.fps()),
+                last_error: None,
+            },
+            last_packets: 0,
+        })));
+        self.endpoints.push(handle.clone());
+        handle
+    }
+
+    /// Current stats of all endpoints, the frame rates are measured since the last snapshot
+    pub fn snapshot(&mut self) -> Vec<EndpointStats> {
+        let elapsed = self.last_snapshot.elapsed().as_secs_f32();
+        self.last_snapshot = Instant::now();
+
+        self.endpoints
+            .iter()
+            .map(|h| {
+                let mut counters = h.counters();
+                let packets = counters.stats.packets_sent - counters.last_packets;
+                counters.last_packets = counters.stats.packets_sent;
+                if elapsed > 0.0 {
+                    counters.stats.fps = packets as f32 / elapsed;
+                }
+                counters.stats.clone()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::StatsCollector;
+    use mlc_data::endpoints::{EndpointConfig, EndpointSpeed};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn snapshots() {
+        let mut collector = StatsCollector::new();
+        let sacn = EndpointConfig::sacn(1, EndpointSpeed::Medium);
+        let handle = collector.register(1, &sacn);
+        collector.register(1, &EndpointConfig::Simulator);
+
+        for _ in 0..4 {
+            handle.sent();
+        }
+        handle.error("Network unreachable");
+        collector.last_snapshot = Instant::now() - Duration::from_secs(2);
+        let stats = collector.snapshot();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].packets_sent, 4);
+        assert_eq!(stats[0].send_errors, 1);
+        assert_eq!(stats[0].last_error.as_deref(), Some("Network unreachable"));
+        assert_eq!(stats[0].target_fps, Some(10.0));
+        assert!((stats[0].fps - 2.0).abs() < 0.1, "{}", stats[0].fps);
+        assert_eq!(stats[1].packets_sent, 0);
+        assert_eq!(stats[1].target_fps, None);
+
+        // The rate only counts the packets since the last snapshot
+        collector.last_snapshot = Instant::now() - Duration::from_secs(1);
+        let stats = collector.snapshot();
+        assert_eq!(stats[0].packets_sent, 4);
+        assert_eq!(stats[0].fps, 0.0);
+
+        // A handle still held by a driver is no longer reported
+        collector.unregister(1, &sacn);
+        handle.sent();
+        let stats = collector.snapshot();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].endpoint, EndpointConfig::Simulator);
+    }
+}
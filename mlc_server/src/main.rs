@@ -1,33 +1,68 @@
-use crate::endpoints::EndpointsManagerService;
+use crate::audio::AudioAnalysisService;
+use crate::endpoints::{EndpointsManagerService, SimulatorStore};
 use crate::global_services::{AutosaveService, ShutdownService};
 use crate::logging::setup_logging;
+use crate::midi::MidiInputService;
 use crate::misc::ShutdownHandler;
+use crate::osc::OscService;
+use crate::programmer::LayerRefreshService;
 use crate::project::create_default_project;
+use crate::project::history::History;
+use crate::recorder::{RecorderCommand, RecordingManagerService};
+use crate::schedule::{ScheduleCommand, SchedulerService};
+use crate::script::{ScriptCommand, ScriptRunnerService};
 use crate::server::ServerService;
+use crate::tempo::{TempoClock, TempoService};
+use crate::timecode::{TimecodeChaseService, TimecodeEvent};
 use crate::tui::TuiService;
 use crate::universe::UniverseRuntimeService;
 use misc::AdaptNotifier;
 use mlc_communication::remoc::rch::watch::{Receiver, Sender};
 use mlc_communication::remoc::rtc::CallError;
-use mlc_communication::services::general::Info;
+use mlc_communication::services::audio::AudioState;
+use mlc_communication::services::endpoints::EndpointStats;
 use mlc_communication::services::general::{Alive, View};
-use mlc_communication::services::project::ProjectServiceError;
+use mlc_communication::services::general::{Info, Masters, Tempo, TempoError};
+use mlc_communication::services::programmer::{
+    CuePlaybackState, ProgrammerState, TimecodeState,
+};
+use mlc_communication::services::midi::MidiState;
+use mlc_communication::services::project::{EditHistory, ProjectServiceError};
+use mlc_communication::services::recorder::RecorderState;
+use mlc_communication::services::schedule::ScheduleState;
+use mlc_communication::services::script::ScriptState;
 use mlc_communication::{self as com, remoc::prelude::*};
 use mlc_data::misc::ErrIgnore;
+use mlc_data::project::group::GroupId;
+use mlc_data::project::universe::UniverseId;
+use mlc_data::SavePercentage;
 use mlc_ofl::OflLibrary;
 use project::{get_base_app_dir, Project};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use tracing::error;
-use universe::UniverseRuntimeController;
+use universe::{RuntimeCommand, UniverseRuntimeController};
 
+mod audio;
+mod control;
+mod cues;
 mod endpoints;
 mod global_services;
 mod logging;
+mod midi;
 mod misc;
+mod osc;
+mod programmer;
 mod project;
+mod recorder;
+mod schedule;
+mod script;
 mod server;
+mod tempo;
+#[cfg(test)]
+mod test_util;
+mod timecode;
 mod tui;
 mod universe;
 
@@ -38,6 +73,25 @@ pub struct ServiceImpl {
     valid_project: RwLock<bool>,
     info: Sender<Info>,
     status: Sender<String>,
+    programmer: Sender<ProgrammerState>,
+    cue_playback: Sender<CuePlaybackState>,
+    masters: Sender<Masters>,
+    endpoint_stats: Sender<Vec<EndpointStats>>,
+    simulator: SimulatorStore,
+    recorder_state: Sender<RecorderState>,
+    recorder: tokio::sync::mpsc::UnboundedSender<RecorderCommand>,
+    midi_state: Sender<MidiState>,
+    timecode_state: Sender<TimecodeState>,
+    timecode: tokio::sync::mpsc::UnboundedSender<TimecodeEvent>,
+    audio_state: Sender<AudioState>,
+    tempo: Sender<Tempo>,
+    tempo_clock: Mutex<TempoClock>,
+    schedule_state: Sender<ScheduleState>,
+    schedule: tokio::sync::mpsc::UnboundedSender<ScheduleCommand>,
+    script_state: Sender<ScriptState>,
+    scripts: tokio::sync::mpsc::UnboundedSender<ScriptCommand>,
+    history: Mutex<History>,
+    history_state: Sender<EditHistory>,
     adapt_notifier: AdaptNotifier,
     ofl_library: OflLibrary,
     universe_runtime: Arc<UniverseRuntimeController>,
@@ -51,6 +105,12 @@ impl ServiceImpl {
             error!("SendInfo error: {err:#?}");
         }
     }
+
+    pub fn update_masters(&self, f: impl FnOnce(&mut Masters)) {
+        self.masters.send_modify(f);
+        self.universe_runtime
+            .cmd(RuntimeCommand::SetMasters(self.masters.borrow().clone()));
+    }
 }
 
 #[rtc::async_trait]
@@ -93,6 +153,70 @@ impl com::services::general::GeneralService for ServiceImpl {
 
         Ok(true)
     }
+
+    async fn masters(&self) -> Result<Receiver<Masters>, CallError> {
+        Ok(self.masters.subscribe())
+    }
+
+    async fn set_grand_master(&self, level: SavePercentage) -> Result<(), CallError> {
+        self.update_masters(|m| m.grand_master = level);
+        Ok(())
+    }
+
+    async fn set_blackout(&self, blackout: bool) -> Result<(), CallError> {
+        self.update_masters(|m| m.blackout = blackout);
+        Ok(())
+    }
+
+    async fn set_universe_master(
+        &self,
+        universe: UniverseId,
+        level: SavePercentage,
+    ) -> Result<(), CallError> {
+        self.set_universe_level(universe, level.take());
+        Ok(())
+    }
+
+    async fn set_group_master(
+        &self,
+        group: GroupId,
+        level: SavePercentage,
+    ) -> Result<(), CallError> {
+        self.set_group_level(group, level.take());
+        Ok(())
+    }
+
+    async fn tempo(&self) -> Result<Receiver<Tempo>, CallError> {
+        Ok(self.tempo.subscribe())
+    }
+
+    async fn tap_tempo(&self) -> Result<(), CallError> {
+        self.tap();
+        Ok(())
+    }
+
+    async fn set_bpm(&self, bpm: f32) -> Result<(), TempoError> {
+        if !bpm.is_finite() {
+            return Err(TempoError::InvalidBpm(bpm));
+        }
+        self.update_tempo(|clock, now| clock.set_bpm(bpm, now));
+        Ok(())
+    }
+
+    async fn set_tempo_multiplier(&self, multiplier: f32) -> Result<(), CallError> {
+        self.update_tempo(|clock, now| clock.set_multiplier(multiplier, now));
+        Ok(())
+    }
+
+    async fn reset_tempo_phase(&self) -> Result<(), CallError> {
+        self.update_tempo(|clock, now| clock.reset_phase(now));
+        Ok(())
+    }
+
+    async fn set_tempo_follow_audio(&self, follow: bool) -> Result<(), CallError> {
+        self.tempo.send_modify(|t| t.follow_audio = follow);
+        Ok(())
+    }
 }
 
 pub struct MlcServiceResources {
@@ -181,12 +305,35 @@ async fn main() {
     tokio::fs::create_dir_all(&lib_path).await.ignore();
 
     let (universe_runtime_service, universe_runtime_controller) = UniverseRuntimeService::create();
+    let (recording_manager_service, recorder) = RecordingManagerService::create();
+    let (timecode_chase_service, timecode) = TimecodeChaseService::create();
+    let (scheduler_service, schedule) = SchedulerService::create();
+    let (script_runner_service, scripts) = ScriptRunnerService::create();
 
     let service_obj = Arc::new(ServiceImpl {
         project,
         valid_project: RwLock::new(false),
         info: rch::watch::channel(Info::Idle).0,
         status: rch::watch::channel(String::new()).0,
+        programmer: rch::watch::channel(ProgrammerState::default()).0,
+        cue_playback: rch::watch::channel(CuePlaybackState::default()).0,
+        masters: rch::watch::channel(Masters::default()).0,
+        endpoint_stats: rch::watch::channel(vec![]).0,
+        simulator: SimulatorStore::default(),
+        recorder_state: rch::watch::channel(RecorderState::default()).0,
+        recorder,
+        midi_state: rch::watch::channel(MidiState::default()).0,
+        timecode_state: rch::watch::channel(TimecodeState::default()).0,
+        timecode,
+        audio_state: rch::watch::channel(AudioState::default()).0,
+        tempo: rch::watch::channel(Tempo::default()).0,
+        tempo_clock: Mutex::new(TempoClock::new(&Tempo::default())),
+        schedule_state: rch::watch::channel(ScheduleState::default()).0,
+        schedule,
+        script_state: rch::watch::channel(ScriptState::default()).0,
+        scripts,
+        history: Mutex::new(History::default()),
+        history_state: rch::watch::channel(EditHistory::default()).0,
         adapt_notifier: adapt_notifier.clone(),
         ofl_library: OflLibrary::create(lib_path.join("ofl.json")),
         universe_runtime: Arc::new(universe_runtime_controller),
@@ -197,11 +344,20 @@ async fn main() {
         MlcServiceResourcesBuilder::new(service_obj, shutdown_handler, adapt_notifier);
 
     service_handler.add_service(universe_runtime_service);
+    service_handler.add_service(LayerRefreshService);
 
     service_handler.add_service(ServerService);
     service_handler.add_service(ShutdownService);
     service_handler.add_service(AutosaveService);
     service_handler.add_service(EndpointsManagerService);
+    service_handler.add_service(recording_manager_service);
+    service_handler.add_service(OscService);
+    service_handler.add_service(MidiInputService);
+    service_handler.add_service(timecode_chase_service);
+    service_handler.add_service(AudioAnalysisService);
+    service_handler.add_service(TempoService);
+    service_handler.add_service(scheduler_service);
+    service_handler.add_service(script_runner_service);
 
     service_handler.add_complex_service(TuiService, log_rx);
 
@@ -0,0 +1,357 @@
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::project::history::Part;
+use crate::timecode::TimecodeEvent;
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use mlc_communication::remoc::rch::watch;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::general::ProjectInfo;
+use mlc_communication::services::midi::{MidiService, MidiServiceError, MidiState};
+use mlc_data::midi::{MidiMessage, MidiParser};
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::control::ControlAction;
+use mlc_data::project::midi::{MidiControl, MidiSettings};
+use mlc_data::timecode::{MscMessage, MtcDecoder};
+use std::time::Instant;
+use tokio::io::AsyncReadExt;
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+mod ports;
+
+/// The ALSA sequencer client of the server and its port other MIDI applications connect to
+const CLIENT_NAME: &str = "MLC";
+const VIRTUAL_PORT_NAME: &str = "MLC In";
+
+#[rtc::async_trait]
+impl MidiService for ServiceImpl {
+    async fn state(&self) -> Result<watch::Receiver<MidiState>, MidiServiceError> {
+        Ok(self.midi_state.subscribe())
+    }
+
+    async fn available_ports(&self) -> Result<Vec<String>, MidiServiceError> {
+        ports::available().map_err(|e| MidiServiceError::PortError(e.to_string()))
+    }
+
+    async fn start_learn(&self, action: ControlAction) -> Result<(), MidiServiceError> {
+        let p = self
+            .validate_project()
+            .await
+            .map_err(|_| MidiServiceError::InvalidProject)?;
+        if !p.midi.enabled {
+            return Err(MidiServiceError::Disabled);
+        }
+
+        self.midi_state.send_modify(|s| s.learning = Some(action));
+        Ok(())
+    }
+
+    async fn cancel_learn(&self) -> Result<(), MidiServiceError> {
+        if self.midi_state.borrow().learning.is_none() {
+            return Err(MidiServiceError::NotLearning);
+        }
+
+        self.midi_state.send_modify(|s| s.learning = None);
+        Ok(())
+    }
+}
+
+pub struct MidiInputService;
+
+impl MlcServiceSimple for MidiInputService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_midi(
+            res.service_obj.clone(),
+            res.shutdown.clone(),
+            res.adapt_notifier.clone(),
+        )
+    }
+}
+
+async fn run_midi(
+    service_obj: AServiceImpl,
+    shutdown_handler: ShutdownHandler,
+    adapt_notifier: AdaptNotifier,
+) {
+    loop {
+        let mut settings = service_obj.project.read().await.midi.clone();
+        let mut decoder = MtcDecoder::new();
+        let (tx, mut rx) = mpsc::channel(64);
+        let readers = if settings.enabled {
+            open_ports(&settings, tx.clone())
+        } else {
+            vec![]
+        };
+        let virtual_port = if settings.enabled && settings.virtual_port {
+            open_virtual(tx)
+        } else {
+            None
+        };
+
+        service_obj.midi_state.send_modify(|s| {
+            s.ports = readers
+                .iter()
+                .map(|(p, _)| p.clone())
+                .chain(virtual_port.as_ref().map(|_| VIRTUAL_PORT_NAME.to_string()))
+                .collect();
+            if !settings.enabled {
+                s.learning = None;
+            }
+        });
+
+        let exit = loop {
+            select! {
+                _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                    break true;
+                }
+                _ = adapt_notifier.wait(AdaptScopes::CONTROL | AdaptScopes::PROJECT) => {
+                    break false;
+                }
+                Some(message) = rx.recv() => {
+                    handle_message(&service_obj, &mut settings, &mut decoder, message).await;
+                }
+            }
+        };
+
+        for (_, reader) in readers {
+            reader.abort();
+        }
+        // Closing the virtual port waits for its thread, which must not wait for a full channel
+        drop(rx);
+        drop(virtual_port);
+        if exit {
+            break;
+        }
+    }
+}
+
+fn open_ports(
+    settings: &MidiSettings,
+    tx: mpsc::Sender<MidiMessage>,
+) -> Vec<(String, JoinHandle<()>)> {
+    let paths = if settings.ports.is_empty() {
+        ports::available().unwrap_or_else(|e| {
+            warn!("Listing the MIDI ports failed: {e}");
+            vec![]
+        })
+    } else {
+        settings.ports.clone()
+    };
+
+    paths
+        .into_iter()
+        .filter_map(|path| match ports::open(&path) {
+            Ok(port) => {
+                info!("Reading MIDI from {path}");
+                let reader = tokio::spawn(read_port(port, path.clone(), tx.clone()));
+                Some((path, reader))
+            }
+            Err(e) => {
+                warn!("Opening the MIDI port {path} failed: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+fn open_virtual(tx: mpsc::Sender<MidiMessage>) -> Option<ports::VirtualPort> {
+    let mut parser = MidiParser::new();
+    // Called on the thread of the sequencer, outside of the runtime
+    let receive = move |data: &[u8]| {
+        for message in parser.parse(data) {
+            tx.blocking_send(message).debug_ignore();
+        }
+    };
+    match ports::create_virtual(CLIENT_NAME, VIRTUAL_PORT_NAME, receive) {
+        Ok(port) => {
+            info!("Reading MIDI from the virtual port {VIRTUAL_PORT_NAME}");
+            Some(port)
+        }
+        Err(e) => {
+            warn!("Creating the virtual MIDI port failed: {e}");
+            None
+        }
+    }
+}
+
+async fn read_port(mut port: ports::Port, path: String, tx: mpsc::Sender<MidiMessage>) {
+    let mut parser = MidiParser::new();
+    let mut buf = [0; 256];
+    loop {
+        let len = match port.read(&mut buf).await {
+            Ok(0) => {
+                warn!("The MIDI port {path} was closed");
+                return;
+            }
+            Ok(len) => len,
+            Err(e) => {
+                warn!("Reading the MIDI port {path} failed: {e}");
+                return;
+            }
+        };
+
+        for message in parser.parse(&buf[..len]) {
+            if tx.send(message).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+async fn handle_message(
+    service_obj: &AServiceImpl,
+    settings: &mut MidiSettings,
+    decoder: &mut MtcDecoder,
+    message: MidiMessage,
+) {
+    if let Some(timecode) = decoder.push(&message) {
+        service_obj
+            .timecode
+            .send(TimecodeEvent::Timecode {
+                timecode,
+                position: timecode.to_duration(),
+                at: Instant::now(),
+            })
+            .debug_ignore();
+        return;
+    }
+    if let MidiMessage::SysEx(data) = &message
+        && let Some(msc) = MscMessage::parse(data)
+    {
+        service_obj
+            .timecode
+            .send(TimecodeEvent::Msc(msc))
+            .debug_ignore();
+        return;
+    }
+
+    let Some((control, _)) = MidiControl::from_message(&message) else {
+        return;
+    };
+
+    let learning = service_obj.midi_state.borrow().learning.clone();
+    service_obj.midi_state.send_modify(|s| {
+        s.last_control = Some(control);
+        s.learning = None;
+    });
+
+    if let Some(action) = learning {
+        if let Ok(mut p) = service_obj
+            .edit_project("Learn MIDI control", &[Part::Midi])
+            .await
+        {
+            info!("Mapped {control} to {action:?}");
+            p.midi.learn(control, action.clone());
+            p.commit();
+            // The running input maps the control from now on, nothing has to be restarted
+            settings.learn(control, action);
+        }
+        service_obj.send_info(ProjectInfo::SettingsChanged.into());
+        return;
+    }
+
+    if let Some((action, value)) = settings.resolve(&message)
+        && let Err(e) = service_obj.execute_control(&action, Some(value)).await
+    {
+        warn!("MIDI control {action:?} failed: {e}");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::handle_message;
+    use crate::misc::AdaptScopes;
+    use crate::project::create_default_project;
+    use crate::test_util::service_obj;
+    use mlc_communication::services::general::{Info, ProjectInfo};
+    use mlc_data::midi::MidiMessage;
+    use mlc_data::project::control::ControlAction;
+    use mlc_data::project::midi::MidiControl;
+    use mlc_data::timecode::MtcDecoder;
+    use std::time::Duration;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn read_raw_port() {
+        use super::{ports, read_port};
+        use std::io::Write;
+        use tokio::sync::mpsc;
+
+        let path = std::env::temp_dir().join(format!("mlc_midi_test_{}", std::process::id()));
+        let created = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(created.success());
+
+        let port = ports::open(&path).unwrap();
+        let (tx, mut rx) = mpsc::channel(8);
+        let reader = tokio::spawn(read_port(port, path.to_string_lossy().to_string(), tx));
+
+        let mut writer = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        writer.write_all(&[0xB0, 7, 127, 0x99, 36]).unwrap();
+        writer.write_all(&[100]).unwrap();
+        drop(writer);
+
+        assert_eq!(
+            rx.recv().await,
+            Some(MidiMessage::ControlChange {
+                channel: 0,
+                controller: 7,
+                value: 127
+            })
+        );
+        assert_eq!(
+            rx.recv().await,
+            Some(MidiMessage::NoteOn {
+                channel: 9,
+                note: 36,
+                velocity: 100
+            })
+        );
+
+        reader.abort();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn learn_without_restart() {
+        let mut project = create_default_project();
+        project.midi.enabled = true;
+        let (service_obj, _) = service_obj(project);
+        let mut settings = service_obj.project.read().await.midi.clone();
+        let info = service_obj.info.subscribe();
+        let restart = service_obj.adapt_notifier.wait(AdaptScopes::all());
+
+        service_obj
+            .midi_state
+            .send_modify(|s| s.learning = Some(ControlAction::GrandMaster));
+        let message = MidiMessage::ControlChange {
+            channel: 0,
+            controller: 7,
+            value: 127,
+        };
+        let mut decoder = MtcDecoder::new();
+        handle_message(&service_obj, &mut settings, &mut decoder, message.clone()).await;
+
+        let fader = MidiControl::ControlChange {
+            channel: 0,
+            controller: 7,
+        };
+        assert_eq!(service_obj.midi_state.borrow().learning, None);
+        assert_eq!(service_obj.midi_state.borrow().last_control, Some(fader));
+        assert_eq!(service_obj.project.read().await.midi, settings);
+        assert_eq!(
+            settings.resolve(&message),
+            Some((ControlAction::GrandMaster, 1.0))
+        );
+        assert_eq!(
+            *info.borrow().unwrap(),
+            Info::from(ProjectInfo::SettingsChanged)
+        );
+        // The other inputs keep running
+        let restarted = tokio::time::timeout(Duration::from_millis(50), restart).await;
+        assert!(restarted.is_err());
+    }
+}
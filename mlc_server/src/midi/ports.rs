@@ -0,0 +1,98 @@
+//! Raw MIDI ports. ALSA exposes its MIDI devices, including the virtual ports of `snd-virmidi`,
+//! as character devices in `/dev/snd`. The virtual port of the server is an ALSA sequencer port
+//! other MIDI applications connect to.
+
+use std::io;
+use std::path::Path;
+
+const DEVICE_DIR: &str = "/dev/snd";
+
+pub fn available() -> io::Result<Vec<String>> {
+    let entries = match std::fs::read_dir(DEVICE_DIR) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut ports = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("midi"))
+        .map(|e| e.path().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    ports.sort();
+    Ok(ports)
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use std::os::unix::fs::FileTypeExt;
+    use std::path::Path;
+    use tokio::net::unix::pipe;
+
+    pub type Port = pipe::Receiver;
+
+    pub fn open(path: &Path) -> io::Result<Port> {
+        // Keeping a FIFO open for writing as well prevents it from closing with its last writer
+        let fifo = std::fs::metadata(path)?.file_type().is_fifo();
+        pipe::OpenOptions::new()
+            .read_write(fifo)
+            .unchecked(true)
+            .open_receiver(path)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+    use std::path::Path;
+
+    pub type Port = tokio::io::Empty;
+
+    pub fn open(_: &Path) -> io::Result<Port> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod sequencer {
+    use midir::os::unix::VirtualInput;
+    use midir::{MidiInput, MidiInputConnection};
+    use std::io;
+
+    /// Closed when dropped
+    pub type VirtualPort = MidiInputConnection<()>;
+
+    pub fn create_virtual(
+        client: &str,
+        name: &str,
+        mut receive: impl FnMut(&[u8]) + Send + 'static,
+    ) -> io::Result<VirtualPort> {
+        let input = MidiInput::new(client).map_err(io::Error::other)?;
+        input
+            .create_virtual(name, move |_, data, _| receive(data), ())
+            .map_err(|e| io::Error::other(e.to_string()))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sequencer {
+    use std::io;
+
+    pub struct VirtualPort;
+
+    pub fn create_virtual(
+        _: &str,
+        _: &str,
+        _: impl FnMut(&[u8]) + Send + 'static,
+    ) -> io::Result<VirtualPort> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+pub use imp::Port;
+pub use sequencer::{VirtualPort, create_virtual};
+
+pub fn open(path: impl AsRef<Path>) -> io::Result<Port> {
+    imp::open(path.as_ref())
+}
@@ -55,6 +55,12 @@ bitflags! {
         const UNIVERSES = 0b00000001;
         const ENDPOINTS = 0b00000010;
         const SETTINGS =  0b00000100;
+        const PATCH =     0b00001000;
+        const PARKED =    0b00010000;
+        /// A different project was loaded, runtime state has to be reset
+        const PROJECT =   0b00100000;
+        /// External control mappings changed
+        const CONTROL =   0b01000000;
     }
 }
 
@@ -0,0 +1,337 @@
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple};
+use mlc_communication::services::general::Info;
+use mlc_data::project::control::ControlAction;
+use mlc_data::project::osc::OscSettings;
+use packet::{OscArg, OscMessage};
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::net::UdpSocket;
+use tokio::select;
+use tracing::{error, info, warn};
+
+mod packet;
+
+pub struct OscService;
+
+impl MlcServiceSimple for OscService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_osc(
+            res.service_obj.clone(),
+            res.shutdown.clone(),
+            res.adapt_notifier.clone(),
+        )
+    }
+}
+
+async fn run_osc(
+    service_obj: AServiceImpl,
+    shutdown_handler: ShutdownHandler,
+    adapt_notifier: AdaptNotifier,
+) {
+    'o: loop {
+        let settings = service_obj.project.read().await.osc.clone();
+        let socket = if settings.enabled {
+            bind(&service_obj, settings.port).await
+        } else {
+            None
+        };
+
+        let mut masters = service_obj.masters.subscribe();
+        let mut cues = service_obj.cue_playback.subscribe();
+        let mut feedback = Feedback::default();
+        send_feedback(&service_obj, &settings, socket.as_ref(), &mut feedback).await;
+
+        let mut buf = [0; 4096];
+        loop {
+            let receive = async {
+                match &socket {
+                    Some(socket) => socket.recv_from(&mut buf).await,
+                    None => std::future::pending().await,
+                }
+            };
+
+            select! {
+                _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                    break 'o;
+                }
+                _ = adapt_notifier.wait(AdaptScopes::CONTROL | AdaptScopes::PROJECT) => {
+                    break;
+                }
+                received = receive => {
+                    let len = match received {
+                        Ok((len, _)) => len,
+                        Err(e) => {
+                            warn!("Receiving OSC failed: {e}");
+                            continue;
+                        }
+                    };
+                    for (action, value) in actions(&settings, &buf[..len]) {
+                        if let Err(e) = service_obj.execute_control(&action, value).await {
+                            warn!("OSC control {action:?} failed: {e}");
+                        }
+                    }
+                }
+                Ok(_) = masters.changed() => {
+                    send_feedback(&service_obj, &settings, socket.as_ref(), &mut feedback).await;
+                }
+                Ok(_) = cues.changed() => {
+                    send_feedback(&service_obj, &settings, socket.as_ref(), &mut feedback).await;
+                }
+            }
+        }
+    }
+}
+
+async fn bind(service_obj: &AServiceImpl, port: u16) -> Option<UdpSocket> {
+    match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).await {
+        Ok(socket) => {
+            info!("Listening for OSC on port {port}");
+            Some(socket)
+        }
+        Err(e) => {
+            error!("Binding the OSC port {port} failed: {e}");
+            service_obj.send_info(Info::Warning {
+                title: "OSC unavailable".to_string(),
+                msg: format!("The port {port} could not be opened: {e}"),
+            });
+            None
+        }
+    }
+}
+
+/// The actions addressed by a packet with the value of their first argument
+fn actions(settings: &OscSettings, data: &[u8]) -> Vec<(ControlAction, Option<f32>)> {
+    let Some(messages) = packet::decode(data) else {
+        warn!("Received malformed OSC packet");
+        return vec![];
+    };
+
+    messages
+        .into_iter()
+        .filter_map(|m| {
+            let action = settings.resolve(&m.address)?;
+            Some((action, m.args.first().and_then(OscArg::control_value)))
+        })
+        .collect()
+}
+
+async fn send_feedback(
+    service_obj: &AServiceImpl,
+    settings: &OscSettings,
+    socket: Option<&UdpSocket>,
+    feedback: &mut Feedback,
+) {
+    let (Some(socket), Some(target)) = (socket, settings.feedback) else {
+        return;
+    };
+
+    let (first_list, universes) = {
+        let project = service_obj.project.read().await;
+        let first_list = project.cue_lists.first().map(|l| l.id);
+        let universes = project.universes.iter().map(|u| u.id).collect::<Vec<_>>();
+        (first_list, universes)
+    };
+    let masters = service_obj.masters.borrow().clone();
+    let cues = service_obj.cue_playback.borrow().clone();
+
+    let values = settings.mappings.iter().flat_map(|m| {
+        let value = match &m.action {
+            ControlAction::GrandMaster => OscArg::Float(masters.grand_master.take()),
+            ControlAction::Blackout => OscArg::Float(if masters.blackout { 1.0 } else { 0.0 }),
+            ControlAction::UniverseMaster(u) => OscArg::Float(masters.universe_level(*u)),
+            ControlAction::UniverseMasters => {
+                return universes
+                    .iter()
+                    .map(|u| {
+                        let level = OscArg::Float(masters.universe_level(*u));
+                        (format!("{}/{u}", m.address), level)
+                    })
+                    .collect();
+            }
+            ControlAction::GroupMaster(g) => OscArg::Float(masters.group_level(g)),
+            ControlAction::CueGo(list) => {
+                let current = list.or(first_list).and_then(|l| cues.current(l));
+                OscArg::Int(current.map_or(0, |i| i as i32 + 1))
+            }
+            _ => return vec![],
+        };
+        vec![(m.address.clone(), value)]
+    });
+
+    for message in feedback.changes(values) {
+        send_to(socket, &message, target).await;
+    }
+}
+
+async fn send_to(socket: &UdpSocket, message: &OscMessage, target: SocketAddr) {
+    if let Err(e) = socket.send_to(&message.encode(), target).await {
+        warn!("Sending OSC feedback to {target} failed: {e}");
+    }
+}
+
+/// Remembers the values sent as feedback to only send changes
+#[derive(Debug, Default)]
+struct Feedback {
+    sent: HashMap<String, OscArg>,
+}
+
+impl Feedback {
+    fn changes(&mut self, values: impl IntoIterator<Item = (String, OscArg)>) -> Vec<OscMessage> {
+        values
+            .into_iter()
+            .filter_map(|(address, value)| {
+                if self.sent.get(&address) == Some(&value) {
+                    return None;
+                }
+                self.sent.insert(address.clone(), value.clone());
+                Some(OscMessage::new(address, vec![value]))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::packet::{self, OscArg, OscMessage};
+    use super::{Feedback, actions, run_osc};
+    use crate::project::create_default_project;
+    use crate::test_util::service_obj;
+    use mlc_data::project::control::ControlAction;
+    use mlc_data::project::osc::{OscMapping, OscSettings};
+    use mlc_data::project::universe::{FixtureAddress, UniverseAddress};
+    use mlc_data::uuid::Uuid;
+    use std::net::{Ipv4Addr, SocketAddr};
+    use std::time::Duration;
+    use tokio::net::UdpSocket;
+
+    #[tokio::test]
+    async fn receive_from_udp_client() {
+        let group = Uuid::new_v4();
+        let mut settings = OscSettings::default();
+        settings.mappings.push(OscMapping::new(
+            "/mlc/fader/3",
+            ControlAction::GroupMaster(group),
+        ));
+
+        let server = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let target = server.local_addr().unwrap();
+
+        let go = OscMessage::new("/mlc/cue/go", vec![]).encode();
+        client.send_to(&go, target).await.unwrap();
+
+        // A bundle with a fader and a dmx value
+        let fader = OscMessage::new("/mlc/fader/3", vec![OscArg::Float(0.5)]).encode();
+        let channel = OscMessage::new("/mlc/universe/1/12", vec![OscArg::Int(255)]).encode();
+        let mut bundle = b"#bundle\0".to_vec();
+        bundle.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for message in [fader, channel] {
+            bundle.extend_from_slice(&(message.len() as i32).to_be_bytes());
+            bundle.extend_from_slice(&message);
+        }
+        client.send_to(&bundle, target).await.unwrap();
+
+        let mut buf = [0; 1024];
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert_eq!(
+            actions(&settings, &buf[..len]),
+            vec![(ControlAction::CueGo(None), None)]
+        );
+
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert_eq!(
+            actions(&settings, &buf[..len]),
+            vec![
+                (ControlAction::GroupMaster(group), Some(0.5)),
+                (
+                    ControlAction::Channel(FixtureAddress::new(1, UniverseAddress::create(12))),
+                    Some(1.0)
+                ),
+            ]
+        );
+
+        assert!(actions(&settings, b"no osc").is_empty());
+        let nan = OscMessage::new("/mlc/master", vec![OscArg::Float(f32::NAN)]).encode();
+        assert!(actions(&settings, &nan).is_empty());
+        let mut infinite = b"/mlc/master\0,d\0\0".to_vec();
+        infinite.extend_from_slice(&f64::INFINITY.to_be_bytes());
+        assert_eq!(packet::decode(&infinite), None);
+    }
+
+    #[tokio::test]
+    async fn control_and_feedback() {
+        let client = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        // A free port for the server
+        let port = {
+            let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+            socket.local_addr().unwrap().port()
+        };
+        let mut project = create_default_project();
+        project.osc.enabled = true;
+        project.osc.port = port;
+        project.osc.feedback = Some(client.local_addr().unwrap());
+
+        let (service_obj, _) = service_obj(project);
+        let server = tokio::spawn(run_osc(
+            service_obj.clone(),
+            service_obj.shutdown.clone(),
+            service_obj.adapt_notifier.clone(),
+        ));
+
+        let target = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let master = OscMessage::new("/mlc/master", vec![OscArg::Float(0.5)]).encode();
+        let fader = OscMessage::new("/mlc/fader/1", vec![OscArg::Float(0.25)]).encode();
+        let mut buf = [0; 1024];
+        let mut received = vec![];
+        let expected = OscMessage::new("/mlc/fader/1", vec![OscArg::Float(0.25)]);
+        // Sent until the server is listening and has answered with the feedback
+        for _ in 0..100 {
+            if received.contains(&expected) {
+                break;
+            }
+            client.send_to(&master, target).await.unwrap();
+            client.send_to(&fader, target).await.unwrap();
+            let read = client.recv_from(&mut buf);
+            if let Ok(Ok((len, _))) = tokio::time::timeout(Duration::from_millis(50), read).await {
+                received.extend(packet::decode(&buf[..len]).unwrap());
+            }
+        }
+        assert!(received.contains(&expected), "{received:?}");
+
+        let masters = service_obj.masters.borrow().clone();
+        assert_eq!(masters.grand_master.take(), 0.5);
+        assert_eq!(masters.universe_level(1), 0.25);
+        // The initial feedback contains the full state
+        assert!(received.contains(&OscMessage::new("/mlc/blackout", vec![OscArg::Float(0.0)])));
+
+        service_obj.shutdown.shutdown();
+        tokio::time::timeout(Duration::from_secs(1), server)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn encode_feedback() {
+        let message = OscMessage::new("/mlc/master", vec![OscArg::Float(0.25)]);
+        let data = message.encode();
+        assert_eq!(&data[..16], b"/mlc/master\0,f\0\0");
+        assert_eq!(&data[16..], &0.25f32.to_be_bytes());
+        assert_eq!(packet::decode(&data), Some(vec![message]));
+
+        let mut feedback = Feedback::default();
+        let values = || {
+            vec![
+                ("/mlc/master".to_string(), OscArg::Float(1.0)),
+                ("/mlc/cue/go".to_string(), OscArg::Int(0)),
+            ]
+        };
+        assert_eq!(feedback.changes(values()).len(), 2);
+        assert!(feedback.changes(values()).is_empty());
+        assert_eq!(
+            feedback.changes([("/mlc/cue/go".to_string(), OscArg::Int(1))]),
+            vec![OscMessage::new("/mlc/cue/go", vec![OscArg::Int(1)])]
+        );
+    }
+}
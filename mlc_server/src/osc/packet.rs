@@ -0,0 +1,156 @@
+//! Encoding and decoding of OSC 1.0 packets, bundles are flattened into their messages.
+
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    String(String),
+    True,
+    False,
+}
+
+impl OscArg {
+    /// The argument as a control value, integers are read as dmx values
+    pub fn control_value(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(i) => Some(*i as f32 / 255.0),
+            OscArg::Float(f) => Some(*f),
+            OscArg::String(_) => None,
+            OscArg::True => Some(1.0),
+            OscArg::False => Some(0.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: impl Into<String>, args: Vec<OscArg>) -> Self {
+        Self {
+            address: address.into(),
+            args,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = vec![];
+        write_string(&mut out, &self.address);
+
+        let tags = self
+            .args
+            .iter()
+            .map(|a| match a {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::String(_) => 's',
+                OscArg::True => 'T',
+                OscArg::False => 'F',
+            })
+            .collect::<String>();
+        write_string(&mut out, &format!(",{tags}"));
+
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(i) => out.extend_from_slice(&i.to_be_bytes()),
+                OscArg::Float(f) => out.extend_from_slice(&f.to_be_bytes()),
+                OscArg::String(s) => write_string(&mut out, s),
+                OscArg::True | OscArg::False => {}
+            }
+        }
+        out
+    }
+}
+
+/// Decodes a packet into its messages, `None` if it is malformed
+pub fn decode(data: &[u8]) -> Option<Vec<OscMessage>> {
+    let mut messages = vec![];
+    decode_into(data, &mut messages)?;
+    Some(messages)
+}
+
+fn decode_into(data: &[u8], messages: &mut Vec<OscMessage>) -> Option<()> {
+    if let Some(mut rest) = data.strip_prefix(BUNDLE_TAG) {
+        // The time tag is ignored, bundled messages are executed immediately
+        rest = rest.get(8..)?;
+        while !rest.is_empty() {
+            let size = i32::from_be_bytes(rest.get(..4)?.try_into().ok()?);
+            let size = usize::try_from(size).ok()?;
+            decode_into(rest.get(4..4 + size)?, messages)?;
+            rest = &rest[4 + size..];
+        }
+        return Some(());
+    }
+
+    let mut reader = Reader(data);
+    let address = reader.string()?;
+    if !address.starts_with('/') {
+        return None;
+    }
+
+    // Messages from old implementations may omit the type tags
+    let tags = if reader.0.is_empty() {
+        String::new()
+    } else {
+        reader.string()?.strip_prefix(',')?.to_string()
+    };
+
+    let mut args = vec![];
+    for tag in tags.chars() {
+        args.push(match tag {
+            'i' => OscArg::Int(i32::from_be_bytes(reader.take(4)?.try_into().ok()?)),
+            'f' => {
+                let value = f32::from_be_bytes(reader.take(4)?.try_into().ok()?);
+                OscArg::Float(finite(value)?)
+            }
+            'h' => OscArg::Int(i64::from_be_bytes(reader.take(8)?.try_into().ok()?) as i32),
+            'd' => {
+                let value = f64::from_be_bytes(reader.take(8)?.try_into().ok()?) as f32;
+                OscArg::Float(finite(value)?)
+            }
+            's' | 'S' => OscArg::String(reader.string()?),
+            'T' => OscArg::True,
+            'F' => OscArg::False,
+            'N' | 'I' => continue,
+            _ => return None,
+        });
+    }
+
+    messages.push(OscMessage { address, args });
+    Some(())
+}
+
+/// NaN and infinite values can't be applied to a control
+fn finite(value: f32) -> Option<f32> {
+    value.is_finite().then_some(value)
+}
+
+fn write_string(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let (value, rest) = self.0.split_at_checked(n)?;
+        self.0 = rest;
+        Some(value)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let end = self.0.iter().position(|b| *b == 0)?;
+        let s = std::str::from_utf8(&self.0[..end]).ok()?.to_string();
+        self.take((end + 4) & !3)?;
+        Some(s)
+    }
+}
@@ -0,0 +1,300 @@
+use crate::cues;
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::project::history::Part;
+use crate::project::Project;
+use crate::universe::{LayerFrame, RuntimeCommand, RuntimeLayer};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use mlc_communication::remoc::rch::watch::Receiver;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::general::ProjectInfo;
+use mlc_communication::services::programmer::{
+    CuePlaybackState, ProgrammerService, ProgrammerServiceError, ProgrammerState, TimecodeState,
+};
+use mlc_data::fixture::blueprint::entities::Color;
+use mlc_data::fixture::patched::attribute::{merge_attribute, FixtureAttribute};
+use mlc_data::fixture::patched::PatchedFixtureId;
+use mlc_data::project::cue::{Cue, CueId, CueList, CueListId, CueValue};
+use mlc_data::project::universe::{UniverseId, UNIVERSE_SIZE};
+use mlc_data::uuid::Uuid;
+use mlc_data::SavePercentage;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::select;
+
+#[rtc::async_trait]
+impl ProgrammerService for ServiceImpl {
+    async fn state(&self) -> Result<Receiver<ProgrammerState>, ProgrammerServiceError> {
+        Ok(self.programmer.subscribe())
+    }
+
+    async fn select(&self, fixtures: Vec<PatchedFixtureId>) -> Result<(), ProgrammerServiceError> {
+        let p = self
+            .validate_project()
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        if let Some(id) = fixtures.iter().find(|f| !p.is_patched(f)) {
+            return Err(ProgrammerServiceError::UnknownFixture(*id));
+        }
+
+        self.programmer.send_modify(|s| s.selection = fixtures);
+        self.update_programmer_layer(&p);
+        Ok(())
+    }
+
+    async fn set_attribute(
+        &self,
+        attribute: FixtureAttribute,
+    ) -> Result<(), ProgrammerServiceError> {
+        let p = self
+            .validate_project()
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        self.programmer.send_modify(|s| {
+            for id in &s.selection {
+                match s.values.iter_mut().find(|(f, _)| f == id) {
+                    Some((_, attributes)) => merge_attribute(attributes, attribute.clone()),
+                    None => s.values.push((*id, vec![attribute.clone()])),
+                }
+            }
+        });
+        self.update_programmer_layer(&p);
+        Ok(())
+    }
+
+    async fn highlight(&self, enabled: bool) -> Result<(), ProgrammerServiceError> {
+        let p = self
+            .validate_project()
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        self.programmer.send_modify(|s| s.highlight = enabled);
+        self.update_programmer_layer(&p);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), ProgrammerServiceError> {
+        self.reset_programmer();
+        Ok(())
+    }
+
+    async fn record(
+        &self,
+        cue_list: Option<CueListId>,
+        name: String,
+    ) -> Result<CueId, ProgrammerServiceError> {
+        let values = self.programmer.borrow().values.clone();
+        if values.is_empty() {
+            return Err(ProgrammerServiceError::EmptyProgrammer);
+        }
+
+        let mut p = self
+            .edit_project("Record cue", &[Part::CueLists])
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        let list = match cue_list {
+            Some(id) => p
+                .cue_lists
+                .iter_mut()
+                .find(|l| l.id == id)
+                .ok_or(ProgrammerServiceError::UnknownCueList(id))?,
+            None => {
+                if p.cue_lists.is_empty() {
+                    p.cue_lists.push(CueList::new("Main"));
+                }
+                &mut p.cue_lists[0]
+            }
+        };
+
+        let cue = Cue {
+            id: Uuid::new_v4(),
+            name,
+            values: values
+                .into_iter()
+                .map(|(fixture, attributes)| CueValue {
+                    fixture,
+                    attributes,
+                })
+                .collect(),
+            timecode: None,
+        };
+        let id = cue.id;
+        list.cues.push(cue);
+        p.commit();
+
+        self.send_info(ProjectInfo::CueListsChanged.into());
+        Ok(id)
+    }
+
+    async fn cue_playback(
+        &self,
+    ) -> Result<Receiver<CuePlaybackState>, ProgrammerServiceError> {
+        Ok(self.cue_playback.subscribe())
+    }
+
+    async fn cue_go(&self, cue_list: Option<CueListId>) -> Result<(), ProgrammerServiceError> {
+        self.step_cue_list(cue_list, cues::go).await
+    }
+
+    async fn cue_back(&self, cue_list: Option<CueListId>) -> Result<(), ProgrammerServiceError> {
+        self.step_cue_list(cue_list, cues::back).await
+    }
+
+    async fn cue_release(
+        &self,
+        cue_list: Option<CueListId>,
+    ) -> Result<(), ProgrammerServiceError> {
+        self.step_cue_list(cue_list, |_, _| None).await
+    }
+
+    async fn timecode(&self) -> Result<Receiver<TimecodeState>, ProgrammerServiceError> {
+        Ok(self.timecode_state.subscribe())
+    }
+
+    async fn cue_set_timecode(
+        &self,
+        cue_list: CueListId,
+        cue: CueId,
+        timecode: Option<Duration>,
+    ) -> Result<(), ProgrammerServiceError> {
+        let mut p = self
+            .edit_project("Change cue timecode", &[Part::CueLists])
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        let list = p
+            .cue_lists
+            .iter_mut()
+            .find(|l| l.id == cue_list)
+            .ok_or(ProgrammerServiceError::UnknownCueList(cue_list))?;
+        list.cues
+            .iter_mut()
+            .find(|c| c.id == cue)
+            .ok_or(ProgrammerServiceError::UnknownCue(cue))?
+            .timecode = timecode;
+        p.commit();
+
+        self.send_info(ProjectInfo::CueListsChanged.into());
+        Ok(())
+    }
+
+    async fn cue_set_beat_step(
+        &self,
+        cue_list: CueListId,
+        beats: Option<u32>,
+    ) -> Result<(), ProgrammerServiceError> {
+        if beats == Some(0) {
+            return Err(ProgrammerServiceError::InvalidBeatStep);
+        }
+        let mut p = self
+            .edit_project("Change cue list beat step", &[Part::CueLists])
+            .await
+            .map_err(|_| ProgrammerServiceError::InvalidProject)?;
+
+        p.cue_lists
+            .iter_mut()
+            .find(|l| l.id == cue_list)
+            .ok_or(ProgrammerServiceError::UnknownCueList(cue_list))?
+            .beat_step = beats;
+        p.commit();
+
+        self.send_info(ProjectInfo::CueListsChanged.into());
+        Ok(())
+    }
+}
+
+impl ServiceImpl {
+    /// Drops selection and values of the programmer and releases its layer
+    pub fn reset_programmer(&self) {
+        self.programmer.send_replace(ProgrammerState::default());
+        self.universe_runtime
+            .cmd(RuntimeCommand::ClearLayer(RuntimeLayer::Programmer));
+    }
+
+    fn update_programmer_layer(&self, project: &Project) {
+        let frames = render_programmer(&self.programmer.borrow(), project);
+        self.universe_runtime.cmd(RuntimeCommand::SetLayer {
+            layer: RuntimeLayer::Programmer,
+            frames,
+        });
+    }
+}
+
+/// Renders the programmer and the cues again when the patch changed, so their values follow the
+/// fixtures to their new addresses
+pub struct LayerRefreshService;
+
+impl MlcServiceSimple for LayerRefreshService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        refresh_layers(
+            res.service_obj.clone(),
+            res.adapt_notifier.clone(),
+            res.shutdown.clone(),
+        )
+    }
+}
+
+async fn refresh_layers(
+    service_obj: AServiceImpl,
+    adapt_notifier: AdaptNotifier,
+    shutdown: ShutdownHandler,
+) {
+    loop {
+        select! {
+            _ = adapt_notifier.wait(AdaptScopes::PATCH | AdaptScopes::UNIVERSES) => {
+                let p = service_obj.project.read().await;
+                service_obj.update_programmer_layer(&p);
+                service_obj.update_cue_layer(&p);
+            }
+            _ = shutdown.wait(ShutdownPhase::Phase1) => {
+                break;
+            }
+        }
+    }
+}
+
+pub fn render_programmer(
+    state: &ProgrammerState,
+    project: &Project,
+) -> HashMap<UniverseId, Box<LayerFrame>> {
+    let highlight = [
+        FixtureAttribute::Intensity(SavePercentage::create(1.0)),
+        FixtureAttribute::Color(Color::White),
+    ];
+    let highlighted = if state.highlight {
+        state.selection.as_slice()
+    } else {
+        &[]
+    };
+
+    let values = state
+        .values
+        .iter()
+        .map(|(id, attributes)| (id, attributes.as_slice()))
+        .chain(highlighted.iter().map(|id| (id, highlight.as_slice())))
+        .collect::<Vec<_>>();
+
+    render_values(&values, project)
+}
+
+pub fn render_values(
+    values: &[(&PatchedFixtureId, &[FixtureAttribute])],
+    project: &Project,
+) -> HashMap<UniverseId, Box<LayerFrame>> {
+    let mut frames = HashMap::new();
+    for universe in &project.universes {
+        let rendered = universe.render_attributes(values.iter().copied());
+        if rendered.is_empty() {
+            continue;
+        }
+
+        let mut frame = Box::new([None; UNIVERSE_SIZE]);
+        for (address, value) in rendered {
+            frame[address.take() - 1] = Some(value);
+        }
+        frames.insert(universe.id, frame);
+    }
+    frames
+}
@@ -1,30 +1,47 @@
 use crate::misc::{AdaptScopes, ShutdownPhase};
-use crate::project::project_loader::Plm;
+use crate::project::history::Part;
+use crate::project::project_loader::{BoxedLoader, Plm};
+use crate::script;
 use crate::universe::{RuntimeCommand, UniverseUpdate};
 use crate::ServiceImpl;
 use chrono::Local;
 use mlc_communication::remoc::rch::mpsc::{Receiver, Sender};
+use mlc_communication::remoc::rch::watch;
 use mlc_communication::remoc::rtc;
 use mlc_communication::services::general::{Info, ProjectInfo};
 use mlc_communication::services::project::{
-    FixtureBlueprintHead, ProjectService, ProjectServiceError,
+    EditHistory, FixtureBlueprintHead, ProjectService, ProjectServiceError, UniverseHead,
 };
 use mlc_communication::services::project_selection::{
-    ProjectIdent, ProjectSelectionService, ProjectSelectionServiceError,
+    ImportOptions, ProjectIdent, ProjectSelectionService, ProjectSelectionServiceError,
 };
-use mlc_data::endpoints::{EndpointConfig, EndpointMapping, EndpointSpeed};
+use mlc_data::endpoints::{
+    EndpointConfig, EndpointMapping, MAX_ARTNET_PORT_ADDRESS, MAX_SACN_PRIORITY,
+};
+use mlc_data::fixture::patched::output::OutputProcessing;
+use mlc_data::fixture::patched::{PatchedFixture, PatchedFixtureId};
 use mlc_data::misc::ErrIgnore;
+use mlc_data::project::audio::AudioSettings;
+use mlc_data::project::cue::CueList;
+use mlc_data::project::group::{FixtureGroup, GroupId};
+use mlc_data::project::midi::MidiSettings;
+use mlc_data::project::osc::OscSettings;
+use mlc_data::project::schedule::{ScheduleAction, ScheduleSettings};
+use mlc_data::project::script::Script;
+use mlc_data::project::timecode::TimecodeSettings;
 use mlc_data::project::universe::{
-    FixtureAddress, FixtureUniverse, UniverseAddress, UniverseId, UniverseSlot, UNIVERSE_SIZE,
+    FixtureAddress, FixtureUniverse, ParkedChannel, UniverseAddress, UniverseId,
 };
-use mlc_data::project::{ProjectSettings, ToFileName};
+use mlc_data::project::{ProjectBackup, ProjectSettings, ToFileName};
+use mlc_data::timecode::MSC_ALL_CALL;
 use mlc_data::{
     fixture::blueprint::FixtureBlueprint,
     project::{ProjectMetadata, ProjectType},
+    uuid::Uuid,
     DynamicResult,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::error;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -32,16 +49,45 @@ use tokio::select;
 use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
 use tracing::{error, info, warn};
 
+mod archive;
+mod backup;
+pub mod history;
+mod migration;
 mod project_loader;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
+    /// Version of the stored format, older files are migrated when loaded
+    #[serde(default)]
+    pub schema_version: u32,
     #[serde(flatten)]
     pub metadata: ProjectMetadata,
     pub blueprints: Vec<FixtureBlueprint>,
     pub settings: ProjectSettings,
     pub universes: Vec<FixtureUniverse>,
     pub endpoint_mapping: EndpointMapping,
+    #[serde(default)]
+    pub cue_lists: Vec<CueList>,
+    #[serde(default)]
+    pub groups: Vec<FixtureGroup>,
+    #[serde(default)]
+    pub parked: Vec<ParkedChannel>,
+    #[serde(default)]
+    pub osc: OscSettings,
+    #[serde(default)]
+    pub midi: MidiSettings,
+    #[serde(default)]
+    pub timecode: TimecodeSettings,
+    #[serde(default)]
+    pub audio: AudioSettings,
+    #[serde(default)]
+    pub schedule: ScheduleSettings,
+    #[serde(default)]
+    pub scripts: Vec<Script>,
+    /// Id of the next created universe, ids of deleted universes are not handed out again.
+    /// Exceeds the last valid id once all ids are used up
+    #[serde(default)]
+    pub next_universe_id: u32,
 }
 
 #[rtc::async_trait]
@@ -90,13 +136,16 @@ impl ProjectService for ServiceImpl {
                 msg: "Not all specified blueprints could be found".to_string(),
             });
         }
-        let mut p = self.project.write().await;
+        let mut p = self
+            .edit_project("Import fixture blueprints", &[Part::Blueprints])
+            .await?;
 
         p.blueprints
             .retain(|b| !identifiers.contains(&b.meta.identifier));
         p.blueprints.append(&mut blueprints);
         p.blueprints
             .sort_by(|b1, b2| b1.meta.identifier.cmp(&b2.meta.identifier));
+        p.commit();
 
         self.send_info(ProjectInfo::BlueprintsChanged.into());
 
@@ -107,18 +156,87 @@ impl ProjectService for ServiceImpl {
         Ok(self.project.read().await.blueprints.clone())
     }
 
-    async fn universe_list(&self) -> Result<Vec<UniverseId>, ProjectServiceError> {
+    async fn universe_list(&self) -> Result<Vec<UniverseHead>, ProjectServiceError> {
         Ok(self
             .project
             .read()
             .await
             .universes
             .iter()
-            .enumerate()
-            .map(|(i, _)| i as u16 + 1)
+            .map(|u| UniverseHead {
+                id: u.id,
+                name: u.name.clone(),
+            })
             .collect::<Vec<_>>())
     }
 
+    async fn universe_create(&self, name: String) -> Result<UniverseId, ProjectServiceError> {
+        let mut p = self.edit_project("Create universe", &[Part::Universes]).await?;
+        let id = p
+            .allocate_universe_id()
+            .ok_or(ProjectServiceError::NoUniverseIdLeft)?;
+        p.universes.push(FixtureUniverse::new(id, name));
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::UNIVERSES);
+        self.send_info(ProjectInfo::UniverseListChanged.into());
+        Ok(id)
+    }
+
+    async fn universe_rename(
+        &self,
+        universe: UniverseId,
+        name: String,
+    ) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Rename universe", &[Part::Universes]).await?;
+        p.universe_mut(universe)
+            .ok_or(ProjectServiceError::UnknownUniverse(universe))?
+            .name = name;
+        p.commit();
+
+        self.send_info(ProjectInfo::UniverseListChanged.into());
+        Ok(())
+    }
+
+    async fn universe_delete(&self, universe: UniverseId) -> Result<(), ProjectServiceError> {
+        let parts = &[
+            Part::Universes,
+            Part::Endpoints,
+            Part::Parked,
+            Part::UniverseMasters,
+        ];
+        let mut p = self.edit_project("Delete universe", parts).await?;
+        let u = p
+            .universe(universe)
+            .ok_or(ProjectServiceError::UnknownUniverse(universe))?;
+        if !u.fixtures.is_empty() {
+            return Err(ProjectServiceError::UniversePatched(universe));
+        }
+        if p
+            .endpoint_mapping
+            .endpoints
+            .get(&universe)
+            .is_some_and(|e| !e.is_empty())
+        {
+            return Err(ProjectServiceError::UniverseMapped(universe));
+        }
+
+        p.universes.retain(|u| u.id != universe);
+        p.endpoint_mapping.endpoints.remove(&universe);
+        let parked = p.parked.len();
+        p.parked.retain(|(a, _)| a.universe() != universe);
+        if p.parked.len() != parked {
+            self.send_info(ProjectInfo::ParkedChanged.into());
+        }
+        self.update_masters(|m| m.universes.retain(|(u, _)| *u != universe));
+        p.commit();
+
+        self.adapt_notifier
+            .notify(AdaptScopes::UNIVERSES | AdaptScopes::PARKED);
+        self.send_info(ProjectInfo::UniverseListChanged.into());
+        Ok(())
+    }
+
     async fn universe_sub(
         &self,
         universe: UniverseId,
@@ -138,7 +256,7 @@ impl ProjectService for ServiceImpl {
 
         let shutdown = self.shutdown.clone();
         tokio::task::spawn(async move {
-            let mut sub = controller.subscribe_universe(universe);
+            let mut sub = controller.subscribe_raw_universe(universe);
             loop {
                 select! {
                     _ = shutdown.wait(ShutdownPhase::Phase1) => break,
@@ -173,14 +291,79 @@ impl ProjectService for ServiceImpl {
         Ok((rx_1, tx_2))
     }
 
+    async fn endpoint_mapping(&self) -> Result<EndpointMapping, ProjectServiceError> {
+        let p = self.validate_project().await?;
+        Ok(p.endpoint_mapping.clone())
+    }
+
+    async fn endpoint_add(
+        &self,
+        universe: UniverseId,
+        config: EndpointConfig,
+    ) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Add endpoint", &[Part::Endpoints]).await?;
+        p.modify_endpoints(universe, |endpoints| {
+            endpoints.push(config);
+            Ok(())
+        })?;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::ENDPOINTS);
+        self.send_info(ProjectInfo::EndpointsChanged.into());
+        Ok(())
+    }
+
+    async fn endpoint_update(
+        &self,
+        universe: UniverseId,
+        index: usize,
+        config: EndpointConfig,
+    ) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Update endpoint", &[Part::Endpoints]).await?;
+        p.modify_endpoints(universe, |endpoints| {
+            *endpoints
+                .get_mut(index)
+                .ok_or(ProjectServiceError::UnknownEndpoint(universe, index))? = config;
+            Ok(())
+        })?;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::ENDPOINTS);
+        self.send_info(ProjectInfo::EndpointsChanged.into());
+        Ok(())
+    }
+
+    async fn endpoint_remove(
+        &self,
+        universe: UniverseId,
+        index: usize,
+    ) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Remove endpoint", &[Part::Endpoints]).await?;
+        p.modify_endpoints(universe, |endpoints| {
+            if index >= endpoints.len() {
+                return Err(ProjectServiceError::UnknownEndpoint(universe, index));
+            }
+            endpoints.remove(index);
+            Ok(())
+        })?;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::ENDPOINTS);
+        self.send_info(ProjectInfo::EndpointsChanged.into());
+        Ok(())
+    }
+
     async fn get_settings(&self) -> Result<ProjectSettings, ProjectServiceError> {
         let p = self.validate_project().await?;
         Ok(p.settings.clone())
     }
 
     async fn update_settings(&self, settings: ProjectSettings) -> Result<(), ProjectServiceError> {
-        let mut p = self.validate_project_mut().await?;
+        let mut p = self
+            .edit_project("Change project settings", &[Part::Settings])
+            .await?;
         p.settings = settings;
+        p.commit();
         self.adapt_notifier.notify(AdaptScopes::SETTINGS);
         self.send_info(ProjectInfo::SettingsChanged.into());
         Ok(())
@@ -190,6 +373,319 @@ impl ProjectService for ServiceImpl {
         let p = self.validate_project().await?;
         Ok(p.metadata.clone())
     }
+
+    async fn group_list(&self) -> Result<Vec<FixtureGroup>, ProjectServiceError> {
+        let p = self.validate_project().await?;
+        Ok(p.groups.clone())
+    }
+
+    async fn group_create(
+        &self,
+        name: String,
+        fixtures: Vec<PatchedFixtureId>,
+    ) -> Result<GroupId, ProjectServiceError> {
+        let mut p = self.edit_project("Create group", &[Part::Groups]).await?;
+        if let Some(f) = fixtures.iter().find(|f| !p.is_patched(f)) {
+            return Err(ProjectServiceError::UnknownFixture(*f));
+        }
+
+        let id = Uuid::new_v4();
+        p.groups.push(FixtureGroup { id, name, fixtures });
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::PATCH);
+        self.send_info(ProjectInfo::GroupsChanged.into());
+        Ok(id)
+    }
+
+    async fn group_update(&self, group: FixtureGroup) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Update group", &[Part::Groups]).await?;
+        if let Some(f) = group.fixtures.iter().find(|f| !p.is_patched(f)) {
+            return Err(ProjectServiceError::UnknownFixture(*f));
+        }
+
+        let existing = p
+            .groups
+            .iter_mut()
+            .find(|g| g.id == group.id)
+            .ok_or(ProjectServiceError::UnknownGroup(group.id))?;
+        *existing = group;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::PATCH);
+        self.send_info(ProjectInfo::GroupsChanged.into());
+        Ok(())
+    }
+
+    async fn group_delete(&self, group: GroupId) -> Result<(), ProjectServiceError> {
+        let parts = &[Part::Groups, Part::GroupMasters];
+        let mut p = self.edit_project("Delete group", parts).await?;
+        let len = p.groups.len();
+        p.groups.retain(|g| g.id != group);
+        if p.groups.len() == len {
+            return Err(ProjectServiceError::UnknownGroup(group));
+        }
+        self.update_masters(|m| m.groups.retain(|(g, _)| *g != group));
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::PATCH);
+        self.send_info(ProjectInfo::GroupsChanged.into());
+        Ok(())
+    }
+
+    async fn fixture_output(
+        &self,
+        fixture: PatchedFixtureId,
+    ) -> Result<OutputProcessing, ProjectServiceError> {
+        let p = self.validate_project().await?;
+        p.fixture(&fixture)
+            .map(|f| f.output.clone())
+            .ok_or(ProjectServiceError::UnknownFixture(fixture))
+    }
+
+    async fn update_fixture_output(
+        &self,
+        fixture: PatchedFixtureId,
+        output: OutputProcessing,
+    ) -> Result<(), ProjectServiceError> {
+        if let Some(c) = output.channels.iter().find(|c| c.min.take() > c.max.take()) {
+            return Err(ProjectServiceError::InvalidOutputLimits(c.channel.clone()));
+        }
+        let mut p = self
+            .edit_project("Change fixture output", &[Part::Universes])
+            .await?;
+        let f = p
+            .universes
+            .iter_mut()
+            .find_map(|u| u.fixtures.get_mut(&fixture))
+            .ok_or(ProjectServiceError::UnknownFixture(fixture))?;
+        f.output = output;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::PATCH);
+        self.send_info(ProjectInfo::FixtureOutputChanged { fixture }.into());
+        Ok(())
+    }
+
+    async fn parked_list(&self) -> Result<Vec<ParkedChannel>, ProjectServiceError> {
+        let p = self.validate_project().await?;
+        Ok(p.parked.clone())
+    }
+
+    async fn park(&self, channels: Vec<ParkedChannel>) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Park channels", &[Part::Parked]).await?;
+        if let Some((a, _)) = channels
+            .iter()
+            .find(|(a, _)| p.universe(a.universe()).is_none())
+        {
+            return Err(ProjectServiceError::UnknownUniverse(a.universe()));
+        }
+
+        for (address, value) in channels {
+            match p.parked.iter_mut().find(|(a, _)| *a == address) {
+                Some(parked) => parked.1 = value,
+                None => p.parked.push((address, value)),
+            }
+        }
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::PARKED);
+        self.send_info(ProjectInfo::ParkedChanged.into());
+        Ok(())
+    }
+
+    async fn unpark(&self, addresses: Vec<FixtureAddress>) -> Result<(), ProjectServiceError> {
+        let mut p = self.edit_project("Unpark channels", &[Part::Parked]).await?;
+        p.parked.retain(|(a, _)| !addresses.contains(a));
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::PARKED);
+        self.send_info(ProjectInfo::ParkedChanged.into());
+        Ok(())
+    }
+
+    async fn osc_settings(&self) -> Result<OscSettings, ProjectServiceError> {
+        Ok(self.validate_project().await?.osc.clone())
+    }
+
+    async fn update_osc_settings(&self, settings: OscSettings) -> Result<(), ProjectServiceError> {
+        if settings.port == 0 {
+            return Err(ProjectServiceError::InvalidOscPort);
+        }
+        let mut addresses = HashSet::new();
+        for mapping in &settings.mappings {
+            if !mapping.address.starts_with('/') {
+                return Err(ProjectServiceError::InvalidOscAddress(mapping.address.clone()));
+            }
+            if !addresses.insert(&mapping.address) {
+                return Err(ProjectServiceError::DuplicateOscAddress(mapping.address.clone()));
+            }
+        }
+
+        let mut p = self.edit_project("Change OSC settings", &[Part::Osc]).await?;
+        p.osc = settings;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::CONTROL);
+        self.send_info(ProjectInfo::SettingsChanged.into());
+        Ok(())
+    }
+
+    async fn midi_settings(&self) -> Result<MidiSettings, ProjectServiceError> {
+        Ok(self.validate_project().await?.midi.clone())
+    }
+
+    async fn update_midi_settings(
+        &self,
+        settings: MidiSettings,
+    ) -> Result<(), ProjectServiceError> {
+        let mut controls = HashSet::new();
+        for mapping in &settings.mappings {
+            if !mapping.control.is_valid() {
+                return Err(ProjectServiceError::InvalidMidiControl(mapping.control));
+            }
+            if !controls.insert(mapping.control) {
+                return Err(ProjectServiceError::DuplicateMidiControl(mapping.control));
+            }
+        }
+
+        let mut p = self.edit_project("Change MIDI settings", &[Part::Midi]).await?;
+        p.midi = settings;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::CONTROL);
+        self.send_info(ProjectInfo::SettingsChanged.into());
+        Ok(())
+    }
+
+    async fn timecode_settings(&self) -> Result<TimecodeSettings, ProjectServiceError> {
+        Ok(self.validate_project().await?.timecode.clone())
+    }
+
+    async fn update_timecode_settings(
+        &self,
+        settings: TimecodeSettings,
+    ) -> Result<(), ProjectServiceError> {
+        if settings.msc_device_id > MSC_ALL_CALL {
+            return Err(ProjectServiceError::InvalidMscDeviceId(settings.msc_device_id));
+        }
+        if settings.ltc.as_ref().is_some_and(|ltc| !ltc.is_valid()) {
+            return Err(ProjectServiceError::InvalidAudioInput);
+        }
+
+        let mut p = self
+            .edit_project("Change timecode settings", &[Part::Timecode])
+            .await?;
+        if let Some(list) = settings.cue_list
+            && !p.cue_lists.iter().any(|l| l.id == list)
+        {
+            return Err(ProjectServiceError::UnknownCueList(list));
+        }
+        p.timecode = settings;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::CONTROL);
+        self.send_info(ProjectInfo::SettingsChanged.into());
+        Ok(())
+    }
+
+    async fn audio_settings(&self) -> Result<AudioSettings, ProjectServiceError> {
+        Ok(self.validate_project().await?.audio.clone())
+    }
+
+    async fn update_audio_settings(
+        &self,
+        settings: AudioSettings,
+    ) -> Result<(), ProjectServiceError> {
+        if settings.input.as_ref().is_some_and(|input| !input.is_valid()) {
+            return Err(ProjectServiceError::InvalidAudioInput);
+        }
+        if let Some(mapping) = settings.mappings.iter().find(|m| !m.is_valid()) {
+            return Err(ProjectServiceError::InvalidAudioMapping(mapping.feature));
+        }
+
+        let mut p = self.edit_project("Change audio settings", &[Part::Audio]).await?;
+        p.audio = settings;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::CONTROL);
+        self.send_info(ProjectInfo::SettingsChanged.into());
+        Ok(())
+    }
+
+    async fn schedule_settings(&self) -> Result<ScheduleSettings, ProjectServiceError> {
+        Ok(self.validate_project().await?.schedule.clone())
+    }
+
+    async fn update_schedule_settings(
+        &self,
+        settings: ScheduleSettings,
+    ) -> Result<(), ProjectServiceError> {
+        if !(-90.0..=90.0).contains(&settings.latitude)
+            || !(-180.0..=180.0).contains(&settings.longitude)
+        {
+            return Err(ProjectServiceError::InvalidCoordinates(
+                settings.latitude,
+                settings.longitude,
+            ));
+        }
+        for entry in &settings.entries {
+            let valid = match &entry.action {
+                ScheduleAction::Control { action, .. } => !action.is_pattern(),
+                ScheduleAction::PlayRecording { name, .. } => !name.is_empty(),
+                ScheduleAction::StopPlayback => true,
+            };
+            if !valid {
+                return Err(ProjectServiceError::InvalidScheduleAction(entry.name.clone()));
+            }
+        }
+
+        let mut p = self.edit_project("Change schedule", &[Part::Schedule]).await?;
+        p.schedule = settings;
+        p.commit();
+
+        self.adapt_notifier.notify(AdaptScopes::CONTROL);
+        self.send_info(ProjectInfo::SettingsChanged.into());
+        Ok(())
+    }
+
+    async fn scripts(&self) -> Result<Vec<Script>, ProjectServiceError> {
+        Ok(self.validate_project().await?.scripts.clone())
+    }
+
+    async fn update_scripts(&self, scripts: Vec<Script>) -> Result<(), ProjectServiceError> {
+        let mut names = HashSet::new();
+        for script in &scripts {
+            if script.name.is_empty() || !names.insert(script.name.as_str()) {
+                return Err(ProjectServiceError::InvalidScriptName(script.name.clone()));
+            }
+            if let Err(e) = script::compile(&script.source) {
+                return Err(ProjectServiceError::InvalidScript {
+                    name: script.name.clone(),
+                    msg: e.to_string(),
+                });
+            }
+        }
+
+        let mut p = self.edit_project("Change scripts", &[Part::Scripts]).await?;
+        p.scripts = scripts;
+        p.commit();
+
+        self.send_info(ProjectInfo::SettingsChanged.into());
+        Ok(())
+    }
+
+    async fn history(&self) -> Result<watch::Receiver<EditHistory>, ProjectServiceError> {
+        Ok(self.history_state.subscribe())
+    }
+
+    async fn undo(&self) -> Result<String, ProjectServiceError> {
+        self.undo_edit().await
+    }
+
+    async fn redo(&self) -> Result<String, ProjectServiceError> {
+        self.redo_edit().await
+    }
 }
 
 impl ServiceImpl {
@@ -232,6 +728,14 @@ fn to_po_err<E: error::Error>(e: E) -> ProjectSelectionServiceError {
     ProjectSelectionServiceError::ProjectOpenError(format!("{e:?}"))
 }
 
+fn to_pd_err<E: std::fmt::Debug>(e: E) -> ProjectSelectionServiceError {
+    error!("project delete error: {:?}", e);
+    ProjectSelectionServiceError::ProjectDeleteError(format!("{e:?}"))
+}
+
+/// Deleted projects are moved to this folder in the projects folder
+const TRASH_DIR: &str = "trash";
+
 async fn get_valid_project_dir() -> DynamicResult<PathBuf> {
     let projects_dir = get_base_app_dir().join("projects");
     tokio::fs::create_dir_all(&projects_dir)
@@ -343,42 +847,230 @@ impl ProjectSelectionService for ServiceImpl {
     }
 
     async fn open(&self, ident: ProjectIdent) -> Result<bool, ProjectSelectionServiceError> {
-        let projects_dir = get_valid_project_dir()
+        let found = find_project_file(&ident)
             .await
             .map_err(|e| ProjectSelectionServiceError::ProjectOpenError(e.to_string()))?;
+        let Some((path, loader)) = found else {
+            info!("Project with ident: {ident} not found");
+            return Ok(false);
+        };
 
-        for loader in Plm::loaders() {
-            let path = projects_dir.join(format!("{}.{}", &ident, loader.kind().extension()));
-            if path.exists() && path.is_file() {
-                let content = tokio::fs::read(path).await.map_err(to_po_err)?;
-                let mut p = loader.load_project(content).map_err(|e| {
-                    ProjectSelectionServiceError::ProjectOpenError(format!("{e:?}"))
-                })?;
+        let content = tokio::fs::read(path).await.map_err(to_po_err)?;
+        let loaded = loader.load_project(content).map_err(|e| e.to_string());
+        let mut p = match loaded {
+            Ok(p) => p,
+            Err(error) => {
+                error!("Project {ident} is corrupt: {error}");
+                return Err(ProjectSelectionServiceError::ProjectCorrupt {
+                    backup: backup::find_fallback(&ident).await,
+                    ident,
+                    error,
+                });
+            }
+        };
 
-                p.metadata.project_type = loader.kind();
-                p.metadata.file_name = ident.clone();
+        p.metadata.project_type = loader.kind();
+        p.metadata.file_name = ident.clone();
 
-                {
-                    *self.project.write().await = p;
-                    *self.valid_project.write().await = true;
-                }
-                self.adapt_notifier.notify(AdaptScopes::all());
-                return Ok(true);
-            }
+        {
+            *self.project.write().await = p;
+            *self.valid_project.write().await = true;
+        }
+        self.clear_history();
+        self.reset_programmer();
+        self.reset_cue_playback();
+        self.adapt_notifier.notify(AdaptScopes::all());
+        Ok(true)
+    }
+
+    async fn delete(&self, ident: ProjectIdent) -> Result<(), ProjectSelectionServiceError> {
+        if self.is_open_project(&ident).await {
+            return Err(ProjectSelectionServiceError::ProjectInUse(ident));
+        }
+        let (path, loader) = find_project_file(&ident)
+            .await
+            .map_err(to_pd_err)?
+            .ok_or_else(|| ProjectSelectionServiceError::UnknownProject(ident.clone()))?;
+
+        let trash_dir = get_valid_project_dir()
+            .await
+            .map_err(to_pd_err)?
+            .join(TRASH_DIR);
+        tokio::fs::create_dir_all(&trash_dir)
+            .await
+            .map_err(to_pd_err)?;
+        let trashed_name = format!("{ident}_{}", Local::now().format("%Y%m%d_%H%M%S"));
+        let trashed = trash_dir.join(format!("{trashed_name}.{}", loader.kind().extension()));
+        tokio::fs::rename(path, &trashed).await.map_err(to_pd_err)?;
+        backup::move_backups(&ident, &trash_dir.join(format!("{trashed_name}_backups")))
+            .await
+            .map_err(to_pd_err)?;
+
+        info!("Moved project {ident} to {}", trashed.display());
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        ident: ProjectIdent,
+        name: String,
+    ) -> Result<ProjectIdent, ProjectSelectionServiceError> {
+        let to_err = |e: String| ProjectSelectionServiceError::ProjectRenameError(e);
+        let (path, loader, mut p) = load_project_file(&ident).await?;
+        let kind = loader.kind();
+
+        let new_ident = if name.to_project_file_name() == ident {
+            ident.clone()
+        } else {
+            new_file_name(&name, &kind, to_err).await?
+        };
+        p.metadata.name = name.clone();
+        p.metadata.file_name = new_ident.clone();
+        p.metadata.project_type = kind;
+        p.save().await.map_err(to_err)?;
+        if new_ident != ident {
+            tokio::fs::remove_file(path)
+                .await
+                .map_err(|e| to_err(e.to_string()))?;
+            let backups = backup::backup_dir(&new_ident)
+                .await
+                .map_err(|e| to_err(e.to_string()))?;
+            backup::move_backups(&ident, &backups)
+                .await
+                .map_err(|e| to_err(e.to_string()))?;
+        }
+
+        if self.is_open_project(&ident).await {
+            let mut open = self.project.write().await;
+            open.metadata.name = name;
+            open.metadata.file_name = new_ident.clone();
+            self.send_info(ProjectInfo::SettingsChanged.into());
         }
+        info!("Renamed project {ident} to {new_ident}");
+        Ok(new_ident)
+    }
+
+    async fn duplicate(
+        &self,
+        ident: ProjectIdent,
+        name: String,
+    ) -> Result<ProjectIdent, ProjectSelectionServiceError> {
+        let (_, loader, mut p) = load_project_file(&ident).await?;
+        let kind = loader.kind();
+
+        let to_err = ProjectSelectionServiceError::ProjectDuplicateError;
+        let new_ident = new_file_name(&name, &kind, to_err).await?;
+        p.metadata.name = name;
+        p.metadata.created_at = Local::now();
+        p.metadata.file_name = new_ident.clone();
+        p.metadata.project_type = kind;
+        p.save()
+            .await
+            .map_err(ProjectSelectionServiceError::ProjectDuplicateError)?;
+
+        info!("Duplicated project {ident} as {new_ident}");
+        Ok(new_ident)
+    }
+
+    async fn export(&self, ident: ProjectIdent) -> Result<Vec<u8>, ProjectSelectionServiceError> {
+        // The open project is exported with its unsaved edits
+        let open = if self.is_open_project(&ident).await {
+            Some(self.project.read().await.clone())
+        } else {
+            None
+        };
+        archive::export_project(&ident, open).await
+    }
+
+    async fn import(
+        &self,
+        archive: Vec<u8>,
+        options: ImportOptions,
+    ) -> Result<ProjectIdent, ProjectSelectionServiceError> {
+        archive::import_project(archive, options).await
+    }
+
+    async fn backups(
+        &self,
+        ident: ProjectIdent,
+    ) -> Result<Vec<ProjectBackup>, ProjectSelectionServiceError> {
+        if !is_valid_ident(&ident) {
+            return Err(ProjectSelectionServiceError::UnknownProject(ident));
+        }
+        backup::list_backups(&ident)
+            .await
+            .map_err(|e| ProjectSelectionServiceError::ProjectBackupError(e.to_string()))
+    }
+
+    async fn restore_backup(
+        &self,
+        ident: ProjectIdent,
+        backup: String,
+    ) -> Result<(), ProjectSelectionServiceError> {
+        if self.is_open_project(&ident).await {
+            return Err(ProjectSelectionServiceError::ProjectInUse(ident));
+        }
+        backup::restore_backup(&ident, &backup).await
+    }
+}
+
+impl ServiceImpl {
+    async fn is_open_project(&self, ident: &str) -> bool {
+        self.project_valid().await && self.project.read().await.metadata.file_name == ident
+    }
+}
+
+/// The file and loader of a saved project
+/// Identifiers are plain file names without extension
+fn is_valid_ident(ident: &str) -> bool {
+    !(ident.is_empty() || ident.contains(['/', '\\']) || ident.starts_with('.'))
+}
 
-        info!("Project with ident: {ident} not found");
-        Ok(false)
+async fn find_project_file(ident: &str) -> DynamicResult<Option<(PathBuf, &'static BoxedLoader)>> {
+    if !is_valid_ident(ident) {
+        return Ok(None);
     }
+    let projects_dir = get_valid_project_dir().await?;
+    Ok(Plm::loaders().iter().find_map(|loader| {
+        let path = projects_dir.join(format!("{}.{}", ident, loader.kind().extension()));
+        path.is_file().then_some((path, loader))
+    }))
+}
 
-    async fn delete(&self, _ident: ProjectIdent) -> Result<(), ProjectSelectionServiceError> {
-        unimplemented!()
+async fn load_project_file(
+    ident: &str,
+) -> Result<(PathBuf, &'static BoxedLoader, Project), ProjectSelectionServiceError> {
+    let (path, loader) = find_project_file(ident)
+        .await
+        .map_err(|e| ProjectSelectionServiceError::ProjectOpenError(e.to_string()))?
+        .ok_or_else(|| ProjectSelectionServiceError::UnknownProject(ident.to_string()))?;
+    let content = tokio::fs::read(&path).await.map_err(to_po_err)?;
+    let p = loader
+        .load_project(content)
+        .map_err(|e| ProjectSelectionServiceError::ProjectOpenError(format!("{e:?}")))?;
+    Ok((path, loader, p))
+}
+
+/// A free file name for the project name, other errors are reported by `to_err`
+async fn new_file_name(
+    name: &str,
+    kind: &ProjectType,
+    to_err: impl Fn(String) -> ProjectSelectionServiceError,
+) -> Result<String, ProjectSelectionServiceError> {
+    if name.to_project_file_name().is_empty() {
+        return Err(ProjectSelectionServiceError::InvalidProjectName(
+            name.to_string(),
+        ));
     }
+    make_save_file_name(name, kind)
+        .await
+        .map_err(|e| to_err(e.to_string()))
 }
 
 impl Project {
     fn new() -> Self {
         Self {
+            schema_version: migration::SCHEMA_VERSION,
             metadata: ProjectMetadata {
                 name: "Default invalid project".into(),
                 last_saved: Local::now(),
@@ -390,16 +1082,141 @@ impl Project {
             settings: ProjectSettings {
                 autosave: Some(Duration::from_secs(30 * 60)),
                 save_on_quit: true,
+                backups: ProjectSettings::DEFAULT_BACKUPS,
             },
-            universes: vec![FixtureUniverse {
-                addresses: [UniverseSlot::Unused; UNIVERSE_SIZE],
-                fixtures: HashMap::new(),
-            }],
+            universes: vec![FixtureUniverse::new(1, "Universe 1".to_string())],
             endpoint_mapping: EndpointMapping {
                 endpoints: HashMap::new(),
             },
+            cue_lists: vec![],
+            groups: vec![],
+            parked: vec![],
+            osc: OscSettings::default(),
+            midi: MidiSettings::default(),
+            timecode: TimecodeSettings::default(),
+            audio: AudioSettings::default(),
+            schedule: ScheduleSettings::default(),
+            scripts: vec![],
+            next_universe_id: 2,
+        }
+    }
+}
+
+impl Project {
+    pub fn is_patched(&self, fixture: &PatchedFixtureId) -> bool {
+        self.universes
+            .iter()
+            .any(|u| u.fixtures.contains_key(fixture))
+    }
+
+    pub fn fixture(&self, fixture: &PatchedFixtureId) -> Option<&PatchedFixture> {
+        self.universes.iter().find_map(|u| u.fixtures.get(fixture))
+    }
+
+    pub fn universe(&self, universe: UniverseId) -> Option<&FixtureUniverse> {
+        self.universes.iter().find(|u| u.id == universe)
+    }
+
+    pub fn universe_mut(&mut self, universe: UniverseId) -> Option<&mut FixtureUniverse> {
+        self.universes.iter_mut().find(|u| u.id == universe)
+    }
+
+    /// Hands out the id for a new universe, projects saved before the counter start after the
+    /// highest existing id
+    fn allocate_universe_id(&mut self) -> Option<UniverseId> {
+        let after_existing = self.universes.iter().map(|u| u.id as u32 + 1).max();
+        let id = self
+            .next_universe_id
+            .max(after_existing.unwrap_or(1))
+            .max(1);
+        let universe = UniverseId::try_from(id).ok()?;
+        self.next_universe_id = id + 1;
+        Some(universe)
+    }
+
+    /// Applies a change to the endpoints of a universe, the mapping is left untouched if
+    /// the change fails or the result is invalid
+    fn modify_endpoints(
+        &mut self,
+        universe: UniverseId,
+        f: impl FnOnce(&mut Vec<EndpointConfig>) -> Result<(), ProjectServiceError>,
+    ) -> Result<(), ProjectServiceError> {
+        if self.universe(universe).is_none() {
+            return Err(ProjectServiceError::UnknownUniverse(universe));
+        }
+
+        let mut mapping = self.endpoint_mapping.clone();
+        f(mapping.endpoints.entry(universe).or_default())?;
+        mapping.endpoints.retain(|_, e| !e.is_empty());
+        validate_endpoints(&mapping)?;
+
+        self.endpoint_mapping = mapping;
+        Ok(())
+    }
+}
+
+fn validate_endpoints(mapping: &EndpointMapping) -> Result<(), ProjectServiceError> {
+    let mut sacn = HashSet::new();
+    let mut sync_speeds = HashMap::new();
+    let mut usb = HashSet::new();
+    let mut artnet = HashSet::new();
+
+    for config in mapping.endpoints.values().flatten() {
+        if config.speed().is_some_and(|s| s.ms() == 0) {
+            return Err(ProjectServiceError::InvalidEndpointSpeed);
+        }
+        match config {
+            EndpointConfig::Sacn {
+                universe,
+                speed,
+                priority,
+                source_name,
+                sync_universe,
+                ..
+            } => {
+                for u in std::iter::once(universe).chain(sync_universe) {
+                    if !(1..=63999).contains(u) {
+                        return Err(ProjectServiceError::InvalidSacnUniverse(*u));
+                    }
+                }
+                if !sacn.insert(*universe) {
+                    return Err(ProjectServiceError::DuplicateSacnUniverse(*universe));
+                }
+                if *priority > MAX_SACN_PRIORITY {
+                    return Err(ProjectServiceError::InvalidSacnPriority(*priority));
+                }
+                if let Some(name) = source_name
+                    && name.len() > 63
+                {
+                    return Err(ProjectServiceError::InvalidSacnSourceName(name.clone()));
+                }
+                if let Some(sync) = sync_universe
+                    && *sync_speeds.entry(*sync).or_insert(*speed) != *speed
+                {
+                    return Err(ProjectServiceError::SacnSyncSpeedMismatch(*sync));
+                }
+            }
+            EndpointConfig::Usb { port, .. } => {
+                if !usb.insert(port) {
+                    return Err(ProjectServiceError::DuplicateUsbPort(port.clone()));
+                }
+            }
+            EndpointConfig::ArtNet {
+                node, port_address, ..
+            } => {
+                if *port_address > MAX_ARTNET_PORT_ADDRESS {
+                    return Err(ProjectServiceError::InvalidArtNetPortAddress(*port_address));
+                }
+                if !artnet.insert((*node, *port_address)) {
+                    return Err(ProjectServiceError::DuplicateArtNetOutput(
+                        config.to_string(),
+                    ));
+                }
+            }
+            EndpointConfig::Logger | EndpointConfig::Simulator => {}
         }
     }
+    Ok(())
 }
 
 pub fn create_default_project() -> Project {
@@ -407,6 +1224,10 @@ pub fn create_default_project() -> Project {
 }
 
 pub fn get_base_app_dir() -> PathBuf {
+    // Tests must not touch the projects of the installed server
+    if cfg!(test) {
+        return std::env::temp_dir().join(format!("mlc_test_{}", std::process::id()));
+    }
     let project_dirs = directories::ProjectDirs::from("de", "timfritzen", "marvin_light_control")
         .expect("Could not get the project directory");
     project_dirs.data_dir().to_path_buf()
@@ -417,23 +1238,520 @@ impl Project {
         let identifier = self.metadata.file_name.clone();
         let kind = self.metadata.project_type;
 
+        let projects_dir = get_valid_project_dir().await.map_err(|e| e.to_string())?;
+
+        let path = projects_dir.join(format!("{}.{}", &identifier, kind.extension()));
+
+        let loader = Plm::for_kind(&kind).ok_or(format!("No saver found for {kind:?}"))?;
+        // The file name and type are given by the file, they are only left out while storing so
+        // the open project stays valid if saving fails
         self.metadata.last_saved = Local::now();
         self.metadata.file_name = "".to_owned();
         self.metadata.project_type = ProjectType::Invalid;
+        let data = loader.store_project(self);
+        self.metadata.file_name = identifier.clone();
+        self.metadata.project_type = kind;
+        let data = data.map_err(|e| format!("{e:}"))?;
 
-        let projects_dir = get_valid_project_dir().await.map_err(|e| e.to_string())?;
+        // A failed backup shouldn't prevent saving
+        if let Err(e) = backup::rotate(&path, &identifier, self.settings.backups).await {
+            warn!("Failed to back up project {identifier}: {e}");
+        }
+        backup::write_atomic(&path, &data)
+            .await
+            .map_err(|e| format!("{e:?}"))
+    }
+}
 
-        let path = projects_dir.join(format!("{}.{}", &identifier, kind.extension()));
+#[cfg(test)]
+mod test {
+    use super::{
+        Project, backup, create_default_project, get_valid_project_dir, validate_endpoints,
+    };
+    use crate::test_util::{dimmer, fixture, patch, service_obj};
+    use mlc_communication::services::general::{Info, ProjectInfo};
+    use mlc_communication::services::project::{ProjectService, ProjectServiceError};
+    use mlc_communication::services::project_selection::{
+        ProjectSelectionService, ProjectSelectionServiceError,
+    };
+    use mlc_data::SavePercentage;
+    use mlc_data::endpoints::{EndpointConfig, EndpointMapping, EndpointSpeed};
+    use mlc_data::fixture::patched::output::{ChannelProcessing, OutputProcessing};
+    use mlc_data::project::universe::{FixtureAddress, UniverseAddress, UniverseId};
+    use mlc_data::project::{ProjectType, ToFileName};
+    use std::net::Ipv4Addr;
 
-        let loader = Plm::for_kind(&kind).ok_or(format!("No saver found for {kind:?}"))?;
-        let data = loader.store_project(self).map_err(|e| format!("{e:}"))?;
-        tokio::fs::write(path, data)
+    fn mapping(configs: Vec<(u16, EndpointConfig)>) -> EndpointMapping {
+        let mut mapping = EndpointMapping {
+            endpoints: Default::default(),
+        };
+        for (universe, config) in configs {
+            mapping.endpoints.entry(universe).or_default().push(config);
+        }
+        mapping
+    }
+
+    fn sacn(universe: u16) -> EndpointConfig {
+        EndpointConfig::sacn(universe, EndpointSpeed::Fast)
+    }
+
+    fn with_sacn(
+        universe: u16,
+        f: impl FnOnce(&mut u8, &mut Option<String>, &mut Option<u16>, &mut EndpointSpeed),
+    ) -> EndpointConfig {
+        let mut config = sacn(universe);
+        if let EndpointConfig::Sacn {
+            priority,
+            source_name,
+            sync_universe,
+            speed,
+            ..
+        } = &mut config
+        {
+            f(priority, source_name, sync_universe, speed);
+        }
+        config
+    }
+
+    fn validate(configs: Vec<(u16, EndpointConfig)>) -> Result<(), ProjectServiceError> {
+        validate_endpoints(&mapping(configs))
+    }
+
+    #[test]
+    fn endpoint_validation() {
+        assert!(
+            validate(vec![
+                (1, sacn(1)),
+                (2, sacn(2)),
+                (2, EndpointConfig::Simulator)
+            ])
+            .is_ok()
+        );
+
+        assert!(matches!(
+            validate(vec![(1, sacn(0))]),
+            Err(ProjectServiceError::InvalidSacnUniverse(0))
+        ));
+        assert!(matches!(
+            validate(vec![(1, sacn(64000))]),
+            Err(ProjectServiceError::InvalidSacnUniverse(64000))
+        ));
+        assert!(matches!(
+            validate(vec![(1, sacn(7)), (2, sacn(7))]),
+            Err(ProjectServiceError::DuplicateSacnUniverse(7))
+        ));
+        assert!(matches!(
+            validate(vec![(1, with_sacn(1, |p, _, _, _| *p = 201))]),
+            Err(ProjectServiceError::InvalidSacnPriority(201))
+        ));
+        assert!(matches!(
+            validate(vec![(
+                1,
+                with_sacn(1, |_, n, _, _| *n = Some("x".repeat(64)))
+            )]),
+            Err(ProjectServiceError::InvalidSacnSourceName(_))
+        ));
+        assert!(
+            validate(vec![(
+                1,
+                with_sacn(1, |_, n, _, _| *n = Some("x".repeat(63)))
+            )])
+            .is_ok()
+        );
+        assert!(matches!(
+            validate(vec![(1, with_sacn(1, |_, _, s, _| *s = Some(64000)))]),
+            Err(ProjectServiceError::InvalidSacnUniverse(64000))
+        ));
+
+        let synced = |universe, speed| {
+            with_sacn(universe, |_, _, s, sp| {
+                *s = Some(100);
+                *sp = speed;
+            })
+        };
+        assert!(
+            validate(vec![
+                (1, synced(1, EndpointSpeed::Fast)),
+                (2, synced(2, EndpointSpeed::Fast))
+            ])
+            .is_ok()
+        );
+        assert!(matches!(
+            validate(vec![
+                (1, synced(1, EndpointSpeed::Fast)),
+                (2, synced(2, EndpointSpeed::Slow))
+            ]),
+            Err(ProjectServiceError::SacnSyncSpeedMismatch(100))
+        ));
+
+        let usb = |port: &str| EndpointConfig::Usb {
+            port: port.to_string(),
+            speed: EndpointSpeed::Medium,
+        };
+        assert!(validate(vec![(1, usb("/dev/ttyUSB0")), (2, usb("/dev/ttyUSB1"))]).is_ok());
+        assert!(matches!(
+            validate(vec![(1, usb("/dev/ttyUSB0")), (2, usb("/dev/ttyUSB0"))]),
+            Err(ProjectServiceError::DuplicateUsbPort(_))
+        ));
+
+        for config in [
+            with_sacn(1, |_, _, _, s| *s = EndpointSpeed::Custom(0)),
+            EndpointConfig::Usb {
+                port: "/dev/ttyUSB0".to_string(),
+                speed: EndpointSpeed::Custom(0),
+            },
+        ] {
+            assert!(matches!(
+                validate(vec![(1, config)]),
+                Err(ProjectServiceError::InvalidEndpointSpeed)
+            ));
+        }
+        assert!(!EndpointSpeed::Custom(0).duration().is_zero());
+
+        let artnet = |port_address| {
+            EndpointConfig::artnet(
+                Ipv4Addr::new(10, 0, 0, 7),
+                port_address,
+                EndpointSpeed::Fast,
+            )
+        };
+        assert!(validate(vec![(1, artnet(0)), (2, artnet(0x7FFF))]).is_ok());
+        assert!(matches!(
+            validate(vec![(1, artnet(0x8000))]),
+            Err(ProjectServiceError::InvalidArtNetPortAddress(0x8000))
+        ));
+        assert!(matches!(
+            validate(vec![(1, artnet(3)), (2, artnet(3))]),
+            Err(ProjectServiceError::DuplicateArtNetOutput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn universe_ids() {
+        let mut project = create_default_project();
+        let fixture = fixture(vec![("Dimmer", vec![dimmer()])]);
+        patch(&mut project, 1, 1, fixture);
+        let (service_obj, _) = service_obj(project);
+
+        let stage = service_obj.universe_create("Stage".into()).await.unwrap();
+        let floor = service_obj.universe_create("Floor".into()).await.unwrap();
+        assert_eq!((stage, floor), (2, 3));
+        service_obj.universe_delete(floor).await.unwrap();
+        // Deleted ids are not reused
+        let balcony = service_obj.universe_create("Balcony".into()).await.unwrap();
+        assert_eq!(balcony, 4);
+
+        assert!(matches!(
+            service_obj.universe_delete(1).await,
+            Err(ProjectServiceError::UniversePatched(1))
+        ));
+        service_obj
+            .endpoint_add(stage, EndpointConfig::Simulator)
             .await
-            .map_err(|e| format!("{e:?}"))?;
+            .unwrap();
+        assert!(matches!(
+            service_obj.universe_delete(stage).await,
+            Err(ProjectServiceError::UniverseMapped(2))
+        ));
+        assert!(matches!(
+            service_obj.universe_delete(floor).await,
+            Err(ProjectServiceError::UnknownUniverse(3))
+        ));
+        assert_eq!(service_obj.universe_list().await.unwrap().len(), 3);
 
-        self.metadata.file_name = identifier;
-        self.metadata.project_type = kind;
+        // Projects saved without the counter continue after the highest id, until none is left
+        let mut p = service_obj.project.write().await;
+        p.next_universe_id = 0;
+        p.universes[0].id = UniverseId::MAX - 1;
+        drop(p);
+        let last = service_obj.universe_create("Last".into()).await.unwrap();
+        assert_eq!(last, UniverseId::MAX);
+        assert!(matches!(
+            service_obj.universe_create("Too many".into()).await,
+            Err(ProjectServiceError::NoUniverseIdLeft)
+        ));
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn park_channels() {
+        let (service_obj, _) = service_obj(create_default_project());
+        let stage = service_obj.universe_create("Stage".into()).await.unwrap();
+        let floor = service_obj.universe_create("Floor".into()).await.unwrap();
+        service_obj.universe_delete(stage).await.unwrap();
+
+        let channel =
+            |universe, address| FixtureAddress::new(universe, UniverseAddress::create(address));
+        // Universes are checked by id, not by their count
+        for universe in [0, stage, floor + 1] {
+            assert!(matches!(
+                service_obj.park(vec![(channel(1, 1), 10), (channel(universe, 1), 10)]).await,
+                Err(ProjectServiceError::UnknownUniverse(u)) if u == universe
+            ));
+        }
+        assert!(service_obj.parked_list().await.unwrap().is_empty());
+
+        service_obj
+            .park(vec![(channel(1, 1), 10), (channel(floor, 2), 20)])
+            .await
+            .unwrap();
+        service_obj.park(vec![(channel(1, 1), 30)]).await.unwrap();
+        assert_eq!(
+            service_obj.parked_list().await.unwrap(),
+            [(channel(1, 1), 30), (channel(floor, 2), 20)]
+        );
+
+        service_obj.unpark(vec![channel(1, 1)]).await.unwrap();
+        assert_eq!(
+            service_obj.parked_list().await.unwrap(),
+            [(channel(floor, 2), 20)]
+        );
+        // Deleting a universe unparks its channels
+        service_obj.universe_delete(floor).await.unwrap();
+        assert!(service_obj.parked_list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fixture_output_limits() {
+        let mut project = create_default_project();
+        let dimmer = fixture(vec![("Dimmer", vec![dimmer()])]);
+        let id = patch(&mut project, 1, 1, dimmer);
+        let (service_obj, _) = service_obj(project);
+        let info = service_obj.info.subscribe();
+
+        let mut channel = ChannelProcessing::new("Dimmer".to_string());
+        channel.min = SavePercentage::create(0.8);
+        channel.max = SavePercentage::create(0.2);
+        let output = |channel: &ChannelProcessing| OutputProcessing {
+            channels: vec![channel.clone()],
+            swap_pan_tilt: false,
+        };
+        assert!(matches!(
+            service_obj.update_fixture_output(id, output(&channel)).await,
+            Err(ProjectServiceError::InvalidOutputLimits(c)) if c == "Dimmer"
+        ));
+        assert_eq!(
+            service_obj.fixture_output(id).await.unwrap(),
+            OutputProcessing::default()
+        );
+        assert_eq!(*info.borrow().unwrap(), Info::Idle);
+
+        // Equal limits hold the channel at a fixed level
+        channel.max = SavePercentage::create(0.8);
+        service_obj
+            .update_fixture_output(id, output(&channel))
+            .await
+            .unwrap();
+        assert_eq!(
+            service_obj.fixture_output(id).await.unwrap(),
+            output(&channel)
+        );
+        assert_eq!(
+            *info.borrow().unwrap(),
+            ProjectInfo::FixtureOutputChanged { fixture: id }.into()
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_save_keeps_metadata() {
+        let mut p = create_default_project();
+        // The folder of the file does not exist
+        p.metadata.file_name = "missing/show".to_string();
+        p.metadata.project_type = ProjectType::Json;
+        assert!(p.save().await.is_err());
+        assert_eq!(p.metadata.file_name, "missing/show");
+        assert_eq!(p.metadata.project_type, ProjectType::Json);
+
+        // Unknown types fail before anything is changed
+        p.metadata.project_type = ProjectType::Invalid;
+        assert!(p.save().await.is_err());
+        assert_eq!(p.metadata.file_name, "missing/show");
+    }
+
+    /// Saves a new project named `name` in the projects folder
+    async fn saved(name: &str) -> Project {
+        let mut p = create_default_project();
+        p.metadata.name = name.to_string();
+        p.metadata.file_name = name.to_project_file_name();
+        p.metadata.project_type = ProjectType::Json;
+        p.save().await.unwrap();
+        p
+    }
+
+    async fn exists(ident: &str) -> bool {
+        let dir = get_valid_project_dir().await.unwrap();
+        dir.join(format!("{ident}.json")).is_file()
+    }
+
+    async fn make_backup(ident: &str) {
+        let dir = backup::backup_dir(ident).await.unwrap();
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("backup.json"), b"{}")
+            .await
+            .unwrap();
+    }
+
+    const INVALID_IDENTS: [&str; 4] = ["", "../rename_taken", "a/b", ".hidden"];
+
+    #[tokio::test]
+    async fn rename_project() {
+        let open = saved("Rename Open").await;
+        saved("Rename Taken").await;
+        make_backup("rename_open").await;
+        let (service_obj, _) = service_obj(open);
+        let mut info = service_obj.info.subscribe();
+
+        // The name of another project gets the next free file name
+        let renamed = ProjectSelectionService::rename(
+            &*service_obj,
+            "rename_open".to_string(),
+            "Rename Taken".to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(renamed, "rename_taken_0");
+        assert!(!exists("rename_open").await);
+        assert!(exists("rename_taken").await);
+        assert!(exists("rename_taken_0").await);
+
+        // The open project and its backups follow the file
+        {
+            let p = service_obj.project.read().await;
+            assert_eq!(p.metadata.name, "Rename Taken");
+            assert_eq!(p.metadata.file_name, "rename_taken_0");
+        }
+        let backups = backup::backup_dir("rename_taken_0").await.unwrap();
+        assert!(backups.join("backup.json").is_file());
+        assert!(!backup::backup_dir("rename_open").await.unwrap().exists());
+        info.changed().await.unwrap();
+        assert_eq!(
+            *info.borrow().unwrap(),
+            Info::from(ProjectInfo::SettingsChanged)
+        );
+
+        let result = ProjectSelectionService::rename(
+            &*service_obj,
+            "rename_taken_0".to_string(),
+            "!?".to_string(),
+        )
+        .await;
+        assert!(
+            matches!(result, Err(ProjectSelectionServiceError::InvalidProjectName(n)) if n == "!?")
+        );
+        for ident in INVALID_IDENTS {
+            let result = ProjectSelectionService::rename(
+                &*service_obj,
+                ident.to_string(),
+                "Rename Valid".to_string(),
+            )
+            .await;
+            assert!(
+                matches!(result, Err(ProjectSelectionServiceError::UnknownProject(n)) if n == ident)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_rename_keeps_project() {
+        saved("Rename Source").await;
+        // A leftover temporary file of the target can't be replaced, so saving fails
+        let dir = get_valid_project_dir().await.unwrap();
+        tokio::fs::create_dir_all(dir.join("rename_blocked.json.tmp"))
+            .await
+            .unwrap();
+        let (service_obj, _) = service_obj(create_default_project());
+
+        let result = ProjectSelectionService::rename(
+            &*service_obj,
+            "rename_source".to_string(),
+            "Rename Blocked".to_string(),
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(ProjectSelectionServiceError::ProjectRenameError(_))
+        ));
+        assert!(exists("rename_source").await);
+        assert!(!exists("rename_blocked").await);
+    }
+
+    #[tokio::test]
+    async fn duplicate_project() {
+        saved("Duplicate Source").await;
+        let (service_obj, _) = service_obj(create_default_project());
+
+        for expected in ["duplicate_copy", "duplicate_copy_0"] {
+            let ident = ProjectSelectionService::duplicate(
+                &*service_obj,
+                "duplicate_source".to_string(),
+                "Duplicate Copy".to_string(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(ident, expected);
+            assert!(exists(expected).await);
+        }
+        assert!(exists("duplicate_source").await);
+
+        let result = ProjectSelectionService::duplicate(
+            &*service_obj,
+            "duplicate_source".to_string(),
+            "".to_string(),
+        )
+        .await;
+        assert!(
+            matches!(result, Err(ProjectSelectionServiceError::InvalidProjectName(n)) if n.is_empty())
+        );
+        for ident in INVALID_IDENTS {
+            let result = ProjectSelectionService::duplicate(
+                &*service_obj,
+                ident.to_string(),
+                "Duplicate Valid".to_string(),
+            )
+            .await;
+            assert!(
+                matches!(result, Err(ProjectSelectionServiceError::UnknownProject(n)) if n == ident)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn delete_project() {
+        let open = saved("Delete Open").await;
+        saved("Delete Me").await;
+        make_backup("delete_me").await;
+        let (service_obj, _) = service_obj(open);
+
+        let result =
+            ProjectSelectionService::delete(&*service_obj, "delete_open".to_string()).await;
+        assert!(
+            matches!(result, Err(ProjectSelectionServiceError::ProjectInUse(n)) if n == "delete_open")
+        );
+        assert!(exists("delete_open").await);
+
+        ProjectSelectionService::delete(&*service_obj, "delete_me".to_string())
+            .await
+            .unwrap();
+        assert!(!exists("delete_me").await);
+        assert!(!backup::backup_dir("delete_me").await.unwrap().exists());
+        let trash = get_valid_project_dir()
+            .await
+            .unwrap()
+            .join(super::TRASH_DIR);
+        let mut trashed = std::fs::read_dir(trash)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("delete_me_"))
+            .collect::<Vec<_>>();
+        trashed.sort();
+        assert_eq!(trashed.len(), 2);
+        assert!(trashed[0].ends_with(".json"));
+        assert!(trashed[1].ends_with("_backups"));
+
+        for ident in INVALID_IDENTS {
+            let result = ProjectSelectionService::delete(&*service_obj, ident.to_string()).await;
+            assert!(
+                matches!(result, Err(ProjectSelectionServiceError::UnknownProject(n)) if n == ident)
+            );
+        }
     }
 }
@@ -0,0 +1,367 @@
+//! Self-contained project archives to move projects between machines.
+
+use super::project_loader::Plm;
+use super::{Project, find_project_file, get_base_app_dir, load_project_file};
+use chrono::{DateTime, Local};
+use mlc_communication::services::project_selection::{
+    ImportOptions, ProjectIdent, ProjectSelectionServiceError,
+};
+use mlc_data::DynamicResult;
+use mlc_data::project::audio::AudioInput;
+use mlc_data::project::{ProjectType, ToFileName};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Increased when the layout of the archive changes
+const ARCHIVE_FORMAT: u32 = 1;
+const MLC_VERSION: &str = env!("CARGO_PKG_VERSION");
+const MANIFEST_FILE: &str = "manifest.json";
+const PROJECT_FILE: &str = "project";
+const ASSET_PREFIX: &str = "assets/";
+/// Imported assets are stored in a folder per project in this folder of the app directory
+const ASSET_DIR: &str = "assets";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Manifest {
+    pub format: u32,
+    pub mlc_version: String,
+    pub name: String,
+    pub project_type: ProjectType,
+    pub exported_at: DateTime<Local>,
+    /// Identifiers of the blueprints contained in the project
+    pub blueprints: Vec<String>,
+    /// Archive paths of the assets
+    pub assets: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Package {
+    manifest: Manifest,
+    project: Vec<u8>,
+    assets: Vec<(String, Vec<u8>)>,
+}
+
+/// Exports the saved project, or `open` instead if it is the project with unsaved edits
+pub async fn export_project(
+    ident: &str,
+    open: Option<Project>,
+) -> Result<Vec<u8>, ProjectSelectionServiceError> {
+    let to_err = |e: String| ProjectSelectionServiceError::ProjectExportError(e);
+    let (loader, mut p) = match open {
+        Some(p) => {
+            let kind = p.metadata.project_type;
+            let loader =
+                Plm::for_kind(&kind).ok_or_else(|| to_err(format!("No saver for {kind:?}")))?;
+            (loader, p)
+        }
+        None => {
+            let (_, loader, p) = load_project_file(ident).await?;
+            (loader, p)
+        }
+    };
+
+    let mut assets = vec![];
+    for (i, input) in audio_inputs(&mut p).into_iter().enumerate() {
+        // Raw sample streams like FIFOs are specific to the machine
+        if input.raw_sample_rate.is_some() {
+            continue;
+        }
+        let data = tokio::fs::read(&input.path)
+            .await
+            .map_err(|e| to_err(format!("{}: {e}", input.path.display())))?;
+        let file_name = input
+            .path
+            .file_name()
+            .map_or("audio".into(), |n| n.to_string_lossy());
+        let archived = format!("{ASSET_PREFIX}{i}_{file_name}");
+        input.path = PathBuf::from(&archived);
+        assets.push((archived, data));
+    }
+
+    let manifest = Manifest {
+        format: ARCHIVE_FORMAT,
+        mlc_version: MLC_VERSION.to_string(),
+        name: p.metadata.name.clone(),
+        project_type: loader.kind(),
+        exported_at: Local::now(),
+        blueprints: p
+            .blueprints
+            .iter()
+            .map(|b| b.meta.identifier.clone())
+            .collect(),
+        assets: assets.iter().map(|(name, _)| name.clone()).collect(),
+    };
+    // Like saved projects, the file name and type are given by the file
+    p.metadata.file_name = String::new();
+    p.metadata.project_type = ProjectType::Invalid;
+    let project = loader
+        .store_project(&p)
+        .map_err(|e| to_err(e.to_string()))?;
+
+    let package = Package {
+        manifest,
+        project,
+        assets,
+    };
+    let data =
+        tokio::task::spawn_blocking(move || write_package(&package).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| to_err(e.to_string()))?
+            .map_err(to_err)?;
+    info!("Exported project {ident}");
+    Ok(data)
+}
+
+pub async fn import_project(
+    data: Vec<u8>,
+    options: ImportOptions,
+) -> Result<ProjectIdent, ProjectSelectionServiceError> {
+    let to_err = |e: String| ProjectSelectionServiceError::ProjectImportError(e);
+    let package =
+        tokio::task::spawn_blocking(move || read_package(&data).map_err(|e| e.to_string()))
+            .await
+            .map_err(|e| to_err(e.to_string()))?
+            .map_err(to_err)?;
+    check_manifest(&package.manifest, &options)?;
+
+    let kind = package.manifest.project_type;
+    let loader = Plm::for_kind(&kind).ok_or_else(|| to_err(format!("No loader for {kind:?}")))?;
+    let mut p = loader
+        .load_project(package.project)
+        .map_err(|e| to_err(e.to_string()))?;
+
+    let name = options.name.unwrap_or(package.manifest.name);
+    let ident = name.to_project_file_name();
+    if ident.is_empty() {
+        return Err(ProjectSelectionServiceError::InvalidProjectName(name));
+    }
+    if find_project_file(&ident)
+        .await
+        .map_err(|e| to_err(e.to_string()))?
+        .is_some()
+    {
+        return Err(ProjectSelectionServiceError::ProjectExists(ident));
+    }
+
+    let asset_dir = get_base_app_dir().join(ASSET_DIR).join(&ident);
+    for input in audio_inputs(&mut p) {
+        let archived = input.path.to_string_lossy().to_string();
+        let Some((_, data)) = package.assets.iter().find(|(name, _)| *name == archived) else {
+            continue;
+        };
+        let path = asset_path(&asset_dir, &archived)
+            .ok_or_else(|| to_err(format!("Invalid asset path {archived:?}")))?;
+        tokio::fs::create_dir_all(&asset_dir)
+            .await
+            .map_err(|e| to_err(e.to_string()))?;
+        tokio::fs::write(&path, data)
+            .await
+            .map_err(|e| to_err(e.to_string()))?;
+        input.path = path;
+    }
+
+    p.metadata.name = name;
+    p.metadata.file_name = ident.clone();
+    p.metadata.project_type = kind;
+    p.save().await.map_err(to_err)?;
+    info!("Imported project {ident}");
+    Ok(ident)
+}
+
+fn check_manifest(
+    manifest: &Manifest,
+    options: &ImportOptions,
+) -> Result<(), ProjectSelectionServiceError> {
+    if manifest.format > ARCHIVE_FORMAT {
+        return Err(ProjectSelectionServiceError::ProjectImportError(format!(
+            "The archive format {} is not supported",
+            manifest.format
+        )));
+    }
+    if manifest.mlc_version != MLC_VERSION && !options.ignore_version {
+        return Err(ProjectSelectionServiceError::VersionMismatch {
+            archive: manifest.mlc_version.clone(),
+            current: MLC_VERSION.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// The audio files the project reads
+fn audio_inputs(p: &mut Project) -> Vec<&mut AudioInput> {
+    p.audio
+        .input
+        .iter_mut()
+        .chain(&mut p.timecode.ltc)
+        .collect()
+}
+
+/// Where an archived asset is stored, `None` if its name could leave the asset folder
+fn asset_path(asset_dir: &Path, name: &str) -> Option<PathBuf> {
+    let relative = Path::new(name.strip_prefix(ASSET_PREFIX)?);
+    let mut components = relative.components().peekable();
+    components.peek()?;
+    if !components.all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let path = asset_dir.join(relative);
+    path.starts_with(asset_dir).then_some(path)
+}
+
+fn project_file(kind: ProjectType) -> String {
+    format!("{PROJECT_FILE}.{}", kind.extension())
+}
+
+fn write_package(package: &Package) -> DynamicResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(vec![]));
+    let options = SimpleFileOptions::default();
+
+    zip.start_file(MANIFEST_FILE, options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&package.manifest)?)?;
+    zip.start_file(project_file(package.manifest.project_type), options)?;
+    zip.write_all(&package.project)?;
+    for (name, data) in &package.assets {
+        zip.start_file(name, options)?;
+        zip.write_all(data)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+fn read_package(data: &[u8]) -> DynamicResult<Package> {
+    let mut zip = ZipArchive::new(Cursor::new(data))?;
+    let mut read = |name: &str| -> DynamicResult<Vec<u8>> {
+        let mut data = vec![];
+        zip.by_name(name)?.read_to_end(&mut data)?;
+        Ok(data)
+    };
+
+    let manifest: Manifest = serde_json::from_slice(&read(MANIFEST_FILE)?)?;
+    let project = read(&project_file(manifest.project_type))?;
+    let assets = manifest
+        .assets
+        .iter()
+        .map(|name| {
+            if asset_path(Path::new(ASSET_DIR), name).is_none() {
+                return Err(format!("Invalid asset path {name:?}").into());
+            }
+            Ok((name.clone(), read(name)?))
+        })
+        .collect::<DynamicResult<_>>()?;
+    Ok(Package {
+        manifest,
+        project,
+        assets,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        ARCHIVE_FORMAT, MLC_VERSION, Manifest, Package, asset_path, check_manifest, export_project,
+        read_package, write_package,
+    };
+    use crate::project::create_default_project;
+    use chrono::Local;
+    use mlc_communication::services::project_selection::{
+        ImportOptions, ProjectSelectionServiceError,
+    };
+    use mlc_data::project::ProjectType;
+    use std::path::Path;
+
+    #[test]
+    fn archive_round_trip() {
+        let mut manifest = Manifest {
+            format: ARCHIVE_FORMAT,
+            mlc_version: MLC_VERSION.to_string(),
+            name: "Club".to_string(),
+            project_type: ProjectType::Binary,
+            exported_at: Local::now(),
+            blueprints: vec!["generic/dimmer".to_string()],
+            assets: vec!["assets/0_show.wav".to_string()],
+        };
+        let package = Package {
+            manifest: manifest.clone(),
+            project: vec![1, 2, 3],
+            assets: vec![("assets/0_show.wav".to_string(), vec![4; 100])],
+        };
+        let data = write_package(&package).unwrap();
+        assert_eq!(read_package(&data).unwrap(), package);
+        assert!(read_package(b"not a zip").is_err());
+
+        let options = ImportOptions::default();
+        assert!(check_manifest(&manifest, &options).is_ok());
+        manifest.mlc_version = "0.0.0-old".to_string();
+        assert!(matches!(
+            check_manifest(&manifest, &options),
+            Err(ProjectSelectionServiceError::VersionMismatch { .. })
+        ));
+        let ignore = ImportOptions {
+            ignore_version: true,
+            ..options
+        };
+        assert!(check_manifest(&manifest, &ignore).is_ok());
+        manifest.format += 1;
+        assert!(check_manifest(&manifest, &ignore).is_err());
+    }
+
+    #[test]
+    fn reject_escaping_assets() {
+        let dir = Path::new("/data/assets/show");
+        assert_eq!(
+            asset_path(dir, "assets/0_show.wav"),
+            Some(dir.join("0_show.wav"))
+        );
+        for name in [
+            "assets//etc/x",
+            "assets/../x",
+            "assets/a/../../x",
+            "assets/./x",
+            "assets/",
+            "other/x",
+        ] {
+            assert_eq!(asset_path(dir, name), None, "{name}");
+        }
+
+        // An archive naming an absolute asset path is not read
+        let package = Package {
+            manifest: Manifest {
+                format: ARCHIVE_FORMAT,
+                mlc_version: MLC_VERSION.to_string(),
+                name: "Evil".to_string(),
+                project_type: ProjectType::Json,
+                exported_at: Local::now(),
+                blueprints: vec![],
+                assets: vec!["assets//tmp/mlc_evil".to_string()],
+            },
+            project: vec![],
+            assets: vec![("assets//tmp/mlc_evil".to_string(), vec![0; 4])],
+        };
+        let data = write_package(&package).unwrap();
+        let error = read_package(&data).unwrap_err().to_string();
+        assert!(error.contains("Invalid asset path"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn export_open_project() {
+        let mut p = create_default_project();
+        p.metadata.name = "Export Saved".to_string();
+        p.metadata.file_name = "export_saved".to_string();
+        p.metadata.project_type = ProjectType::Json;
+        p.save().await.unwrap();
+
+        let saved = export_project("export_saved", None).await.unwrap();
+        assert_eq!(read_package(&saved).unwrap().manifest.name, "Export Saved");
+
+        // Unsaved edits of the open project are exported as well
+        p.metadata.name = "Export Edited".to_string();
+        let edited = export_project("export_saved", Some(p)).await.unwrap();
+        assert_eq!(
+            read_package(&edited).unwrap().manifest.name,
+            "Export Edited"
+        );
+    }
+}
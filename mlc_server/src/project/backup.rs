@@ -0,0 +1,267 @@
+//! Atomic project saves and rotating backups of the previous saves.
+
+use super::project_loader::{BoxedLoader, Plm};
+use super::{Project, find_project_file, get_valid_project_dir, is_valid_ident};
+use chrono::{DateTime, Local, NaiveDateTime};
+use mlc_communication::services::project_selection::ProjectSelectionServiceError;
+use mlc_data::DynamicResult;
+use mlc_data::project::ProjectBackup;
+use std::cmp::Reverse;
+use std::io;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tracing::info;
+
+/// Backups are stored in a folder per project in this folder of the projects folder
+const BACKUP_DIR: &str = "backups";
+/// Backups are named by the time they were made
+const TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S_%3f";
+
+pub async fn backup_dir(ident: &str) -> DynamicResult<PathBuf> {
+    if !is_valid_ident(ident) {
+        return Err(format!("Invalid project identifier {ident:?}").into());
+    }
+    Ok(get_valid_project_dir().await?.join(BACKUP_DIR).join(ident))
+}
+
+/// Writes a temporary file and renames it over the target, so the target is never partially
+/// written
+pub async fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = tokio::fs::File::create(&tmp).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    drop(file);
+    tokio::fs::rename(&tmp, path).await
+}
+
+/// Copies the current save into the backups and removes the oldest backups beyond `keep`
+pub async fn rotate(path: &Path, ident: &str, keep: u32) -> DynamicResult<()> {
+    let dir = backup_dir(ident).await?;
+    rotate_in(&dir, path, keep).await
+}
+
+async fn rotate_in(dir: &Path, path: &Path, keep: u32) -> DynamicResult<()> {
+    if keep > 0 && path.is_file() {
+        tokio::fs::create_dir_all(dir).await?;
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        let name = format!("{}.{extension}", Local::now().format(TIMESTAMP_FORMAT));
+        tokio::fs::copy(path, dir.join(name)).await?;
+    }
+    let backups = list_in(dir).await?;
+    for old in backups.into_iter().skip(keep as usize) {
+        tokio::fs::remove_file(dir.join(old.id)).await?;
+    }
+    Ok(())
+}
+
+/// Newest first
+pub async fn list_backups(ident: &str) -> DynamicResult<Vec<ProjectBackup>> {
+    let dir = backup_dir(ident).await?;
+    list_in(&dir).await
+}
+
+async fn list_in(dir: &Path) -> DynamicResult<Vec<ProjectBackup>> {
+    let mut backups = vec![];
+    if !dir.is_dir() {
+        return Ok(backups);
+    }
+
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let id = entry.file_name().to_string_lossy().to_string();
+        let Some(created_at) = parse_timestamp(&id) else {
+            continue;
+        };
+        if Plm::for_file(&entry.path()).is_some() {
+            let size = entry.metadata().await?.len();
+            backups.push(ProjectBackup {
+                id,
+                created_at,
+                size,
+            });
+        }
+    }
+    backups.sort_by_key(|b| Reverse(b.created_at));
+    Ok(backups)
+}
+
+fn parse_timestamp(id: &str) -> Option<DateTime<Local>> {
+    let (stem, _) = id.split_once('.')?;
+    NaiveDateTime::parse_from_str(stem, TIMESTAMP_FORMAT)
+        .ok()?
+        .and_local_timezone(Local)
+        .earliest()
+}
+
+/// The raw backup file and its contents
+async fn load_backup(
+    dir: &Path,
+    id: &str,
+) -> Result<(Vec<u8>, &'static BoxedLoader, Project), ProjectSelectionServiceError> {
+    let to_err = |e: String| ProjectSelectionServiceError::ProjectBackupError(e);
+    // Only listed backups are accepted, ids are file names
+    let known = list_in(dir)
+        .await
+        .map_err(|e| to_err(e.to_string()))?
+        .iter()
+        .any(|b| b.id == id);
+    if !known {
+        return Err(ProjectSelectionServiceError::UnknownBackup(id.to_string()));
+    }
+
+    let path = dir.join(id);
+    let loader = Plm::for_file(&path).ok_or_else(|| to_err(format!("No loader for {id}")))?;
+    let data = tokio::fs::read(&path)
+        .await
+        .map_err(|e| to_err(e.to_string()))?;
+    let p = loader
+        .load_project(data.clone())
+        .map_err(|e| to_err(format!("{id}: {e}")))?;
+    Ok((data, loader, p))
+}
+
+/// The newest backup that can still be loaded
+pub async fn find_fallback(ident: &str) -> Option<String> {
+    let dir = backup_dir(ident).await.ok()?;
+    fallback_in(&dir).await
+}
+
+async fn fallback_in(dir: &Path) -> Option<String> {
+    for backup in list_in(dir).await.ok()? {
+        if load_backup(dir, &backup.id).await.is_ok() {
+            return Some(backup.id);
+        }
+    }
+    None
+}
+
+/// Replaces the project by the backup, a save in the other format is backed up and removed so
+/// the restored one is opened
+pub async fn restore_backup(ident: &str, id: &str) -> Result<(), ProjectSelectionServiceError> {
+    let to_err = |e: String| ProjectSelectionServiceError::ProjectBackupError(e);
+    let dir = backup_dir(ident)
+        .await
+        .map_err(|_| ProjectSelectionServiceError::UnknownProject(ident.to_string()))?;
+    let (data, loader, p) = load_backup(&dir, id).await?;
+    let path = get_valid_project_dir()
+        .await
+        .map_err(|e| to_err(e.to_string()))?
+        .join(format!("{ident}.{}", loader.kind().extension()));
+    let current = find_project_file(ident)
+        .await
+        .map_err(|e| to_err(e.to_string()))?
+        .map(|(current, _)| current);
+
+    rotate_in(
+        &dir,
+        current.as_deref().unwrap_or(&path),
+        p.settings.backups,
+    )
+    .await
+    .map_err(|e| to_err(e.to_string()))?;
+    write_atomic(&path, &data)
+        .await
+        .map_err(|e| to_err(e.to_string()))?;
+    if let Some(other) = current.filter(|c| *c != path) {
+        tokio::fs::remove_file(other)
+            .await
+            .map_err(|e| to_err(e.to_string()))?;
+    }
+    info!("Restored project {ident} from backup {id}");
+    Ok(())
+}
+
+/// Moves the backup folder of the project, if there is one
+pub async fn move_backups(ident: &str, to: &Path) -> DynamicResult<()> {
+    let dir = backup_dir(ident).await?;
+    if dir.is_dir() {
+        if let Some(parent) = to.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::rename(dir, to).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        TIMESTAMP_FORMAT, backup_dir, fallback_in, list_in, parse_timestamp, rotate_in,
+        write_atomic,
+    };
+    use crate::project::create_default_project;
+    use crate::project::project_loader::{JsonLoader, ProjectLoader};
+    use chrono::{Local, TimeDelta};
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mlc_backup_test_{name}_{}", std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn atomic_write_and_backup_names() {
+        let dir = temp_dir("atomic");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("show.json");
+        write_atomic(&path, b"first").await.unwrap();
+        write_atomic(&path, b"second").await.unwrap();
+        assert_eq!(tokio::fs::read(&path).await.unwrap(), b"second");
+        assert!(!dir.join("show.json.tmp").exists());
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+
+        let now = Local::now();
+        let id = format!("{}.bson", now.format(TIMESTAMP_FORMAT));
+        let parsed = parse_timestamp(&id).unwrap();
+        assert_eq!(parsed.timestamp_millis(), now.timestamp_millis());
+        assert!(parse_timestamp("show.json").is_none());
+
+        for ident in ["../show", "..", ".hidden", "a/b", "a\\b", ""] {
+            assert!(backup_dir(ident).await.is_err(), "{ident}");
+        }
+    }
+
+    #[tokio::test]
+    async fn rotate_and_fall_back() {
+        let dir = temp_dir("rotate");
+        let backups = dir.join("backups");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("show.json");
+
+        for i in 0..4 {
+            write_atomic(&path, format!("save {i}").as_bytes())
+                .await
+                .unwrap();
+            rotate_in(&backups, &path, 2).await.unwrap();
+            // Backups are named by the millisecond
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let kept = list_in(&backups).await.unwrap();
+        assert_eq!(kept.len(), 2);
+        let newest = tokio::fs::read(backups.join(&kept[0].id)).await.unwrap();
+        assert_eq!(newest, b"save 3");
+        // None of them is a loadable project
+        assert_eq!(fallback_in(&backups).await, None);
+
+        rotate_in(&backups, &path, 0).await.unwrap();
+        assert!(list_in(&backups).await.unwrap().is_empty());
+
+        let name = |age: i64| {
+            let created = Local::now() - TimeDelta::seconds(age);
+            format!("{}.json", created.format(TIMESTAMP_FORMAT))
+        };
+        let valid = JsonLoader.store_project(&create_default_project()).unwrap();
+        let (older, newer) = (name(20), name(10));
+        tokio::fs::write(backups.join(&older), valid).await.unwrap();
+        tokio::fs::write(backups.join(&newer), b"{ corrupt")
+            .await
+            .unwrap();
+        assert_eq!(fallback_in(&backups).await, Some(older));
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,450 @@
+//! Project edits recorded as reversible commands, undone and redone for all clients.
+
+use super::Project;
+use crate::ServiceImpl;
+use crate::misc::AdaptScopes;
+use mlc_communication::services::general::{Masters, ProjectInfo};
+use mlc_communication::services::project::{EditHistory, ProjectServiceError};
+use mlc_data::SavePercentage;
+use mlc_data::endpoints::EndpointMapping;
+use mlc_data::fixture::blueprint::FixtureBlueprint;
+use mlc_data::project::ProjectSettings;
+use mlc_data::project::audio::AudioSettings;
+use mlc_data::project::cue::CueList;
+use mlc_data::project::group::FixtureGroup;
+use mlc_data::project::group::GroupId;
+use mlc_data::project::midi::MidiSettings;
+use mlc_data::project::osc::OscSettings;
+use mlc_data::project::schedule::ScheduleSettings;
+use mlc_data::project::script::Script;
+use mlc_data::project::timecode::TimecodeSettings;
+use mlc_data::project::universe::{FixtureUniverse, ParkedChannel, UniverseId};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use tokio::sync::RwLockWriteGuard;
+
+/// Older edits are dropped
+const HISTORY_LIMIT: usize = 100;
+/// Older edits are dropped once the snapshots of all edits are larger, the newest edit is kept
+const HISTORY_SIZE: usize = 64 * 1024 * 1024;
+
+macro_rules! parts {
+    (
+        project { $($part:ident: $field:ident: $ty:ty),* $(,)? }
+        masters { $($m_part:ident: $m_field:ident: $m_ty:ty),* $(,)? }
+    ) => {
+        /// The part of the project or of its submaster levels an edit changes
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Part {
+            $($part,)*
+            $($m_part,)*
+        }
+
+        /// The state of a part of the project
+        #[derive(Debug, Clone)]
+        enum Snapshot {
+            $($part($ty),)*
+            $($m_part($m_ty),)*
+        }
+
+        impl Part {
+            fn snapshot(self, p: &Project, m: &Masters) -> Snapshot {
+                match self {
+                    $(Part::$part => Snapshot::$part(p.$field.clone()),)*
+                    $(Part::$m_part => Snapshot::$m_part(m.$m_field.clone()),)*
+                }
+            }
+        }
+
+        impl Snapshot {
+            fn part(&self) -> Part {
+                match self {
+                    $(Snapshot::$part(_) => Part::$part,)*
+                    $(Snapshot::$m_part(_) => Part::$m_part,)*
+                }
+            }
+
+            fn restore(self, p: &mut Project, m: &mut Masters) {
+                match self {
+                    $(Snapshot::$part(value) => p.$field = value,)*
+                    $(Snapshot::$m_part(value) => m.$m_field = value,)*
+                }
+            }
+
+            /// Approximated by the serialized size
+            fn size(&self) -> usize {
+                let mut counter = ByteCounter(0);
+                let result = match self {
+                    $(Snapshot::$part(value) => serde_json::to_writer(&mut counter, value),)*
+                    $(Snapshot::$m_part(value) => serde_json::to_writer(&mut counter, value),)*
+                };
+                result.map_or(0, |_| counter.0)
+            }
+        }
+    };
+}
+
+/// Counts the bytes written to it
+struct ByteCounter(usize);
+
+impl io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+parts! {
+    project {
+        Blueprints: blueprints: Vec<FixtureBlueprint>,
+        Universes: universes: Vec<FixtureUniverse>,
+        Endpoints: endpoint_mapping: EndpointMapping,
+        Settings: settings: ProjectSettings,
+        CueLists: cue_lists: Vec<CueList>,
+        Groups: groups: Vec<FixtureGroup>,
+        Parked: parked: Vec<ParkedChannel>,
+        Osc: osc: OscSettings,
+        Midi: midi: MidiSettings,
+        Timecode: timecode: TimecodeSettings,
+        Audio: audio: AudioSettings,
+        Schedule: schedule: ScheduleSettings,
+        Scripts: scripts: Vec<Script>,
+    }
+    masters {
+        UniverseMasters: universes: Vec<(UniverseId, SavePercentage)>,
+        GroupMasters: groups: Vec<(GroupId, SavePercentage)>,
+    }
+}
+
+impl Part {
+    /// Runtime state depending on the part
+    fn scopes(self) -> AdaptScopes {
+        match self {
+            // Submaster levels are sent to the runtime when restored
+            Part::Blueprints
+            | Part::CueLists
+            | Part::Scripts
+            | Part::UniverseMasters
+            | Part::GroupMasters => AdaptScopes::NONE,
+            Part::Universes => AdaptScopes::UNIVERSES | AdaptScopes::PATCH,
+            Part::Endpoints => AdaptScopes::ENDPOINTS,
+            Part::Settings => AdaptScopes::SETTINGS,
+            Part::Groups => AdaptScopes::PATCH,
+            Part::Parked => AdaptScopes::PARKED,
+            Part::Osc | Part::Midi | Part::Timecode | Part::Audio | Part::Schedule => {
+                AdaptScopes::CONTROL
+            }
+        }
+    }
+
+    /// Clients follow the masters on their own
+    fn info(self) -> Option<ProjectInfo> {
+        match self {
+            Part::Blueprints => Some(ProjectInfo::BlueprintsChanged),
+            Part::Universes => Some(ProjectInfo::UniverseListChanged),
+            Part::Endpoints => Some(ProjectInfo::EndpointsChanged),
+            Part::CueLists => Some(ProjectInfo::CueListsChanged),
+            Part::Groups => Some(ProjectInfo::GroupsChanged),
+            Part::Parked => Some(ProjectInfo::ParkedChanged),
+            Part::Settings
+            | Part::Osc
+            | Part::Midi
+            | Part::Timecode
+            | Part::Audio
+            | Part::Schedule
+            | Part::Scripts => Some(ProjectInfo::SettingsChanged),
+            Part::UniverseMasters | Part::GroupMasters => None,
+        }
+    }
+}
+
+fn snapshots(parts: &[Part], p: &Project, m: &Masters) -> Vec<Snapshot> {
+    parts.iter().map(|part| part.snapshot(p, m)).collect()
+}
+
+/// A recorded edit, reverted by restoring the parts as they were before
+#[derive(Debug, Clone)]
+struct Edit {
+    label: String,
+    before: Vec<Snapshot>,
+    after: Vec<Snapshot>,
+    /// Size of the snapshots
+    size: usize,
+}
+
+impl Edit {
+    fn new(label: String, before: Vec<Snapshot>, after: Vec<Snapshot>) -> Self {
+        let size = before.iter().chain(&after).map(Snapshot::size).sum();
+        Self {
+            label,
+            before,
+            after,
+            size,
+        }
+    }
+
+    /// Restores the snapshots and returns the label and the changed parts
+    fn apply(
+        snapshots: &[Snapshot],
+        label: &str,
+        p: &mut Project,
+        m: &mut Masters,
+    ) -> (String, Vec<Part>) {
+        let parts = snapshots.iter().map(Snapshot::part).collect();
+        for snapshot in snapshots {
+            snapshot.clone().restore(p, m);
+        }
+        (label.to_string(), parts)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// Size of all edits
+    size: usize,
+}
+
+impl History {
+    fn push(&mut self, edit: Edit) {
+        self.size -= self.redo.drain(..).map(|e| e.size).sum::<usize>();
+        self.size += edit.size;
+        self.undo.push(edit);
+        while self.undo.len() > HISTORY_LIMIT || (self.size > HISTORY_SIZE && self.undo.len() > 1) {
+            self.size -= self.undo.remove(0).size;
+        }
+    }
+
+    fn undo(&mut self, p: &mut Project, m: &mut Masters) -> Option<(String, Vec<Part>)> {
+        let edit = self.undo.pop()?;
+        let result = Edit::apply(&edit.before, &edit.label, p, m);
+        self.redo.push(edit);
+        Some(result)
+    }
+
+    fn redo(&mut self, p: &mut Project, m: &mut Masters) -> Option<(String, Vec<Part>)> {
+        let edit = self.redo.pop()?;
+        let result = Edit::apply(&edit.after, &edit.label, p, m);
+        self.undo.push(edit);
+        Some(result)
+    }
+
+    fn state(&self) -> EditHistory {
+        let labels = |edits: &[Edit]| edits.iter().map(|e| e.label.clone()).collect();
+        EditHistory {
+            undo: labels(&self.undo),
+            redo: labels(&self.redo),
+        }
+    }
+}
+
+/// Write access to the project, recorded as one edit when committed
+pub struct ProjectEdit<'a> {
+    service: &'a ServiceImpl,
+    project: RwLockWriteGuard<'a, Project>,
+    label: String,
+    parts: &'static [Part],
+    before: Vec<Snapshot>,
+}
+
+impl ProjectEdit<'_> {
+    /// Records the edit, edits dropped without commit are not recorded
+    /// Submasters changed with the edit have to be updated before
+    pub fn commit(self) {
+        let after = snapshots(self.parts, &self.project, &self.service.masters.borrow());
+        let edit = Edit::new(self.label, self.before, after);
+        self.service.update_history(|h| h.push(edit));
+    }
+}
+
+impl Deref for ProjectEdit<'_> {
+    type Target = Project;
+
+    fn deref(&self) -> &Project {
+        &self.project
+    }
+}
+
+impl DerefMut for ProjectEdit<'_> {
+    fn deref_mut(&mut self) -> &mut Project {
+        &mut self.project
+    }
+}
+
+impl ServiceImpl {
+    /// Write access to the given parts of the project
+    pub async fn edit_project(
+        &self,
+        label: impl Into<String>,
+        parts: &'static [Part],
+    ) -> Result<ProjectEdit<'_>, ProjectServiceError> {
+        let project = self.validate_project_mut().await?;
+        Ok(ProjectEdit {
+            service: self,
+            before: snapshots(parts, &project, &self.masters.borrow()),
+            project,
+            label: label.into(),
+            parts,
+        })
+    }
+
+    /// Edits of another project can't be undone
+    pub fn clear_history(&self) {
+        self.update_history(|h| *h = History::default());
+    }
+
+    pub async fn undo_edit(&self) -> Result<String, ProjectServiceError> {
+        let mut p = self.validate_project_mut().await?;
+        let mut undone = None;
+        self.update_masters(|m| self.update_history(|h| undone = h.undo(&mut p, m)));
+        let (label, parts) = undone.ok_or(ProjectServiceError::NothingToUndo)?;
+        self.notify_parts(&parts);
+        Ok(label)
+    }
+
+    pub async fn redo_edit(&self) -> Result<String, ProjectServiceError> {
+        let mut p = self.validate_project_mut().await?;
+        let mut redone = None;
+        self.update_masters(|m| self.update_history(|h| redone = h.redo(&mut p, m)));
+        let (label, parts) = redone.ok_or(ProjectServiceError::NothingToRedo)?;
+        self.notify_parts(&parts);
+        Ok(label)
+    }
+
+    fn update_history(&self, f: impl FnOnce(&mut History)) {
+        let mut history = self.history.lock().expect("History lock poisoned");
+        f(&mut history);
+        self.history_state.send_replace(history.state());
+    }
+
+    fn notify_parts(&self, parts: &[Part]) {
+        let scopes = parts
+            .iter()
+            .fold(AdaptScopes::NONE, |s, part| s | part.scopes());
+        self.adapt_notifier.notify(scopes);
+        for info in parts.iter().filter_map(|part| part.info()) {
+            self.send_info(info.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Edit, HISTORY_LIMIT, HISTORY_SIZE, History, Part, Project, snapshots};
+    use crate::project::create_default_project;
+    use crate::test_util::service_obj;
+    use mlc_communication::services::general::Masters;
+    use mlc_communication::services::project::ProjectService;
+    use mlc_data::SavePercentage;
+
+    fn rename(history: &mut History, p: &mut Project, name: &str) {
+        let parts = [Part::Universes, Part::Parked];
+        let m = Masters::default();
+        let before = snapshots(&parts, p, &m);
+        p.universes[0].name = name.to_string();
+        let after = snapshots(&parts, p, &m);
+        history.push(Edit::new(format!("Rename to {name}"), before, after));
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut p = create_default_project();
+        let mut m = Masters::default();
+        let mut history = History::default();
+        rename(&mut history, &mut p, "Stage");
+        rename(&mut history, &mut p, "Floor");
+
+        let (label, parts) = history.undo(&mut p, &mut m).unwrap();
+        assert_eq!(label, "Rename to Floor");
+        assert_eq!(parts, [Part::Universes, Part::Parked]);
+        assert_eq!(p.universes[0].name, "Stage");
+        history.undo(&mut p, &mut m);
+        assert_eq!(p.universes[0].name, "Universe 1");
+        assert!(history.undo(&mut p, &mut m).is_none());
+
+        history.redo(&mut p, &mut m);
+        assert_eq!(p.universes[0].name, "Stage");
+        assert_eq!(history.state().redo, ["Rename to Floor"]);
+
+        // A new edit discards the undone ones
+        rename(&mut history, &mut p, "Balcony");
+        assert!(history.redo(&mut p, &mut m).is_none());
+        assert_eq!(
+            history.state().undo,
+            ["Rename to Stage", "Rename to Balcony"]
+        );
+
+        for i in 0..HISTORY_LIMIT {
+            rename(&mut history, &mut p, &i.to_string());
+        }
+        assert_eq!(history.state().undo.len(), HISTORY_LIMIT);
+        let size = history.undo.iter().map(|e| e.size).sum::<usize>();
+        assert!(size > 0);
+        assert_eq!(history.size, size);
+    }
+
+    #[test]
+    fn limit_size() {
+        let mut history = History::default();
+        let large = |label: &str| Edit {
+            size: HISTORY_SIZE / 2 + 1,
+            ..Edit::new(label.to_string(), vec![], vec![])
+        };
+        history.push(large("First"));
+        history.push(large("Second"));
+        assert_eq!(history.state().undo, ["Second"]);
+        assert_eq!(history.size, HISTORY_SIZE / 2 + 1);
+
+        // The newest edit is kept even if it is larger than the limit
+        history.push(Edit {
+            size: HISTORY_SIZE * 2,
+            ..Edit::new("Huge".to_string(), vec![], vec![])
+        });
+        assert_eq!(history.state().undo, ["Huge"]);
+    }
+
+    #[tokio::test]
+    async fn undo_deletions() {
+        let (service_obj, _) = service_obj(create_default_project());
+        let level = SavePercentage::create(0.5);
+        let universe = service_obj.universe_create("Stage".into()).await.unwrap();
+        let group = service_obj
+            .group_create("Front".into(), vec![])
+            .await
+            .unwrap();
+        service_obj.set_universe_level(universe, 0.5);
+        service_obj.set_group_level(group, 0.5);
+        let masters = || service_obj.masters.borrow().clone();
+
+        service_obj.universe_delete(universe).await.unwrap();
+        service_obj.group_delete(group).await.unwrap();
+        assert!(masters().universes.is_empty());
+        assert!(masters().groups.is_empty());
+
+        assert_eq!(service_obj.undo().await.unwrap(), "Delete group");
+        assert_eq!(service_obj.group_list().await.unwrap()[0].id, group);
+        assert_eq!(masters().groups, [(group, level)]);
+        assert_eq!(service_obj.undo().await.unwrap(), "Delete universe");
+        assert!(
+            service_obj
+                .project
+                .read()
+                .await
+                .universe(universe)
+                .is_some()
+        );
+        assert_eq!(masters().universes, [(universe, level)]);
+
+        service_obj.redo().await.unwrap();
+        assert!(masters().universes.is_empty());
+        assert_eq!(masters().groups, [(group, level)]);
+        service_obj.redo().await.unwrap();
+        assert!(service_obj.group_list().await.unwrap().is_empty());
+        assert!(masters().groups.is_empty());
+    }
+}
@@ -0,0 +1,179 @@
+//! Upgrades stored projects of older schema versions step by step before they are deserialized.
+
+use mlc_data::DynamicResult;
+use serde_json::{Map, Value, json};
+
+/// Version of the stored project format, increased with every migration
+pub const SCHEMA_VERSION: u32 = 2;
+pub const VERSION_KEY: &str = "schema_version";
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), String>;
+
+/// The migration at index `i` upgrades documents of version `i` to `i + 1`
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [number_universes, address_artnet];
+
+/// Brings the document to the current schema version
+pub fn migrate(document: &mut Value) -> DynamicResult<()> {
+    let project = document
+        .as_object_mut()
+        .ok_or("The project is not a document")?;
+    // Projects saved before versioning have no version
+    let version = match project.get(VERSION_KEY) {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| format!("Invalid schema version {v}"))?,
+    };
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "The project uses the schema version {version}, this version of MLC supports up to \
+             {SCHEMA_VERSION}"
+        )
+        .into());
+    }
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let to = from as u32 + 1;
+        migration(project)
+            .map_err(|e| format!("Migrating the project to schema version {to} failed: {e}"))?;
+        project.insert(VERSION_KEY.to_string(), to.into());
+    }
+    Ok(())
+}
+
+/// Version 1: universes saved before they had ids are numbered by their position
+fn number_universes(project: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(universes) = project.get_mut("universes") else {
+        return Ok(());
+    };
+    let universes = universes
+        .as_array_mut()
+        .ok_or("The universes are not a list")?;
+    let numbered = universes.iter().all(|u| {
+        u.get("id")
+            .and_then(Value::as_u64)
+            .is_some_and(|id| id != 0)
+    });
+    if numbered {
+        return Ok(());
+    }
+
+    for (i, universe) in universes.iter_mut().enumerate() {
+        let universe = universe
+            .as_object_mut()
+            .ok_or("A universe is not a document")?;
+        let id = i + 1;
+        universe.insert("id".to_string(), id.into());
+        if universe
+            .get("name")
+            .and_then(Value::as_str)
+            .is_none_or(str::is_empty)
+        {
+            universe.insert("name".to_string(), format!("Universe {id}").into());
+        }
+    }
+    Ok(())
+}
+
+/// Version 2: Art-Net endpoints got a node and a Port-Address, the old ones are broadcast to the
+/// Art-Net universe below their universe, as Art-Net universes start at 0
+fn address_artnet(project: &mut Map<String, Value>) -> Result<(), String> {
+    let Some(endpoints) = project
+        .get_mut("endpoint_mapping")
+        .and_then(|m| m.get_mut("endpoints"))
+    else {
+        return Ok(());
+    };
+    let endpoints = endpoints
+        .as_object_mut()
+        .ok_or("The endpoints are not a document")?;
+
+    for (universe, configs) in endpoints.iter_mut() {
+        let universe = universe
+            .parse::<u16>()
+            .map_err(|_| format!("Invalid endpoint universe {universe}"))?;
+        let configs = configs
+            .as_array_mut()
+            .ok_or("The endpoints of a universe are not a list")?;
+        for config in configs.iter_mut().filter(|c| **c == "ArtNet") {
+            *config = json!({
+                "ArtNet": {
+                    "port_address": universe.saturating_sub(1).min(0x7FFF),
+                    "speed": "Medium",
+                }
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SCHEMA_VERSION, VERSION_KEY, migrate};
+    use crate::project::project_loader::{BsonLoader, JsonLoader, ProjectLoader};
+    use mlc_data::endpoints::{EndpointConfig, EndpointSpeed};
+    use serde_json::Value;
+    use std::net::Ipv4Addr;
+
+    const V0_PROJECT: &str = include_str!("../../fixtures/projects/v0.json");
+    const V1_PROJECT: &str = include_str!("../../fixtures/projects/v1.json");
+    const V2_PROJECT: &str = include_str!("../../fixtures/projects/v2.json");
+
+    /// Only for fixtures without binary values like ids, those are stored as strings in JSON
+    fn to_bson(document: &str) -> Vec<u8> {
+        let value: Value = serde_json::from_str(document).unwrap();
+        bson::to_vec(&bson::Bson::try_from(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn number_universes() {
+        let mut document: Value = serde_json::from_str(V0_PROJECT).unwrap();
+        migrate(&mut document).unwrap();
+        assert_eq!(document[VERSION_KEY], SCHEMA_VERSION);
+        assert_eq!(document["universes"][0]["id"], 1);
+        assert_eq!(document["universes"][0]["name"], "Universe 1");
+        assert_eq!(document["universes"][1]["id"], 2);
+        assert_eq!(document["universes"][1]["name"], "Stage");
+
+        // Both formats load through the migrations
+        for project in [
+            JsonLoader.load_project(V0_PROJECT.into()).unwrap(),
+            BsonLoader.load_project(to_bson(V0_PROJECT)).unwrap(),
+        ] {
+            assert_eq!(project.schema_version, SCHEMA_VERSION);
+            let ids = project.universes.iter().map(|u| u.id).collect::<Vec<_>>();
+            assert_eq!(ids, [1, 2]);
+        }
+    }
+
+    #[test]
+    fn address_artnet() {
+        let project = JsonLoader.load_project(V1_PROJECT.into()).unwrap();
+        assert_eq!(
+            project.endpoint_mapping.endpoints[&2],
+            [
+                EndpointConfig::artnet(Ipv4Addr::BROADCAST, 1, EndpointSpeed::Medium),
+                EndpointConfig::Logger
+            ]
+        );
+    }
+
+    #[test]
+    fn current_and_future_versions() {
+        let mut document: Value = serde_json::from_str(V2_PROJECT).unwrap();
+        let original = document.clone();
+        migrate(&mut document).unwrap();
+        assert_eq!(document, original);
+
+        let project = JsonLoader.load_project(V2_PROJECT.into()).unwrap();
+        let stored = BsonLoader.store_project(&project).unwrap();
+        let reloaded = BsonLoader.load_project(stored).unwrap();
+        assert_eq!(reloaded.cue_lists[0].id, project.cue_lists[0].id);
+
+        document[VERSION_KEY] = (SCHEMA_VERSION + 1).into();
+        let error = migrate(&mut document).unwrap_err().to_string();
+        let expected = format!("schema version {}", SCHEMA_VERSION + 1);
+        assert!(error.contains(&expected), "{error}");
+    }
+}
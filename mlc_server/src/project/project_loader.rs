@@ -1,4 +1,6 @@
 use crate::project::Project;
+use crate::project::migration::migrate;
+use bson::Bson;
 use lazy_static::lazy_static;
 use mlc_data::project::{ProjectMetadata, ProjectType};
 use mlc_data::DynamicResult;
@@ -20,7 +22,7 @@ impl ProjectLoaderManager {
         &LOADERS
     }
 
-    pub fn for_file(path: &Path) -> Option<&BoxedLoader> {
+    pub fn for_file(path: &Path) -> Option<&'static BoxedLoader> {
         Self::loaders().iter().find(|&loader| {
             path.extension()
                 .unwrap_or(&OsString::from_str(".").expect("Must be"))
@@ -28,7 +30,7 @@ impl ProjectLoaderManager {
         })
     }
 
-    pub fn for_kind(kind: &ProjectType) -> Option<&BoxedLoader> {
+    pub fn for_kind(kind: &ProjectType) -> Option<&'static BoxedLoader> {
         Self::loaders()
             .iter()
             .find(|&loader| loader.kind() == *kind)
@@ -56,7 +58,9 @@ impl ProjectLoader for Json5Loader {
     }
 
     fn load_project(&self, data: Vec<u8>) -> DynamicResult<Project> {
-        Ok(json5::from_str(&String::from_utf8(data)?)?)
+        let mut document = json5::from_str(&String::from_utf8(data)?)?;
+        migrate(&mut document)?;
+        Ok(serde_json::from_value(document)?)
     }
 
     fn store_project(&self, data: &Project) -> DynamicResult<Vec<u8>> {
@@ -76,7 +80,9 @@ impl ProjectLoader for JsonLoader {
     }
 
     fn load_project(&self, data: Vec<u8>) -> DynamicResult<Project> {
-        Ok(serde_json::from_str(&String::from_utf8(data)?)?)
+        let mut document = serde_json::from_slice(&data)?;
+        migrate(&mut document)?;
+        Ok(serde_json::from_value(document)?)
     }
 
     fn store_project(&self, data: &Project) -> DynamicResult<Vec<u8>> {
@@ -95,7 +101,13 @@ impl ProjectLoader for BsonLoader {
     }
 
     fn load_project(&self, data: Vec<u8>) -> DynamicResult<Project> {
-        Ok(bson::from_slice(&data)?)
+        // Migrations work on extended JSON, which keeps binary values like ids intact
+        let document: bson::Document = bson::from_slice(&data)?;
+        let mut document = Bson::Document(document).into_relaxed_extjson();
+        migrate(&mut document)?;
+        Ok(bson::from_slice(&bson::to_vec(&Bson::try_from(
+            document,
+        )?)?)?)
     }
 
     fn store_project(&self, data: &Project) -> DynamicResult<Vec<u8>> {
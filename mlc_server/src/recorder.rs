@@ -0,0 +1,604 @@
+use crate::misc::{ShutdownHandler, ShutdownPhase};
+use crate::project::get_base_app_dir;
+use crate::universe::{LayerFrame, RuntimeCommand, RuntimeLayer, UniverseUpdate};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use mlc_communication::remoc::rch::watch;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::general::Info;
+use mlc_communication::services::recorder::{
+    PlaybackState, RecorderService, RecorderServiceError, RecorderState, RecordingSource,
+};
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::ToFileName;
+use mlc_data::project::universe::{UNIVERSE_SIZE, UniverseId};
+use mlc_data::recording::{
+    DmxRecording, RECORDING_EXTENSION, RecordingPlayer, RecordingWriter, UniverseValues,
+};
+use sacn::receive::SacnReceiver;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::{error, info};
+
+/// Recordings are sampled and played back at 40 frames per second
+const FRAME_TIME: Duration = Duration::from_millis(25);
+
+pub fn recordings_dir() -> PathBuf {
+    get_base_app_dir().join("recordings")
+}
+
+fn recording_path(name: &str) -> PathBuf {
+    recordings_dir().join(format!("{name}.{RECORDING_EXTENSION}"))
+}
+
+pub enum RecorderCommand {
+    StartRecording {
+        name: String,
+        writer: RecordingWriter<BufWriter<File>>,
+        source: RecordingSource,
+    },
+    StopRecording,
+    Play {
+        name: String,
+        player: RecordingPlayer,
+        speed: f32,
+        looping: bool,
+    },
+    SetSpeed(f32),
+    StopPlayback,
+}
+
+#[rtc::async_trait]
+impl RecorderService for ServiceImpl {
+    async fn state(&self) -> Result<watch::Receiver<RecorderState>, RecorderServiceError> {
+        Ok(self.recorder_state.subscribe())
+    }
+
+    async fn recordings(&self) -> Result<Vec<String>, RecorderServiceError> {
+        let mut entries = match tokio::fs::read_dir(recordings_dir()).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(RecorderServiceError::FileError(e.to_string())),
+        };
+
+        let mut names = vec![];
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|e| e == RECORDING_EXTENSION)
+                && let Some(name) = path.file_stem()
+            {
+                names.push(name.to_string_lossy().to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    async fn start_recording(
+        &self,
+        name: String,
+        source: RecordingSource,
+        overwrite: bool,
+    ) -> Result<(), RecorderServiceError> {
+        if self.recorder_state.borrow().recording.is_some() {
+            return Err(RecorderServiceError::AlreadyRecording);
+        }
+        let file_name = name.to_project_file_name();
+        if file_name.is_empty() {
+            return Err(RecorderServiceError::InvalidName(name));
+        }
+
+        let path = recording_path(&file_name);
+        let writer = tokio::task::spawn_blocking(move || create_writer(&path, overwrite))
+            .await
+            .map_err(|e| RecorderServiceError::FileError(e.to_string()))?
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => RecorderServiceError::RecordingExists(name),
+                _ => RecorderServiceError::FileError(e.to_string()),
+            })?;
+
+        self.recorder
+            .send(RecorderCommand::StartRecording {
+                name: file_name,
+                writer,
+                source,
+            })
+            .debug_ignore();
+        Ok(())
+    }
+
+    async fn stop_recording(&self) -> Result<(), RecorderServiceError> {
+        if self.recorder_state.borrow().recording.is_none() {
+            return Err(RecorderServiceError::NotRecording);
+        }
+        self.recorder
+            .send(RecorderCommand::StopRecording)
+            .debug_ignore();
+        Ok(())
+    }
+
+    async fn play(
+        &self,
+        name: String,
+        speed: f32,
+        looping: bool,
+    ) -> Result<(), RecorderServiceError> {
+        validate_speed(speed)?;
+        let file_name = name.to_project_file_name();
+        let data = match tokio::fs::read(recording_path(&file_name)).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(RecorderServiceError::UnknownRecording(name));
+            }
+            Err(e) => return Err(RecorderServiceError::FileError(e.to_string())),
+        };
+        let recording = DmxRecording::read(data.as_slice())
+            .map_err(|e| RecorderServiceError::FileError(e.to_string()))?;
+
+        self.recorder
+            .send(RecorderCommand::Play {
+                name: file_name,
+                player: RecordingPlayer::new(recording),
+                speed,
+                looping,
+            })
+            .debug_ignore();
+        Ok(())
+    }
+
+    async fn set_playback_speed(&self, speed: f32) -> Result<(), RecorderServiceError> {
+        validate_speed(speed)?;
+        if self.recorder_state.borrow().playback.is_none() {
+            return Err(RecorderServiceError::NotPlaying);
+        }
+        self.recorder
+            .send(RecorderCommand::SetSpeed(speed))
+            .debug_ignore();
+        Ok(())
+    }
+
+    async fn stop_playback(&self) -> Result<(), RecorderServiceError> {
+        if self.recorder_state.borrow().playback.is_none() {
+            return Err(RecorderServiceError::NotPlaying);
+        }
+        self.recorder
+            .send(RecorderCommand::StopPlayback)
+            .debug_ignore();
+        Ok(())
+    }
+}
+
+fn create_writer(path: &Path, overwrite: bool) -> io::Result<RecordingWriter<BufWriter<File>>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = File::options()
+        .write(true)
+        .truncate(true)
+        .create(overwrite)
+        .create_new(!overwrite)
+        .open(path)?;
+    RecordingWriter::new(BufWriter::new(file))
+}
+
+fn validate_speed(speed: f32) -> Result<(), RecorderServiceError> {
+    if speed.is_finite() && speed > 0.0 {
+        Ok(())
+    } else {
+        Err(RecorderServiceError::InvalidSpeed(speed))
+    }
+}
+
+pub struct RecordingManagerService {
+    cmd_recv: UnboundedReceiver<RecorderCommand>,
+}
+
+impl RecordingManagerService {
+    pub fn create() -> (Self, UnboundedSender<RecorderCommand>) {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        (Self { cmd_recv: rx }, tx)
+    }
+}
+
+impl MlcServiceSimple for RecordingManagerService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_recorder(self.cmd_recv, res.service_obj.clone(), res.shutdown.clone())
+    }
+}
+
+enum RecordingInput {
+    Output(broadcast::Receiver<UniverseUpdate>),
+    Sacn {
+        rx: mpsc::Receiver<(u16, Vec<u8>)>,
+        stop: Arc<AtomicBool>,
+    },
+}
+
+/// Writes the frames of a recording on its own thread, so the file I/O does not block the recorder
+struct FrameWriter {
+    frames: std::sync::mpsc::Sender<(Duration, UniverseValues)>,
+    thread: std::thread::JoinHandle<io::Result<()>>,
+}
+
+impl FrameWriter {
+    fn spawn(mut writer: RecordingWriter<BufWriter<File>>) -> Self {
+        let (frames, rx) = std::sync::mpsc::channel::<(Duration, UniverseValues)>();
+        let thread = std::thread::spawn(move || {
+            for (time, values) in rx {
+                writer.write_frame(time, &values)?;
+            }
+            writer.finish()?.into_inner()?.sync_all()
+        });
+        Self { frames, thread }
+    }
+
+    /// False once the writer stopped because of an error
+    fn write(&self, time: Duration, values: &UniverseValues) -> bool {
+        self.frames.send((time, values.clone())).is_ok()
+    }
+
+    /// Waits for the queued frames to be written and completes the file
+    async fn finish(self) -> io::Result<()> {
+        drop(self.frames);
+        let thread = self.thread;
+        tokio::task::spawn_blocking(move || thread.join())
+            .await
+            .map_err(io::Error::other)?
+            .map_err(|_| io::Error::other("The recording writer panicked"))?
+    }
+}
+
+struct ActiveRecording {
+    writer: FrameWriter,
+    input: RecordingInput,
+    values: UniverseValues,
+    started: Instant,
+    dirty: bool,
+}
+
+impl ActiveRecording {
+    fn apply(&mut self, update: UniverseUpdate) {
+        let mut set = |universe: UniverseId, index: usize, value: u8| {
+            self.values.entry(universe).or_insert([0; UNIVERSE_SIZE])[index] = value;
+        };
+        match update {
+            UniverseUpdate::Single { update } => {
+                set(update.0.universe(), update.0.address().take() - 1, update.1);
+            }
+            UniverseUpdate::Many { updates } => {
+                for update in updates {
+                    set(update.0.universe(), update.0.address().take() - 1, update.1);
+                }
+            }
+            UniverseUpdate::Entire { universe, values } => {
+                self.values.insert(universe, *values);
+            }
+        }
+        self.dirty = true;
+    }
+
+    async fn finish(self) -> io::Result<()> {
+        if let RecordingInput::Sacn { stop, .. } = &self.input {
+            stop.store(true, Ordering::Relaxed);
+        }
+        self.writer.finish().await
+    }
+}
+
+struct ActivePlayback {
+    name: String,
+    player: RecordingPlayer,
+    speed: f32,
+    looping: bool,
+    position: Duration,
+    last_tick: Instant,
+}
+
+impl ActivePlayback {
+    fn state(&self) -> PlaybackState {
+        PlaybackState {
+            name: self.name.clone(),
+            speed: self.speed,
+            looping: self.looping,
+            duration_ms: self.player.duration().as_millis() as u64,
+        }
+    }
+}
+
+async fn next_update(recording: &mut Option<ActiveRecording>) -> Result<UniverseUpdate, RecvError> {
+    let update = match recording.as_mut().map(|r| &mut r.input) {
+        Some(RecordingInput::Output(rx)) => rx.recv().await?,
+        Some(RecordingInput::Sacn { rx, .. }) => match rx.recv().await {
+            Some((universe, values)) => {
+                let mut data = [0; UNIVERSE_SIZE];
+                let len = values.len().min(UNIVERSE_SIZE);
+                data[..len].copy_from_slice(&values[..len]);
+                UniverseUpdate::Entire {
+                    universe,
+                    values: Box::new(data),
+                }
+            }
+            // The receiving thread stopped on an error
+            None => return Err(RecvError::Closed),
+        },
+        None => std::future::pending().await,
+    };
+    Ok(update)
+}
+
+async fn run_recorder(
+    mut cmd_recv: UnboundedReceiver<RecorderCommand>,
+    service_obj: AServiceImpl,
+    shutdown: ShutdownHandler,
+) {
+    let mut recording: Option<ActiveRecording> = None;
+    let mut playback: Option<ActivePlayback> = None;
+    let mut frame_interval = tokio::time::interval(FRAME_TIME);
+    frame_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        select! {
+            _ = shutdown.wait(ShutdownPhase::Phase1) => {
+                break;
+            }
+            Some(cmd) = cmd_recv.recv() => {
+                match cmd {
+                    RecorderCommand::StartRecording { name, writer, source } => {
+                        let input = match source {
+                            RecordingSource::Output => {
+                                RecordingInput::Output(service_obj.universe_runtime.subscribe())
+                            }
+                            RecordingSource::Sacn { universes } => {
+                                match receive_sacn(&universes) {
+                                    Ok(input) => input,
+                                    Err(e) => {
+                                        service_obj.send_info(Info::Warning {
+                                            title: "Recording failed".to_string(),
+                                            msg: format!("Could not receive sACN: {e}"),
+                                        });
+                                        continue;
+                                    }
+                                }
+                            }
+                        };
+                        finish_recording(&service_obj, &mut recording).await;
+                        info!("Recording {name}");
+                        recording = Some(ActiveRecording {
+                            writer: FrameWriter::spawn(writer),
+                            input,
+                            values: UniverseValues::new(),
+                            started: Instant::now(),
+                            dirty: false,
+                        });
+                        service_obj.recorder_state.send_modify(|s| s.recording = Some(name));
+                    }
+                    RecorderCommand::StopRecording => {
+                        finish_recording(&service_obj, &mut recording).await;
+                    }
+                    RecorderCommand::Play { name, player, speed, looping } => {
+                        stop_playback(&service_obj, &mut playback);
+                        let active = ActivePlayback {
+                            name,
+                            player,
+                            speed,
+                            looping,
+                            position: Duration::ZERO,
+                            last_tick: Instant::now(),
+                        };
+                        let state = active.state();
+                        playback = Some(active);
+                        service_obj.recorder_state.send_modify(|s| s.playback = Some(state));
+                    }
+                    RecorderCommand::SetSpeed(speed) => {
+                        if let Some(p) = playback.as_mut() {
+                            p.speed = speed;
+                            let state = p.state();
+                            service_obj.recorder_state.send_modify(|s| s.playback = Some(state));
+                        }
+                    }
+                    RecorderCommand::StopPlayback => {
+                        stop_playback(&service_obj, &mut playback);
+                    }
+                }
+            }
+            update = next_update(&mut recording) => {
+                match update {
+                    Ok(update) => {
+                        if let Some(r) = recording.as_mut() {
+                            r.apply(update);
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => {
+                        service_obj.universe_runtime.cmd(RuntimeCommand::ResendUniverses);
+                    }
+                    Err(RecvError::Closed) => {
+                        service_obj.send_info(Info::Warning {
+                            title: "Recording stopped".to_string(),
+                            msg: "The recorded input is no longer available".to_string(),
+                        });
+                        finish_recording(&service_obj, &mut recording).await;
+                    }
+                }
+            }
+            _ = frame_interval.tick() => {
+                if let Some(r) = recording.as_mut().filter(|r| r.dirty) {
+                    r.dirty = false;
+                    if !r.writer.write(r.started.elapsed(), &r.values) {
+                        // The writer reports its error when finished
+                        finish_recording(&service_obj, &mut recording).await;
+                    }
+                }
+
+                if let Some(p) = playback.as_mut() {
+                    let now = Instant::now();
+                    p.position += (now - p.last_tick).mul_f32(p.speed);
+                    p.last_tick = now;
+
+                    if p.player.finished() && p.position > p.player.duration() {
+                        if p.looping {
+                            p.position = p.position
+                                .checked_sub(p.player.duration())
+                                .unwrap_or_default()
+                                .min(p.player.duration());
+                        } else {
+                            stop_playback(&service_obj, &mut playback);
+                            continue;
+                        }
+                    }
+
+                    if p.player.advance_to(p.position) {
+                        service_obj.universe_runtime.cmd(RuntimeCommand::SetLayer {
+                            layer: RuntimeLayer::Playback,
+                            frames: playback_frames(p.player.values()),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    finish_recording(&service_obj, &mut recording).await;
+    stop_playback(&service_obj, &mut playback);
+}
+
+async fn finish_recording(service_obj: &ServiceImpl, recording: &mut Option<ActiveRecording>) {
+    let Some(r) = recording.take() else {
+        return;
+    };
+    if let Err(e) = r.finish().await {
+        error!("Failed to finish recording: {e}");
+        service_obj.send_info(Info::Warning {
+            title: "Recording failed".to_string(),
+            msg: e.to_string(),
+        });
+    }
+    service_obj
+        .recorder_state
+        .send_modify(|s| s.recording = None);
+}
+
+fn stop_playback(service_obj: &ServiceImpl, playback: &mut Option<ActivePlayback>) {
+    if playback.take().is_some() {
+        service_obj
+            .universe_runtime
+            .cmd(RuntimeCommand::ClearLayer(RuntimeLayer::Playback));
+        service_obj
+            .recorder_state
+            .send_modify(|s| s.playback = None);
+    }
+}
+
+fn playback_frames(values: &UniverseValues) -> HashMap<UniverseId, Box<LayerFrame>> {
+    values
+        .iter()
+        .map(|(universe, values)| (*universe, Box::new(values.map(Some))))
+        .collect()
+}
+
+/// Receives the given sACN universes on a separate thread, as the receiver is blocking
+fn receive_sacn(universes: &[u16]) -> Result<RecordingInput, sacn::error::errors::Error> {
+    let address = SocketAddr::from((Ipv4Addr::UNSPECIFIED, sacn::packet::ACN_SDT_MULTICAST_PORT));
+    let mut receiver = SacnReceiver::with_ip(address, None)?;
+    receiver.listen_universes(universes)?;
+
+    let (tx, rx) = mpsc::channel(64);
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+
+    std::thread::spawn(move || {
+        while !stopped.load(Ordering::Relaxed) {
+            let data = match receiver.recv(Some(Duration::from_millis(100))) {
+                Ok(data) => data,
+                // Only used to check for the stop flag
+                Err(e) if is_timeout(&e) => continue,
+                Err(e) => {
+                    error!("Receiving sACN failed: {e}");
+                    return;
+                }
+            };
+            for d in data {
+                // The first value is the start code, only dmx data is recorded
+                if let Some((0, values)) = d.values.split_first()
+                    && tx.blocking_send((d.universe, values.to_vec())).is_err()
+                {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(RecordingInput::Sacn { rx, stop })
+}
+
+fn is_timeout(error: &sacn::error::errors::Error) -> bool {
+    matches!(
+        error.kind(),
+        sacn::error::errors::ErrorKind::Io(e)
+            if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FrameWriter, create_writer, is_timeout};
+    use mlc_data::project::universe::UNIVERSE_SIZE;
+    use mlc_data::recording::{DmxRecording, RecordingPlayer, UniverseValues};
+    use std::io;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn write_recordings() {
+        let dir = std::env::temp_dir().join(format!("mlc_recorder_test_{}", std::process::id()));
+        let path = dir.join("show.mlcrec");
+
+        let writer = FrameWriter::spawn(create_writer(&path, false).unwrap());
+        let mut values = UniverseValues::new();
+        for (i, value) in [10, 20].into_iter().enumerate() {
+            values.insert(1, [value; UNIVERSE_SIZE]);
+            assert!(writer.write(Duration::from_millis(25 * i as u64), &values));
+        }
+        writer.finish().await.unwrap();
+
+        let data = std::fs::read(&path).unwrap();
+        let mut player = RecordingPlayer::new(DmxRecording::read(data.as_slice()).unwrap());
+        assert_eq!(player.duration(), Duration::from_millis(25));
+        player.advance_to(Duration::from_millis(25));
+        assert_eq!(player.values()[&1][511], 20);
+
+        // Existing recordings are only replaced on request
+        let exists = create_writer(&path, false).err().unwrap();
+        assert_eq!(exists.kind(), io::ErrorKind::AlreadyExists);
+        FrameWriter::spawn(create_writer(&path, true).unwrap())
+            .finish()
+            .await
+            .unwrap();
+        let data = std::fs::read(&path).unwrap();
+        assert!(
+            DmxRecording::read(data.as_slice())
+                .unwrap()
+                .frames
+                .is_empty()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sacn_timeouts() {
+        let error = |kind| sacn::error::errors::Error::from(io::Error::from(kind));
+        assert!(is_timeout(&error(io::ErrorKind::WouldBlock)));
+        assert!(is_timeout(&error(io::ErrorKind::TimedOut)));
+        assert!(!is_timeout(&error(io::ErrorKind::ConnectionReset)));
+        assert!(!is_timeout(&"Failed to set a timeout".into()));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use mlc_communication::remoc::rch::watch;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::recorder::RecorderService;
+use mlc_communication::services::schedule::{
+    ScheduleService, ScheduleServiceError, ScheduleState, ScheduledTrigger,
+};
+use mlc_data::project::schedule::{ScheduleAction, ScheduleSettings};
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+const TICK: Duration = Duration::from_secs(1);
+/// Larger clock jumps skip the entries in between instead of triggering all of them
+const MAX_CATCH_UP: TimeDelta = TimeDelta::minutes(1);
+
+pub enum ScheduleCommand {
+    Simulate(Option<DateTime<Utc>>),
+}
+
+#[rtc::async_trait]
+impl ScheduleService for ServiceImpl {
+    async fn state(&self) -> Result<watch::Receiver<ScheduleState>, ScheduleServiceError> {
+        Ok(self.schedule_state.subscribe())
+    }
+
+    async fn simulate_time(&self, time: Option<DateTime<Utc>>) -> Result<(), ScheduleServiceError> {
+        self.schedule
+            .send(ScheduleCommand::Simulate(time))
+            .map_err(|_| ScheduleServiceError::NotRunning)
+    }
+}
+
+/// The local time, or a simulated time running on from when it was set
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScheduleClock {
+    offset: Option<TimeDelta>,
+}
+
+impl ScheduleClock {
+    pub fn now(&self) -> DateTime<Local> {
+        Local::now() + self.offset.unwrap_or_default()
+    }
+
+    pub fn simulate(&mut self, time: Option<DateTime<Utc>>) {
+        self.offset = time.map(|t| t - Utc::now());
+    }
+
+    pub fn is_simulated(&self) -> bool {
+        self.offset.is_some()
+    }
+}
+
+pub struct SchedulerService {
+    rx: UnboundedReceiver<ScheduleCommand>,
+}
+
+impl SchedulerService {
+    pub fn create() -> (Self, UnboundedSender<ScheduleCommand>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { rx }, tx)
+    }
+}
+
+impl MlcServiceSimple for SchedulerService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_scheduler(
+            res.service_obj.clone(),
+            res.shutdown.clone(),
+            res.adapt_notifier.clone(),
+            self.rx,
+        )
+    }
+}
+
+async fn run_scheduler(
+    service_obj: AServiceImpl,
+    shutdown_handler: ShutdownHandler,
+    adapt_notifier: AdaptNotifier,
+    mut rx: UnboundedReceiver<ScheduleCommand>,
+) {
+    let mut interval = tokio::time::interval(TICK);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut clock = ScheduleClock::default();
+
+    loop {
+        let settings = service_obj.project.read().await.schedule.clone();
+        let mut last = clock.now();
+
+        loop {
+            select! {
+                _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                    return;
+                }
+                _ = adapt_notifier.wait(AdaptScopes::CONTROL | AdaptScopes::PROJECT) => {
+                    break;
+                }
+                Some(command) = rx.recv() => match command {
+                    ScheduleCommand::Simulate(time) => {
+                        clock.simulate(time);
+                        last = clock.now();
+                        info!("Scheduler clock set to {last}");
+                        publish_state(&service_obj, &settings, &clock, last);
+                    }
+                },
+                _ = interval.tick() => {
+                    let now = clock.now();
+                    if now < last || now - last > MAX_CATCH_UP {
+                        last = now;
+                    }
+                    if settings.enabled {
+                        for i in settings.due(&last, &now) {
+                            execute_entry(&service_obj, &settings, i, now).await;
+                        }
+                    }
+                    last = now;
+                    publish_state(&service_obj, &settings, &clock, now);
+                }
+            }
+        }
+    }
+}
+
+async fn execute_entry(
+    service_obj: &AServiceImpl,
+    settings: &ScheduleSettings,
+    entry: usize,
+    now: DateTime<Local>,
+) {
+    let entry = &settings.entries[entry];
+    info!("Running the scheduled {:?}", entry.name);
+    let result = match &entry.action {
+        ScheduleAction::Control { action, value } => service_obj
+            .execute_control(action, *value)
+            .await
+            .map_err(|e| e.to_string()),
+        ScheduleAction::PlayRecording { name, looping } => service_obj
+            .play(name.clone(), 1.0, *looping)
+            .await
+            .map_err(|e| e.to_string()),
+        ScheduleAction::StopPlayback => {
+            service_obj.stop_playback().await.map_err(|e| e.to_string())
+        }
+    };
+    if let Err(e) = result {
+        warn!("The scheduled {:?} failed: {e}", entry.name);
+    }
+
+    service_obj.schedule_state.send_modify(|s| {
+        s.last = Some(ScheduledTrigger {
+            entry: entry.name.clone(),
+            at: now.with_timezone(&Utc),
+        })
+    });
+}
+
+fn publish_state(
+    service_obj: &AServiceImpl,
+    settings: &ScheduleSettings,
+    clock: &ScheduleClock,
+    now: DateTime<Local>,
+) {
+    let next = settings
+        .enabled
+        .then(|| settings.next(&now))
+        .flatten()
+        .map(|(at, i)| ScheduledTrigger {
+            entry: settings.entries[i].name.clone(),
+            at,
+        });
+    let state = ScheduleState {
+        simulated: clock.is_simulated().then(|| now.with_timezone(&Utc)),
+        next,
+        last: service_obj.schedule_state.borrow().last.clone(),
+    };
+    if *service_obj.schedule_state.borrow() != state {
+        service_obj.schedule_state.send_replace(state);
+    }
+}
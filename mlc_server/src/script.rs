@@ -0,0 +1,688 @@
+//! Project scripts written in [Rhai](https://rhai.rs), run on blocking threads.
+//!
+//! Scripts can only call the host functions listed in [`HOST_FUNCTIONS`], modules and `eval` are
+//! disabled and scripts are stopped after too many operations or too deep calls.
+
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::universe::UniverseUpdate;
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use mlc_communication::remoc::rch::watch;
+use mlc_communication::remoc::rtc;
+use mlc_communication::services::general::Info;
+use mlc_communication::services::programmer::ProgrammerService;
+use mlc_communication::services::recorder::RecorderService;
+use mlc_communication::services::script::{ScriptService, ScriptServiceError, ScriptState};
+use mlc_data::SavePercentage;
+use mlc_data::fixture::patched::attribute::FixtureAttribute;
+use mlc_data::misc::ErrIgnore;
+use mlc_data::project::control::ControlAction;
+use mlc_data::project::cue::CueListId;
+use mlc_data::project::universe::{FixtureAddress, UNIVERSE_SIZE, UniverseAddress};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{AST, Dynamic, Engine, EvalAltResult, FLOAT, INT, ParseError};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::select;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Longest time a script can `wait` at once
+const MAX_WAIT: Duration = Duration::from_secs(3600);
+/// Waits are split into slices to notice when the script is stopped
+const WAIT_SLICE: Duration = Duration::from_millis(50);
+const CHANNEL_READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Operations a script can run before it is stopped, waiting doesn't count
+const MAX_OPERATIONS: u64 = 10_000_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPR_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+
+/// The functions scripts can call with the numbers of arguments they accept
+const HOST_FUNCTIONS: [(&str, &[usize]); 27] = [
+    ("log", &[1]),
+    ("wait", &[1]),
+    ("project_name", &[0]),
+    ("channel", &[2]),
+    ("set_channel", &[3]),
+    ("grand_master", &[0]),
+    ("set_grand_master", &[1]),
+    ("blackout", &[0]),
+    ("set_blackout", &[1]),
+    ("set_group_master", &[2]),
+    ("set_universe_master", &[2]),
+    ("group_count", &[0]),
+    ("group_name", &[1]),
+    ("select_group", &[1]),
+    ("set_intensity", &[1]),
+    ("clear", &[0]),
+    ("cue_list_count", &[0]),
+    ("cue_list_name", &[1]),
+    ("cue_go", &[0, 1]),
+    ("cue_back", &[0, 1]),
+    ("cue_release", &[0, 1]),
+    ("current_cue", &[0, 1]),
+    ("play_recording", &[1, 2]),
+    ("stop_playback", &[0]),
+    ("bpm", &[0]),
+    ("beat", &[0]),
+    ("tap", &[0]),
+];
+
+pub enum ScriptCommand {
+    Run(String),
+    Stop(String),
+}
+
+#[rtc::async_trait]
+impl ScriptService for ServiceImpl {
+    async fn state(&self) -> Result<watch::Receiver<ScriptState>, ScriptServiceError> {
+        Ok(self.script_state.subscribe())
+    }
+
+    async fn run(&self, name: String) -> Result<(), ScriptServiceError> {
+        self.run_script(name).await
+    }
+
+    async fn stop(&self, name: String) -> Result<(), ScriptServiceError> {
+        if !self.script_state.borrow().running.contains(&name) {
+            return Err(ScriptServiceError::NotRunning(name));
+        }
+        self.scripts.send(ScriptCommand::Stop(name)).debug_ignore();
+        Ok(())
+    }
+}
+
+impl ServiceImpl {
+    pub async fn run_script(&self, name: String) -> Result<(), ScriptServiceError> {
+        let p = self
+            .validate_project()
+            .await
+            .map_err(|_| ScriptServiceError::InvalidProject)?;
+        if !p.scripts.iter().any(|s| s.name == name) {
+            return Err(ScriptServiceError::UnknownScript(name));
+        }
+        if self.script_state.borrow().running.contains(&name) {
+            return Err(ScriptServiceError::AlreadyRunning(name));
+        }
+        self.scripts.send(ScriptCommand::Run(name)).debug_ignore();
+        Ok(())
+    }
+}
+
+pub struct ScriptRunnerService {
+    rx: UnboundedReceiver<ScriptCommand>,
+}
+
+impl ScriptRunnerService {
+    pub fn create() -> (Self, UnboundedSender<ScriptCommand>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { rx }, tx)
+    }
+}
+
+impl MlcServiceSimple for ScriptRunnerService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_scripts(
+            res.service_obj.clone(),
+            res.shutdown.clone(),
+            res.adapt_notifier.clone(),
+            self.rx,
+        )
+    }
+}
+
+/// Runs every script on a blocking thread, scripts are stopped when the project changes
+async fn run_scripts(
+    service_obj: AServiceImpl,
+    shutdown_handler: ShutdownHandler,
+    adapt_notifier: AdaptNotifier,
+    mut rx: UnboundedReceiver<ScriptCommand>,
+) {
+    let mut running: HashMap<String, Arc<AtomicBool>> = HashMap::new();
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+
+    loop {
+        select! {
+            _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                running.values().for_each(|stop| stop.store(true, Ordering::Relaxed));
+                return;
+            }
+            _ = adapt_notifier.wait(AdaptScopes::PROJECT) => {
+                running.values().for_each(|stop| stop.store(true, Ordering::Relaxed));
+            }
+            Some(command) = rx.recv() => match command {
+                ScriptCommand::Run(name) => {
+                    if running.contains_key(&name) {
+                        continue;
+                    }
+                    let project = service_obj.project.read().await;
+                    let Some(script) = project.scripts.iter().find(|s| s.name == name) else {
+                        continue;
+                    };
+                    let stop = Arc::new(AtomicBool::new(false));
+                    let host = ServerHost {
+                        service_obj: service_obj.clone(),
+                        handle: Handle::current(),
+                        stop: stop.clone(),
+                    };
+                    spawn_script(host, name.clone(), script.source.clone(), done_tx.clone());
+                    running.insert(name, stop);
+                    publish_state(&service_obj, &running);
+                }
+                ScriptCommand::Stop(name) => {
+                    if let Some(stop) = running.get(&name) {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                }
+            },
+            Some(name) = done_rx.recv() => {
+                running.remove(&name);
+                publish_state(&service_obj, &running);
+            }
+        }
+    }
+}
+
+/// Reports the script as done when dropped, so a panicking script doesn't stay running
+struct Done {
+    name: String,
+    tx: UnboundedSender<String>,
+}
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        self.tx.send(std::mem::take(&mut self.name)).debug_ignore();
+    }
+}
+
+fn spawn_script(host: ServerHost, name: String, source: String, done: UnboundedSender<String>) {
+    tokio::task::spawn_blocking(move || {
+        let _done = Done {
+            name: name.clone(),
+            tx: done,
+        };
+        let (service_obj, stop) = (host.service_obj.clone(), host.stop.clone());
+        info!("Running the script {name:?}");
+        match run(host, &source) {
+            Err(_) if stop.load(Ordering::Relaxed) => {
+                info!("The script {name:?} was stopped");
+            }
+            Err(e) => {
+                warn!("The script {name:?} failed: {e}");
+                service_obj.send_info(Info::Warning {
+                    title: format!("Script {name:?} failed"),
+                    msg: e.to_string(),
+                });
+            }
+            Ok(()) => {}
+        }
+    });
+}
+
+/// An engine limited in time and memory, scripts can't load modules or evaluate code
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .on_print(|s| info!("Script: {s}"))
+        .on_debug(|s, _, pos| debug!("Script at {pos}: {s}"));
+    engine
+}
+
+/// Checks the syntax of a script, unknown functions are only noticed when they are called
+pub fn compile(source: &str) -> Result<AST, ParseError> {
+    engine().compile(source)
+}
+
+/// Runs the script with the host functions until it ends or is stopped
+fn run(host: ServerHost, source: &str) -> Result<(), Box<EvalAltResult>> {
+    let stop = host.stop.clone();
+    let host = Rc::new(host);
+    let mut engine = engine();
+    engine.on_progress(move |_| stop.load(Ordering::Relaxed).then_some(Dynamic::UNIT));
+    for (name, arities) in HOST_FUNCTIONS {
+        for &arity in arities {
+            let host = host.clone();
+            let types = vec![TypeId::of::<Dynamic>(); arity];
+            engine.register_raw_fn(name, types, move |_, args| {
+                let args = args.iter().map(|a| (**a).clone()).collect::<Vec<_>>();
+                host.call(name, &args).map_err(Into::into)
+            });
+        }
+    }
+    engine.run(source)
+}
+
+fn publish_state(service_obj: &AServiceImpl, running: &HashMap<String, Arc<AtomicBool>>) {
+    let mut running = running.keys().cloned().collect::<Vec<_>>();
+    running.sort();
+    service_obj
+        .script_state
+        .send_replace(ScriptState { running });
+}
+
+/// The functions available to scripts
+struct ServerHost {
+    service_obj: AServiceImpl,
+    handle: Handle,
+    stop: Arc<AtomicBool>,
+}
+
+fn number(args: &[Dynamic], i: usize) -> Result<f64, String> {
+    args.get(i)
+        .and_then(|a| {
+            a.as_float()
+                .ok()
+                .or_else(|| a.as_int().ok().map(|v| v as f64))
+        })
+        .ok_or_else(|| format!("Argument {} must be a number", i + 1))
+}
+
+fn string(args: &[Dynamic], i: usize) -> Result<String, String> {
+    args.get(i)
+        .filter(|a| a.is_string())
+        .map(Dynamic::to_string)
+        .ok_or_else(|| format!("Argument {} must be a string", i + 1))
+}
+
+/// A missing flag is false
+fn flag(args: &[Dynamic], i: usize) -> Result<bool, String> {
+    args.get(i).map_or(Ok(false), |a| {
+        a.as_bool()
+            .map_err(|_| format!("Argument {} must be a boolean", i + 1))
+    })
+}
+
+fn level(args: &[Dynamic], i: usize) -> Result<f32, String> {
+    Ok(number(args, i)?.clamp(0.0, 1.0) as f32)
+}
+
+fn address(args: &[Dynamic]) -> Result<FixtureAddress, String> {
+    let (universe, channel) = (number(args, 0)?, number(args, 1)?);
+    if !(1.0..=UNIVERSE_SIZE as f64).contains(&channel) {
+        return Err(format!("The channel {channel} is out of range"));
+    }
+    Ok(FixtureAddress::new(
+        universe as u16,
+        UniverseAddress::create(channel as usize),
+    ))
+}
+
+impl ServerHost {
+    fn call(&self, name: &str, args: &[Dynamic]) -> Result<Dynamic, String> {
+        if self.stop.load(Ordering::Relaxed) {
+            return Err("Stopped".into());
+        }
+        self.handle.block_on(self.call_async(name, args))
+    }
+
+    async fn call_async(&self, name: &str, args: &[Dynamic]) -> Result<Dynamic, String> {
+        let s = &self.service_obj;
+        let result = match name {
+            "log" => {
+                let msg = args.iter().map(Dynamic::to_string).collect::<Vec<_>>();
+                info!("Script: {}", msg.join(" "));
+                Ok(())
+            }
+            "wait" => {
+                let seconds = number(args, 0)?.clamp(0.0, MAX_WAIT.as_secs_f64());
+                let deadline =
+                    Instant::now() + Duration::try_from_secs_f64(seconds).unwrap_or(MAX_WAIT);
+                while Instant::now() < deadline {
+                    if self.stop.load(Ordering::Relaxed) {
+                        return Err("Stopped".into());
+                    }
+                    tokio::time::sleep_until(deadline.min(Instant::now() + WAIT_SLICE)).await;
+                }
+                Ok(())
+            }
+            "project_name" => {
+                return Ok(s.project.read().await.metadata.name.clone().into());
+            }
+
+            "channel" => {
+                return self
+                    .channel(address(args)?)
+                    .await
+                    .map(|v| (v as INT).into());
+            }
+            "set_channel" => {
+                let value = number(args, 2)?.clamp(0.0, 255.0) / 255.0;
+                s.execute_control(&ControlAction::Channel(address(args)?), Some(value as f32))
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            "grand_master" => {
+                return Ok((s.masters.borrow().grand_master.take() as FLOAT).into());
+            }
+            "set_grand_master" => {
+                let v = level(args, 0)?;
+                s.update_masters(|m| m.grand_master = SavePercentage::create(v));
+                Ok(())
+            }
+            "blackout" => return Ok(s.masters.borrow().blackout.into()),
+            "set_blackout" => {
+                let on = flag(args, 0)?;
+                s.update_masters(|m| m.blackout = on);
+                Ok(())
+            }
+            "set_group_master" => {
+                let group = self.group(&string(args, 0)?).await?.id;
+                s.set_group_level(group, level(args, 1)?);
+                Ok(())
+            }
+            "set_universe_master" => {
+                s.set_universe_level(number(args, 0)? as u16, level(args, 1)?);
+                Ok(())
+            }
+
+            "group_count" => return Ok((s.project.read().await.groups.len() as INT).into()),
+            "group_name" => {
+                let i = number(args, 0)? as usize;
+                let p = s.project.read().await;
+                return Ok(p
+                    .groups
+                    .get(i)
+                    .map_or(Dynamic::UNIT, |g| g.name.clone().into()));
+            }
+            "select_group" => {
+                let fixtures = self.group(&string(args, 0)?).await?.fixtures;
+                s.select(fixtures).await.map_err(|e| e.to_string())
+            }
+            "set_intensity" => {
+                let intensity =
+                    FixtureAttribute::Intensity(SavePercentage::create(level(args, 0)?));
+                s.set_attribute(intensity).await.map_err(|e| e.to_string())
+            }
+            "clear" => s.clear().await.map_err(|e| e.to_string()),
+
+            "cue_list_count" => {
+                return Ok((s.project.read().await.cue_lists.len() as INT).into());
+            }
+            "cue_list_name" => {
+                let i = number(args, 0)? as usize;
+                let p = s.project.read().await;
+                return Ok(p
+                    .cue_lists
+                    .get(i)
+                    .map_or(Dynamic::UNIT, |l| l.name.clone().into()));
+            }
+            "cue_go" => s
+                .cue_go(self.cue_list(args).await?)
+                .await
+                .map_err(|e| e.to_string()),
+            "cue_back" => s
+                .cue_back(self.cue_list(args).await?)
+                .await
+                .map_err(|e| e.to_string()),
+            "cue_release" => {
+                let list = self.cue_list(args).await?;
+                s.cue_release(list).await.map_err(|e| e.to_string())
+            }
+            "current_cue" => {
+                let list = match self.cue_list(args).await? {
+                    Some(id) => Some(id),
+                    None => s.project.read().await.cue_lists.first().map(|l| l.id),
+                };
+                let current = list.and_then(|l| s.cue_playback.borrow().current(l));
+                return Ok(current.map_or(Dynamic::UNIT, |i| ((i + 1) as INT).into()));
+            }
+
+            "play_recording" => {
+                let looping = flag(args, 1)?;
+                s.play(string(args, 0)?, 1.0, looping)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+            "stop_playback" => s.stop_playback().await.map_err(|e| e.to_string()),
+
+            "bpm" => return Ok((s.tempo.borrow().bpm as FLOAT).into()),
+            "beat" => return Ok((s.tempo.borrow().beat as FLOAT).into()),
+            "tap" => {
+                s.tap();
+                Ok(())
+            }
+            _ => Err("Unknown function".to_string()),
+        };
+        result.map(|_| Dynamic::UNIT)
+    }
+
+    /// The output value of a channel
+    async fn channel(&self, address: FixtureAddress) -> Result<u8, String> {
+        let mut sub = self
+            .service_obj
+            .universe_runtime
+            .subscribe_universe(address.universe());
+        let read = async {
+            loop {
+                if let UniverseUpdate::Entire { values, .. } = sub.recv().await.ok()? {
+                    return Some(values[address.address().take() - 1]);
+                }
+            }
+        };
+        tokio::time::timeout(CHANNEL_READ_TIMEOUT, read)
+            .await
+            .ok()
+            .flatten()
+            .ok_or_else(|| format!("The universe {} is not available", address.universe()))
+    }
+
+    async fn group(&self, name: &str) -> Result<mlc_data::project::group::FixtureGroup, String> {
+        let p = self.service_obj.project.read().await;
+        p.groups
+            .iter()
+            .find(|g| g.name == name)
+            .cloned()
+            .ok_or_else(|| format!("The group {name:?} does not exist"))
+    }
+
+    /// The cue list named by the first argument, the first cue list if it is missing
+    async fn cue_list(&self, args: &[Dynamic]) -> Result<Option<CueListId>, String> {
+        if args.is_empty() {
+            return Ok(None);
+        }
+        let name = string(args, 0)?;
+        let p = self.service_obj.project.read().await;
+        p.cue_lists
+            .iter()
+            .find(|l| l.name == name)
+            .map(|l| Some(l.id))
+            .ok_or_else(|| format!("The cue list {name:?} does not exist"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Done, ServerHost, compile, run};
+    use crate::project::create_default_project;
+    use crate::test_util::service_obj;
+    use mlc_data::project::cue::CueList;
+    use mlc_data::project::group::FixtureGroup;
+    use mlc_data::uuid::Uuid;
+    use rhai::{Dynamic, FLOAT, INT};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+    use tokio::runtime::Handle;
+
+    fn test_host() -> ServerHost {
+        let mut project = create_default_project();
+        project.metadata.name = "Show".to_string();
+        project.groups.push(FixtureGroup {
+            id: Uuid::new_v4(),
+            name: "Front".to_string(),
+            fixtures: vec![],
+        });
+        project.cue_lists.push(CueList::new("Main"));
+        ServerHost {
+            service_obj: service_obj(project).0,
+            handle: Handle::current(),
+            stop: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    #[tokio::test]
+    async fn host_functions() {
+        let host = test_host();
+        let call = |name: &'static str, args: Vec<Dynamic>| {
+            let host = &host;
+            async move { host.call_async(name, &args).await }
+        };
+
+        let name = call("project_name", vec![]).await.unwrap();
+        assert_eq!(name.into_string().unwrap(), "Show");
+
+        assert!(
+            call("set_grand_master", vec![(0.5 as FLOAT).into()])
+                .await
+                .is_ok()
+        );
+        let level = call("grand_master", vec![]).await.unwrap();
+        assert!((level.as_float().unwrap() - 0.5).abs() < 0.01);
+        // Integers are numbers too and levels are clamped
+        assert!(
+            call("set_grand_master", vec![(3 as INT).into()])
+                .await
+                .is_ok()
+        );
+        assert!(host.service_obj.masters.borrow().grand_master.take() > 0.99);
+        assert_eq!(
+            call("set_grand_master", vec!["full".into()])
+                .await
+                .unwrap_err(),
+            "Argument 1 must be a number"
+        );
+
+        assert!(call("set_blackout", vec![true.into()]).await.is_ok());
+        assert!(call("blackout", vec![]).await.unwrap().as_bool().unwrap());
+        assert!(call("set_blackout", vec![(1 as INT).into()]).await.is_err());
+
+        let count = call("group_count", vec![]).await.unwrap();
+        assert_eq!(count.as_int(), Ok(1));
+        let group = call("group_name", vec![(0 as INT).into()]).await.unwrap();
+        assert_eq!(group.into_string().unwrap(), "Front");
+        assert!(
+            call("group_name", vec![(1 as INT).into()])
+                .await
+                .unwrap()
+                .is_unit()
+        );
+        assert_eq!(
+            call("select_group", vec!["Back".into()]).await.unwrap_err(),
+            "The group \"Back\" does not exist"
+        );
+
+        let lists = call("cue_list_count", vec![]).await.unwrap();
+        assert_eq!(lists.as_int(), Ok(1));
+        assert!(
+            call("current_cue", vec!["Main".into()])
+                .await
+                .unwrap()
+                .is_unit()
+        );
+        assert!(call("cue_go", vec!["Other".into()]).await.is_err());
+
+        assert!(
+            call("set_channel", vec![1.into(), 513.into(), 255.into()])
+                .await
+                .is_err()
+        );
+        assert_eq!(call("fog", vec![]).await.unwrap_err(), "Unknown function");
+
+        host.stop.store(true, Ordering::Relaxed);
+        assert_eq!(
+            call("wait", vec![(10 as INT).into()]).await.unwrap_err(),
+            "Stopped"
+        );
+        // Waits beyond the limit are capped instead of overflowing
+        for seconds in [1e30, FLOAT::INFINITY, FLOAT::NAN] {
+            let waited = call("wait", vec![seconds.into()]).await;
+            assert_eq!(waited.unwrap_err(), "Stopped", "{seconds}");
+        }
+        assert!(call("wait", vec![(-1.0 as FLOAT).into()]).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn done_on_panic() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let task = tokio::task::spawn_blocking(move || {
+            let _done = Done {
+                name: "Show".to_string(),
+                tx,
+            };
+            panic!("A host function failed");
+        });
+        assert!(task.await.is_err());
+        assert_eq!(rx.recv().await.as_deref(), Some("Show"));
+    }
+
+    /// Runs the script on a blocking thread like the runner does
+    async fn run_script(host: ServerHost, script: &'static str) -> Result<(), String> {
+        tokio::task::spawn_blocking(move || run(host, script).map_err(|e| e.to_string()))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn run_scripts() {
+        let script = r#"
+            // Dim to a quarter when the first group is the front
+            if group_name(0) == "Front" {
+                set_grand_master(1.0 / 4);
+            }
+            let total = 0;
+            for i in 0..group_count() + 2 {
+                total += i;
+            }
+            set_blackout(total == 3);
+        "#;
+        let host = test_host();
+        let service_obj = host.service_obj.clone();
+        run_script(host, script).await.unwrap();
+        let masters = service_obj.masters.borrow().clone();
+        assert!((masters.grand_master.take() - 0.25).abs() < 0.01);
+        assert!(masters.blackout);
+
+        let error = async |script| run_script(test_host(), script).await.unwrap_err();
+        assert!(error("fog();").await.contains("fog"));
+        assert!(error("eval(\"1\");").await.contains("eval"));
+        assert!(error("import \"show\" as show;").await.contains("show"));
+        assert!(
+            error("grand_master(1, 2, 3);")
+                .await
+                .contains("grand_master")
+        );
+        assert!(
+            error("let s = \"x\"; loop { s += s; }")
+                .await
+                .contains("too large")
+        );
+
+        // Endless scripts are stopped by the operation limit or when they are stopped
+        let host = test_host();
+        let stop = host.stop.clone();
+        let script = tokio::spawn(run_script(host, "loop { wait(0); }"));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop.store(true, Ordering::Relaxed);
+        assert!(script.await.unwrap().is_err());
+        assert!(error("loop { }").await.contains("Too many operations"));
+
+        assert!(compile("let x = ;").is_err());
+        assert!(compile("cue_go(\"Main\");").is_ok());
+    }
+}
@@ -2,9 +2,16 @@ use std::net::Ipv4Addr;
 
 use crate::misc::{ShutdownHandler, ShutdownPhase};
 use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, DEFAULT_SERVER_PORT};
+use mlc_communication::services::audio::AudioServiceIdent;
+use mlc_communication::services::endpoints::EndpointServiceIdent;
 use mlc_communication::services::general::GeneralServiceIdent;
+use mlc_communication::services::midi::MidiServiceIdent;
+use mlc_communication::services::programmer::ProgrammerServiceIdent;
 use mlc_communication::services::project::ProjectServiceIdent;
 use mlc_communication::services::project_selection::ProjectSelectionServiceIdent;
+use mlc_communication::services::recorder::RecorderServiceIdent;
+use mlc_communication::services::schedule::ScheduleServiceIdent;
+use mlc_communication::services::script::ScriptServiceIdent;
 use mlc_communication::{ServiceIdentifiable, ServiceIdentifiableServer};
 use tokio::io::AsyncReadExt;
 use tokio::net::TcpListener;
@@ -91,6 +98,27 @@ fn handle_connection(
             ProjectServiceIdent::IDENT => {
                 ProjectServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
             }
+            ProgrammerServiceIdent::IDENT => {
+                ProgrammerServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
+            EndpointServiceIdent::IDENT => {
+                EndpointServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
+            RecorderServiceIdent::IDENT => {
+                RecorderServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
+            MidiServiceIdent::IDENT => {
+                MidiServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
+            AudioServiceIdent::IDENT => {
+                AudioServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
+            ScheduleServiceIdent::IDENT => {
+                ScheduleServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
+            ScriptServiceIdent::IDENT => {
+                ScriptServiceIdent::spinup(service_obj, socket_rx, socket_tx).await
+            }
             _ => {
                 error!("Identifier was not valid!");
                 return;
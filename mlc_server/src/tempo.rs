@@ -0,0 +1,189 @@
+use crate::misc::{ShutdownHandler, ShutdownPhase};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple, ServiceImpl};
+use mlc_communication::services::general::Tempo;
+use mlc_data::tempo::{BeatClock, TapTempo};
+use std::time::{Duration, Instant};
+use tokio::select;
+use tokio::time::MissedTickBehavior;
+use tracing::warn;
+
+/// The beat counter is updated at this rate
+const TICK: Duration = Duration::from_millis(5);
+
+pub struct TempoClock {
+    clock: BeatClock,
+    taps: TapTempo,
+}
+
+impl TempoClock {
+    pub fn new(tempo: &Tempo) -> Self {
+        Self {
+            clock: BeatClock::new(tempo.bpm, Instant::now()),
+            taps: TapTempo::default(),
+        }
+    }
+}
+
+impl ServiceImpl {
+    /// Changes the tempo clock and broadcasts the result
+    pub fn update_tempo(&self, f: impl FnOnce(&mut BeatClock, Instant)) {
+        let now = Instant::now();
+        let clock = {
+            let mut tempo_clock = self.tempo_clock.lock().unwrap();
+            f(&mut tempo_clock.clock, now);
+            tempo_clock.clock
+        };
+        self.publish_tempo(&clock, now);
+    }
+
+    pub fn tap(&self) {
+        let now = Instant::now();
+        let clock = {
+            let mut tempo_clock = self.tempo_clock.lock().unwrap();
+            if let Some(bpm) = tempo_clock.taps.tap(now) {
+                tempo_clock.clock.set_bpm(bpm, now);
+            }
+            tempo_clock.clock.reset_phase(now);
+            tempo_clock.clock
+        };
+        self.publish_tempo(&clock, now);
+    }
+
+    fn publish_tempo(&self, clock: &BeatClock, now: Instant) {
+        let tempo = Tempo {
+            bpm: clock.bpm(),
+            multiplier: clock.multiplier(),
+            beat: clock.beats(now) as u64,
+            ..self.tempo.borrow().clone()
+        };
+        if *self.tempo.borrow() != tempo {
+            self.tempo.send_replace(tempo);
+        }
+    }
+}
+
+pub struct TempoService;
+
+impl MlcServiceSimple for TempoService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_tempo(res.service_obj.clone(), res.shutdown.clone())
+    }
+}
+
+/// Counts the beats, follows the audio analysis if enabled and steps the cue lists on the beat
+async fn run_tempo(service_obj: AServiceImpl, shutdown_handler: ShutdownHandler) {
+    let mut interval = tokio::time::interval(TICK);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut audio = service_obj.audio_state.subscribe();
+    let mut last_beat = service_obj.tempo.borrow().beat;
+
+    loop {
+        select! {
+            _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                break;
+            }
+            Ok(()) = audio.changed() => {
+                let Ok(levels) = audio.borrow_and_update().map(|s| s.levels) else {
+                    continue;
+                };
+                if service_obj.tempo.borrow().follow_audio && levels.beat {
+                    service_obj.update_tempo(|clock, now| {
+                        if let Some(bpm) = levels.bpm {
+                            clock.set_bpm(bpm, now);
+                        }
+                        clock.reset_phase(now);
+                    });
+                }
+            }
+            _ = interval.tick() => {
+                service_obj.update_tempo(|_, _| {});
+                let beat = service_obj.tempo.borrow().beat;
+                if beat > last_beat {
+                    step_on_beat(&service_obj, beat).await;
+                }
+                last_beat = beat;
+            }
+        }
+    }
+}
+
+/// Advances the running cue lists that step on this beat, wrapping around after the last cue
+async fn step_on_beat(service_obj: &AServiceImpl, beat: u64) {
+    let lists = {
+        let p = service_obj.project.read().await;
+        let playback = service_obj.cue_playback.borrow();
+        p.cue_lists
+            .iter()
+            .filter(|l| l.steps_on(beat) && playback.current(l.id).is_some())
+            .map(|l| l.id)
+            .collect::<Vec<_>>()
+    };
+
+    for list in lists {
+        let result = service_obj
+            .step_cue_list(Some(list), |current, len| current.map(|i| (i + 1) % len))
+            .await;
+        if let Err(e) = result {
+            warn!("Stepping cue list {list} on the beat failed: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::step_on_beat;
+    use crate::project::create_default_project;
+    use crate::test_util::service_obj;
+    use mlc_communication::services::general::{GeneralService, TempoError};
+    use mlc_communication::services::programmer::{ProgrammerService, ProgrammerServiceError};
+    use mlc_data::project::cue::{Cue, CueList};
+    use mlc_data::uuid::Uuid;
+
+    #[tokio::test]
+    async fn step_cue_lists_on_the_beat() {
+        let mut project = create_default_project();
+        let mut chase = CueList::new("Chase");
+        for name in ["1", "2", "3"] {
+            chase.cues.push(Cue {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                values: vec![],
+                timecode: None,
+            });
+        }
+        let (list, mut manual) = (chase.id, chase.clone());
+        manual.id = Uuid::new_v4();
+        project.cue_lists = vec![chase, manual.clone()];
+        let (service_obj, _) = service_obj(project);
+
+        assert!(matches!(
+            service_obj.cue_set_beat_step(list, Some(0)).await,
+            Err(ProgrammerServiceError::InvalidBeatStep)
+        ));
+        service_obj.cue_set_beat_step(list, Some(2)).await.unwrap();
+        let current = |list| service_obj.cue_playback.borrow().current(list);
+
+        // Lists only step while running
+        step_on_beat(&service_obj, 2).await;
+        assert_eq!(current(list), None);
+
+        service_obj.cue_go(Some(list)).await.unwrap();
+        service_obj.cue_go(Some(manual.id)).await.unwrap();
+        step_on_beat(&service_obj, 3).await;
+        assert_eq!(current(list), Some(0));
+        step_on_beat(&service_obj, 4).await;
+        assert_eq!(current(list), Some(1));
+        step_on_beat(&service_obj, 6).await;
+        step_on_beat(&service_obj, 8).await;
+        assert_eq!(current(list), Some(0));
+        assert_eq!(current(manual.id), Some(0));
+
+        assert!(matches!(
+            service_obj.set_bpm(f32::NAN).await,
+            Err(TempoError::InvalidBpm(_))
+        ));
+        assert!(service_obj.set_bpm(f32::INFINITY).await.is_err());
+        service_obj.set_bpm(90.0).await.unwrap();
+        assert_eq!(service_obj.tempo.borrow().bpm, 90.0);
+    }
+}
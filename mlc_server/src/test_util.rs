@@ -0,0 +1,153 @@
+//! Service objects for tests, without the server and the services around them.
+
+use crate::endpoints::SimulatorStore;
+use crate::misc::{AdaptNotifier, ShutdownHandler};
+use crate::project::Project;
+use crate::project::history::History;
+use crate::tempo::TempoClock;
+use crate::universe::UniverseRuntimeService;
+use crate::{AServiceImpl, ServiceImpl};
+use mlc_communication::remoc::rch;
+use mlc_communication::services::general::{Info, Tempo};
+use mlc_data::fixture::blueprint::entities::Brightness;
+use mlc_data::fixture::blueprint::units::Percentage;
+use mlc_data::fixture::blueprint::{
+    Capability, CapabilityKind, Channel, CommonChannel, FixtureBlueprint, Metadata, Mode,
+    Physical, PixelIdentifier,
+};
+use mlc_data::fixture::patched::{PatchedFixture, PatchedFixtureId};
+use mlc_data::project::universe::{UniverseId, UniverseSlot};
+use mlc_data::uuid::Uuid;
+use mlc_data::{MaybeLinear, SavePercentage};
+use mlc_ofl::OflLibrary;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+use tokio::sync::mpsc::unbounded_channel;
+
+/// A service object with the project opened, the commands to the other services are dropped and
+/// the runtime is returned to be started by the test if needed
+pub fn service_obj(project: Project) -> (AServiceImpl, UniverseRuntimeService) {
+    let (runtime_service, runtime) = UniverseRuntimeService::create();
+    let service_obj = Arc::new(ServiceImpl {
+        project: Arc::new(RwLock::new(project)),
+        valid_project: RwLock::new(true),
+        info: rch::watch::channel(Info::Idle).0,
+        status: rch::watch::channel(String::new()).0,
+        programmer: rch::watch::channel(Default::default()).0,
+        cue_playback: rch::watch::channel(Default::default()).0,
+        masters: rch::watch::channel(Default::default()).0,
+        endpoint_stats: rch::watch::channel(vec![]).0,
+        simulator: SimulatorStore::default(),
+        recorder_state: rch::watch::channel(Default::default()).0,
+        recorder: unbounded_channel().0,
+        midi_state: rch::watch::channel(Default::default()).0,
+        timecode_state: rch::watch::channel(Default::default()).0,
+        timecode: unbounded_channel().0,
+        audio_state: rch::watch::channel(Default::default()).0,
+        tempo: rch::watch::channel(Tempo::default()).0,
+        tempo_clock: Mutex::new(TempoClock::new(&Tempo::default())),
+        schedule_state: rch::watch::channel(Default::default()).0,
+        schedule: unbounded_channel().0,
+        script_state: rch::watch::channel(Default::default()).0,
+        scripts: unbounded_channel().0,
+        history: Mutex::new(History::default()),
+        history_state: rch::watch::channel(Default::default()).0,
+        adapt_notifier: AdaptNotifier::create(),
+        ofl_library: OflLibrary::create(std::env::temp_dir().join("mlc_test_ofl.json")),
+        universe_runtime: Arc::new(runtime),
+        shutdown: ShutdownHandler::create(),
+    });
+    (service_obj, runtime_service)
+}
+
+pub fn dimmer() -> CapabilityKind {
+    CapabilityKind::Intensity {
+        brightness: MaybeLinear::Constant(Brightness::Percent(Percentage(1.0))),
+    }
+}
+
+/// A fixture with one single byte channel per entry, the capabilities of a channel split its range
+/// evenly
+pub fn fixture(channels: Vec<(&str, Vec<CapabilityKind>)>) -> PatchedFixture {
+    let names = channels
+        .iter()
+        .map(|(name, _)| Some(name.to_string()))
+        .collect();
+    let channels = channels
+        .into_iter()
+        .map(|(name, kinds)| {
+            let step = 1.0 / kinds.len() as f32;
+            let capabilities = kinds
+                .into_iter()
+                .enumerate()
+                .map(|(i, kind)| Capability {
+                    range: SavePercentage::create(i as f32 * step)
+                        ..=SavePercentage::create((i + 1) as f32 * step),
+                    pixel: PixelIdentifier::Master,
+                    comment: None,
+                    kind,
+                })
+                .collect();
+            let channel = CommonChannel {
+                default_value: SavePercentage::create(0.0),
+                capabilities,
+            };
+            (name.to_string(), Channel::Single { channel })
+        })
+        .collect::<HashMap<_, _>>();
+
+    PatchedFixture {
+        id: Uuid::new_v4(),
+        identifier: "Test".to_string(),
+        config: FixtureBlueprint {
+            meta: Metadata {
+                name: "Test".to_string(),
+                identifier: "test".to_string(),
+                manufacturer: "Test".to_string(),
+                physical: Physical {
+                    dimensions: None,
+                    weight: 0.0,
+                    power_consumption: 0.0,
+                    power_connectors: String::new(),
+                    dmx_connector: String::new(),
+                    bulb: String::new(),
+                    lens: String::new(),
+                },
+            },
+            channels,
+            modes: vec![Mode {
+                name: "Default".to_string(),
+                channels: names,
+            }],
+            matrix: None,
+            wheels: None,
+        },
+        mode_index: 0,
+        features: vec![],
+        matrix_features: None,
+        output: Default::default(),
+    }
+}
+
+/// Patches the fixture at the address of the universe
+pub fn patch(
+    project: &mut Project,
+    universe: UniverseId,
+    address: usize,
+    fixture: PatchedFixture,
+) -> PatchedFixtureId {
+    let universe = project
+        .universes
+        .iter_mut()
+        .find(|u| u.id == universe)
+        .expect("Unknown universe");
+    let id = fixture.id;
+    let len = fixture.config.modes[fixture.mode_index].channels.len();
+    universe.addresses[address - 1] = UniverseSlot::Fixture(id);
+    for slot in &mut universe.addresses[address..address - 1 + len] {
+        *slot = UniverseSlot::Consecutive;
+    }
+    universe.fixtures.insert(id, fixture);
+    id
+}
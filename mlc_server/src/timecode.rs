@@ -0,0 +1,240 @@
+use crate::audio::{InputReader, InputThread};
+use crate::misc::{AdaptNotifier, AdaptScopes, ShutdownHandler, ShutdownPhase};
+use crate::{AServiceImpl, MlcServiceResources, MlcServiceSimple};
+use mlc_communication::services::programmer::{
+    ProgrammerService, ProgrammerServiceError, TimecodeState,
+};
+use mlc_data::project::audio::AudioInput;
+use mlc_data::project::cue::CueListId;
+use mlc_data::project::timecode::TimecodeSettings;
+use mlc_data::timecode::ltc::LtcDecoder;
+use mlc_data::timecode::{JitterFilter, MscCommand, MscMessage, Timecode, TimecodeChase};
+use std::time::{Duration, Instant};
+use tokio::select;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::time::MissedTickBehavior;
+use tracing::warn;
+
+/// The cue list clock is evaluated at this rate
+const TICK: Duration = Duration::from_millis(25);
+
+pub enum TimecodeEvent {
+    /// A timecode with its smoothed position received at the instant
+    Timecode {
+        timecode: Timecode,
+        position: Duration,
+        at: Instant,
+    },
+    Msc(MscMessage),
+}
+
+pub struct TimecodeChaseService {
+    tx: UnboundedSender<TimecodeEvent>,
+    rx: UnboundedReceiver<TimecodeEvent>,
+}
+
+impl TimecodeChaseService {
+    pub fn create() -> (Self, UnboundedSender<TimecodeEvent>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (Self { tx: tx.clone(), rx }, tx)
+    }
+}
+
+impl MlcServiceSimple for TimecodeChaseService {
+    fn start(self, res: &MlcServiceResources) -> impl Future<Output = ()> + Send + 'static {
+        run_chase(
+            res.service_obj.clone(),
+            res.shutdown.clone(),
+            res.adapt_notifier.clone(),
+            self.tx,
+            self.rx,
+        )
+    }
+}
+
+async fn run_chase(
+    service_obj: AServiceImpl,
+    shutdown_handler: ShutdownHandler,
+    adapt_notifier: AdaptNotifier,
+    tx: UnboundedSender<TimecodeEvent>,
+    mut rx: UnboundedReceiver<TimecodeEvent>,
+) {
+    let mut interval = tokio::time::interval(TICK);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    'o: loop {
+        let settings = service_obj.project.read().await.timecode.clone();
+        let mut chase = TimecodeChase::new(settings.offset_ms, settings.freewheel);
+        // The cue last set by the chase, manual changes are kept until the next timecode cue
+        let mut applied = None;
+        let _ltc = settings
+            .ltc
+            .clone()
+            .map(|input| read_ltc(input, tx.clone()));
+        service_obj.timecode_state.send_modify(|s| {
+            s.timecode = None;
+            s.position = None;
+            s.freewheeling = false;
+        });
+
+        loop {
+            select! {
+                _ = shutdown_handler.wait(ShutdownPhase::Phase1) => {
+                    break 'o;
+                }
+                _ = adapt_notifier.wait(AdaptScopes::CONTROL | AdaptScopes::PROJECT) => {
+                    break;
+                }
+                Some(event) = rx.recv() => match event {
+                    TimecodeEvent::Timecode { timecode, position, at } => {
+                        chase.sync(timecode, position, at);
+                    }
+                    TimecodeEvent::Msc(message) => {
+                        if settings.msc
+                            && message.addresses(settings.msc_device_id)
+                            && let Err(e) = execute_msc(&service_obj, &message).await
+                        {
+                            warn!("MSC {:?} failed: {e}", message.command);
+                        }
+                    }
+                },
+                _ = interval.tick() => {
+                    let now = Instant::now();
+                    let position = chase.position(now);
+                    if settings.chase && let Some(position) = position {
+                        chase_cue_list(&service_obj, &settings, position, &mut applied).await;
+                    }
+
+                    let state = TimecodeState {
+                        timecode: chase.timecode(),
+                        position,
+                        freewheeling: chase.freewheeling(now),
+                        stopped: service_obj.timecode_state.borrow().stopped.clone(),
+                    };
+                    if *service_obj.timecode_state.borrow() != state {
+                        service_obj.timecode_state.send_replace(state);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn read_ltc(input: AudioInput, tx: UnboundedSender<TimecodeEvent>) -> InputThread {
+    InputThread::spawn(input, move |sample_rate| {
+        let mut decoder = LtcDecoder::new(sample_rate);
+        let mut filter = JitterFilter::default();
+        move |reader: &InputReader, samples: &[f32]| {
+            decoder.push(samples).into_iter().all(|frame| {
+                let local = Duration::from_secs_f64(frame.sample as f64 / sample_rate as f64);
+                let event = TimecodeEvent::Timecode {
+                    timecode: frame.timecode,
+                    position: filter.update(frame.timecode.to_duration(), local),
+                    at: reader.instant(frame.sample),
+                };
+                tx.send(event).is_ok()
+            })
+        }
+    })
+}
+
+async fn chase_cue_list(
+    service_obj: &AServiceImpl,
+    settings: &TimecodeSettings,
+    position: Duration,
+    applied: &mut Option<(CueListId, Option<usize>)>,
+) {
+    let (list, cue) = {
+        let p = service_obj.project.read().await;
+        let list = match settings.cue_list {
+            Some(id) => p.cue_lists.iter().find(|l| l.id == id),
+            None => p.cue_lists.first(),
+        };
+        let Some(list) = list else {
+            return;
+        };
+        (list.id, list.cue_at(position))
+    };
+
+    if *applied == Some((list, cue)) || service_obj.timecode_state.borrow().stopped.contains(&list)
+    {
+        return;
+    }
+    *applied = Some((list, cue));
+
+    let result = service_obj
+        .step_cue_list(Some(list), move |_, len| cue.map(|i| i.min(len - 1)))
+        .await;
+    if let Err(e) = result {
+        warn!("Chasing timecode failed: {e}");
+    }
+}
+
+/// Cue lists are addressed by name or number, cues by their number in the list
+async fn execute_msc(
+    service_obj: &AServiceImpl,
+    message: &MscMessage,
+) -> Result<(), ProgrammerServiceError> {
+    let lists = service_obj
+        .project
+        .read()
+        .await
+        .cue_lists
+        .iter()
+        .map(|l| (l.id, l.name.clone()))
+        .collect::<Vec<_>>();
+
+    let list = match &message.list {
+        Some(address) => {
+            let found = lists
+                .iter()
+                .position(|(_, name)| name == address)
+                .or_else(|| {
+                    address
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|n| n.checked_sub(1))
+                        .filter(|i| *i < lists.len())
+                });
+            match found {
+                Some(i) => Some(lists[i].0),
+                None => {
+                    warn!("MSC addresses the unknown cue list {address}");
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+
+    match message.command {
+        MscCommand::Go => {
+            let number = message.cue.as_deref().and_then(|c| c.parse::<usize>().ok());
+            match number.and_then(|n| n.checked_sub(1)) {
+                Some(i) => {
+                    service_obj
+                        .step_cue_list(list, move |_, len| Some(i.min(len - 1)))
+                        .await
+                }
+                None => service_obj.cue_go(list).await,
+            }
+        }
+        MscCommand::Stop => {
+            let stopped =
+                list.map_or_else(|| lists.iter().map(|(id, _)| *id).collect(), |l| vec![l]);
+            service_obj.timecode_state.send_modify(|s| {
+                s.stopped.retain(|l| !stopped.contains(l));
+                s.stopped.extend(stopped);
+            });
+            Ok(())
+        }
+        MscCommand::Resume => {
+            service_obj.timecode_state.send_modify(|s| match list {
+                Some(list) => s.stopped.retain(|l| *l != list),
+                None => s.stopped.clear(),
+            });
+            Ok(())
+        }
+        MscCommand::Other(_) => Ok(()),
+    }
+}
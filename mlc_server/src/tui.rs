@@ -1,6 +1,9 @@
 use crate::misc::{ShutdownHandler, ShutdownPhase};
 use crate::{AServiceImpl, MlcService, MlcServiceResources};
 use ansi_to_tui::IntoText;
+use mlc_communication::services::endpoints::EndpointStats;
+use mlc_communication::services::general::{Masters, Tempo};
+use mlc_data::SavePercentage;
 use circular_buffer::CircularBuffer;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Alignment;
@@ -183,7 +186,12 @@ impl TuiApp {
 
     async fn update_meta_information(&mut self) {
         if self.service_obj.project_valid().await {
-            self.tui_state.meta_information = Some(MetaInformation {});
+            self.tui_state.meta_information = Some(MetaInformation {
+                name: self.service_obj.project.read().await.metadata.name.clone(),
+                masters: self.service_obj.masters.borrow().clone(),
+                tempo: self.service_obj.tempo.borrow().clone(),
+                endpoints: self.service_obj.endpoint_stats.borrow().clone(),
+            });
         } else {
             self.tui_state.meta_information = None;
         }
@@ -234,6 +242,22 @@ impl TuiApp {
             KeyCode::Char('n') if self.tui_state.exit == ExitState::UserConfirm => {
                 self.tui_state.exit = ExitState::Idle;
             }
+            KeyCode::Char('t') => {
+                self.service_obj.tap();
+            }
+            KeyCode::Char('b') => {
+                self.service_obj.update_masters(|m| m.blackout = !m.blackout);
+            }
+            KeyCode::Char('+') => {
+                self.service_obj.update_masters(|m| {
+                    m.grand_master = SavePercentage::create(m.grand_master.take() + 0.05)
+                });
+            }
+            KeyCode::Char('-') => {
+                self.service_obj.update_masters(|m| {
+                    m.grand_master = SavePercentage::create(m.grand_master.take() - 0.05)
+                });
+            }
             KeyCode::Up => {
                 self.tui_state.log_state.scroll = self.tui_state.log_state.scroll.saturating_sub(1);
                 self.tui_state.log_state.scroll_state = self
@@ -276,7 +300,11 @@ impl StatefulWidget for MainWidget {
         let main_block = Block::bordered()
             .title(title.centered().bold())
             .border_set(border::ROUNDED)
-            .title_bottom(Line::from("Ctrl + C to exit".underlined()))
+            .title_bottom(Line::from(vec![
+                "Ctrl + C to exit".underlined(),
+                " | ".into(),
+                "b: blackout, +/-: grand master, t: tap tempo".underlined(),
+            ]))
             .border_type(ratatui::widgets::BorderType::Thick);
         let a2 = main_block.inner(area);
         main_block.render(area, buf);
@@ -288,18 +316,52 @@ impl StatefulWidget for MainWidget {
         )
         .split(area);
 
+        let top = Layout::new(
+            Direction::Horizontal,
+            [Constraint::Fill(1), Constraint::Fill(2)],
+        )
+        .split(layout[0]);
+
         let meta_block = Block::bordered().title("Meta").border_set(border::ROUNDED);
+        let endpoints_block = Block::bordered()
+            .title("Endpoints")
+            .border_set(border::ROUNDED);
 
         match &state.meta_information {
-            Some(_meta) => Paragraph::new("MetaInformation tui not implemented")
-                .block(meta_block)
-                .render(layout[0], buf),
+            Some(meta) => Paragraph::new(vec![
+                Line::from(vec!["Project: ".bold(), meta.name.clone().into()]),
+                Line::from(vec![
+                    "Grand Master: ".bold(),
+                    format!("{:.0}%", meta.masters.grand_master.take() * 100.0).into(),
+                    "  Blackout: ".bold(),
+                    if meta.masters.blackout {
+                        "ON".red().bold()
+                    } else {
+                        "OFF".green()
+                    },
+                ]),
+                Line::from(vec![
+                    "Tempo: ".bold(),
+                    format!("{:.1} BPM x{}", meta.tempo.bpm, meta.tempo.multiplier).into(),
+                ]),
+            ])
+            .block(meta_block)
+            .render(top[0], buf),
             None => Paragraph::new("No Project is currently loaded")
                 .alignment(Alignment::Center)
                 .block(meta_block)
-                .render(layout[0], buf),
+                .render(top[0], buf),
         }
 
+        let endpoints = state
+            .meta_information
+            .as_ref()
+            .map(|m| m.endpoints.iter().map(endpoint_line).collect::<Vec<_>>())
+            .unwrap_or_default();
+        Paragraph::new(endpoints)
+            .block(endpoints_block)
+            .render(top[1], buf);
+
         LogWidget.render(layout[1], buf, &mut state.log_state);
 
         if matches!(state.exit, ExitState::UserConfirm) {
@@ -331,6 +393,27 @@ enum ExitState {
     Quit,
 }
 
+fn endpoint_line(stats: &EndpointStats) -> Line<'static> {
+    let fps = match stats.target_fps {
+        Some(target) => format!("{:.1}/{:.1} fps", stats.fps, target),
+        None => format!("{:.1} fps", stats.fps),
+    };
+
+    let mut line = Line::from(vec![
+        format!("U{} ", stats.universe).bold(),
+        stats.endpoint.to_string().into(),
+        format!("  {} packets  ", stats.packets_sent).into(),
+        fps.into(),
+    ]);
+    if stats.send_errors > 0 {
+        line.push_span(format!("  {} errors", stats.send_errors).red());
+    }
+    if let Some(e) = &stats.last_error {
+        line.push_span(format!("  ({e})").red());
+    }
+    line
+}
+
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
     let horizontal = Layout::horizontal([Constraint::Percentage(percent_x)]).flex(Flex::Center);
@@ -340,4 +423,9 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
 }
 
 #[derive(Debug)]
-struct MetaInformation {}
+struct MetaInformation {
+    name: String,
+    masters: Masters,
+    tempo: Tempo,
+    endpoints: Vec<EndpointStats>,
+}
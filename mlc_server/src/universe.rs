@@ -1,3 +1,4 @@
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 use crate::misc::{ShutdownHandler, ShutdownPhase};
@@ -6,9 +7,12 @@ use crate::{
     MlcServiceResources,
     MlcServiceSimple,
 };
+use mlc_communication::services::general::Masters;
 use mlc_data::{
+    fixture::patched::{output::OutputProcessor, PatchedFixtureId},
     misc::ErrIgnore,
-    project::universe::{FixtureAddress, UniverseId, UNIVERSE_SIZE},
+    project::group::GroupId,
+    project::universe::{FixtureAddress, UniverseAddress, UniverseId, UNIVERSE_SIZE},
 };
 use tokio::{
     select,
@@ -22,21 +26,25 @@ use tracing::{info, trace};
 
 pub struct UniverseRuntimeService {
     update_notifier: Sender<UniverseUpdate>,
+    raw_notifier: Sender<UniverseUpdate>,
     cmd_recv: tokio::sync::mpsc::UnboundedReceiver<RuntimeCommand>,
 }
 
 impl UniverseRuntimeService {
     pub fn create() -> (Self, UniverseRuntimeController) {
         let (update_tx, _update_rx) = tokio::sync::broadcast::channel(32);
+        let (raw_tx, _raw_rx) = tokio::sync::broadcast::channel(32);
         let (cmd_tx, cmd_rx) = tokio::sync::mpsc::unbounded_channel();
 
         (
             Self {
                 update_notifier: update_tx.clone(),
+                raw_notifier: raw_tx.clone(),
                 cmd_recv: cmd_rx,
             },
             UniverseRuntimeController {
                 update_subscriber: update_tx,
+                raw_subscriber: raw_tx,
                 cmd_sender: cmd_tx,
             },
         )
@@ -48,7 +56,14 @@ impl MlcServiceSimple for UniverseRuntimeService {
         let runtime = UniverseRuntime {
             cmd_recv: self.cmd_recv,
             update_notifier: self.update_notifier,
-            runtime_universes: vec![],
+            raw_notifier: self.raw_notifier,
+            runtime_universes: BTreeMap::new(),
+            layers: BTreeMap::new(),
+            raw_universes: BTreeMap::new(),
+            output_universes: BTreeMap::new(),
+            masters: Masters::default(),
+            patch: PatchInfo::default(),
+            parked: HashMap::new(),
             project: res.service_obj.project.clone(),
         };
 
@@ -58,14 +73,37 @@ impl MlcServiceSimple for UniverseRuntimeService {
 
 #[derive(Debug)]
 pub struct UniverseRuntime {
-    runtime_universes: Vec<[u8; UNIVERSE_SIZE]>,
+    /// Values set directly on channels, the lowest layer
+    runtime_universes: BTreeMap<UniverseId, [u8; UNIVERSE_SIZE]>,
+    layers: BTreeMap<RuntimeLayer, HashMap<UniverseId, Box<LayerFrame>>>,
+    /// The composed values before output processing, last sent to the raw subscribers
+    raw_universes: BTreeMap<UniverseId, [u8; UNIVERSE_SIZE]>,
+    /// The processed values last sent to the subscribers
+    output_universes: BTreeMap<UniverseId, [u8; UNIVERSE_SIZE]>,
+    masters: Masters,
+    patch: PatchInfo,
+    /// Channels overriding the output regardless of all layers, masters and output processing,
+    /// (0 based) index and value
+    parked: HashMap<UniverseId, Vec<(usize, u8)>>,
     update_notifier: Sender<UniverseUpdate>,
+    raw_notifier: Sender<UniverseUpdate>,
     cmd_recv: tokio::sync::mpsc::UnboundedReceiver<RuntimeCommand>,
     project: Arc<RwLock<Project>>,
 }
 
+/// Information derived from the patch, recomputed whenever it changes
+#[derive(Debug, Default)]
+struct PatchInfo {
+    /// Absolute (0 based) indices of every intensity channel, coarse first, with its fixture
+    intensity: HashMap<UniverseId, Vec<(PatchedFixtureId, Vec<usize>)>>,
+    groups: HashMap<PatchedFixtureId, Vec<GroupId>>,
+    /// Output processing of the fixtures with their (0 based) start index
+    output: HashMap<UniverseId, Vec<(usize, OutputProcessor)>>,
+}
+
 pub struct UniverseRuntimeController {
     update_subscriber: Sender<UniverseUpdate>,
+    raw_subscriber: Sender<UniverseUpdate>,
     cmd_sender: tokio::sync::mpsc::UnboundedSender<RuntimeCommand>,
 }
 
@@ -87,6 +125,25 @@ impl UniverseRuntimeController {
         }
     }
 
+    /// Subscribes to the values before the output processing of the patched fixtures
+    pub fn subscribe_raw(&self) -> Receiver<UniverseUpdate> {
+        let rx = self.raw_subscriber.subscribe();
+
+        self.cmd_sender
+            .send(RuntimeCommand::ResendUniverses)
+            .debug_ignore();
+
+        rx
+    }
+
+    pub fn subscribe_raw_universe(&self, universe: UniverseId) -> UniverseUpdateSubscriber {
+        let sub = self.subscribe_raw();
+        UniverseUpdateSubscriber {
+            rx: sub,
+            universe_id: universe,
+        }
+    }
+
     pub fn cmd(&self, cmd: RuntimeCommand) {
         self.cmd_sender.send(cmd).debug_ignore();
     }
@@ -133,8 +190,29 @@ pub enum RuntimeCommand {
     ResendUniverses,
     ResendUniverse(UniverseId),
     UpdateData(UniverseUpdate),
+    /// Replaces the whole content of a layer, universes not contained are cleared
+    SetLayer {
+        layer: RuntimeLayer,
+        frames: HashMap<UniverseId, Box<LayerFrame>>,
+    },
+    ClearLayer(RuntimeLayer),
+    SetMasters(Masters),
+}
+
+/// Layers are composed on top of the directly set channel values in ascending order,
+/// a value of a higher layer takes priority over the ones below.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RuntimeLayer {
+    /// A recording being played back
+    Playback,
+    /// The current cues of the running cue lists
+    Cues,
+    Programmer,
 }
 
+/// Channel values of a layer, `None` falls through to the layers below.
+pub type LayerFrame = [Option<u8>; UNIVERSE_SIZE];
+
 pub type UpdateChunk = (FixtureAddress, u8);
 
 #[derive(Debug, Clone)]
@@ -161,11 +239,19 @@ impl UniverseRuntime {
         UniverseRuntimeController,
     ) {
         let (update_tx, _update_rx) = tokio::sync::broadcast::channel(32);
+        let (raw_tx, _raw_rx) = tokio::sync::broadcast::channel(32);
         let (cmd_tx, cmd_rx) = tokio::sync::mpsc::unbounded_channel();
 
         let runtime = Self {
-            runtime_universes: vec![],
+            runtime_universes: BTreeMap::new(),
+            layers: BTreeMap::new(),
+            raw_universes: BTreeMap::new(),
+            output_universes: BTreeMap::new(),
+            masters: Masters::default(),
+            patch: PatchInfo::default(),
+            parked: HashMap::new(),
             update_notifier: update_tx.clone(),
+            raw_notifier: raw_tx.clone(),
             cmd_recv: cmd_rx,
             project,
         };
@@ -176,6 +262,7 @@ impl UniverseRuntime {
             j,
             UniverseRuntimeController {
                 update_subscriber: update_tx,
+                raw_subscriber: raw_tx,
                 cmd_sender: cmd_tx,
             },
         )
@@ -189,8 +276,23 @@ impl UniverseRuntime {
                     info!("Shutting down Universe Runtime!");
                     break;
                 }
-                _ = adapt_notifier.wait(AdaptScopes::UNIVERSES) => {
-                    self.adapt().await;
+                scopes = adapt_notifier.wait(
+                    AdaptScopes::UNIVERSES
+                        | AdaptScopes::PATCH
+                        | AdaptScopes::PARKED
+                        | AdaptScopes::PROJECT
+                ) => {
+                    if scopes.contains(AdaptScopes::UNIVERSES) {
+                        self.adapt(scopes.contains(AdaptScopes::PROJECT)).await;
+                    } else {
+                        if scopes.contains(AdaptScopes::PATCH) {
+                            self.adapt_patch().await;
+                        }
+                        if scopes.contains(AdaptScopes::PARKED) {
+                            self.adapt_parked().await;
+                        }
+                        self.render_all();
+                    }
                 }
                 Some(cmd) = self.cmd_recv.recv() => {
                     self.handle_cmd(cmd).await;
@@ -205,50 +307,166 @@ impl UniverseRuntime {
         trace!("Starting RuntimeCommand Handling");
         match cmd {
             RuntimeCommand::ResendUniverses => {
-                for u in 1..=self.runtime_universes.len() {
-                    self.send_universe(u as u16).await
+                for u in self.universe_ids() {
+                    self.send_universe(u).await
                 }
             }
             RuntimeCommand::ResendUniverse(i) => self.send_universe(i).await,
             RuntimeCommand::UpdateData(update) => {
-                match &update {
+                let universes = match &update {
                     UniverseUpdate::Single { update } => {
-                        if let Some(data) = self
-                            .runtime_universes
-                            .get_mut(update.0.universe() as usize - 1)
-                        {
-                            data[update.0.address().take() - 1] = update.1;
-                        }
+                        self.set_value(update);
+                        vec![update.0.universe()]
                     }
                     UniverseUpdate::Many { updates } => {
                         for update in updates {
-                            if let Some(data) = self
-                                .runtime_universes
-                                .get_mut(update.0.universe() as usize - 1)
-                            {
-                                data[update.0.address().take() - 1] = update.1;
-                            }
+                            self.set_value(update);
                         }
+                        let mut universes =
+                            updates.iter().map(|u| u.0.universe()).collect::<Vec<_>>();
+                        universes.sort();
+                        universes.dedup();
+                        universes
                     }
                     UniverseUpdate::Entire { universe, values } => {
-                        if let Some(data) = self.runtime_universes.get_mut(*universe as usize - 1) {
+                        if let Some(data) = self.runtime_universes.get_mut(universe) {
                             *data = **values;
                         }
+                        vec![*universe]
                     }
-                }
+                };
 
-                self.update_notifier.send(update).debug_ignore();
+                for universe in universes {
+                    self.render(universe);
+                }
+            }
+            RuntimeCommand::SetLayer { layer, frames } => {
+                self.layers.insert(layer, frames);
+                self.render_all();
+            }
+            RuntimeCommand::ClearLayer(layer) => {
+                if self.layers.remove(&layer).is_some() {
+                    self.render_all();
+                }
+            }
+            RuntimeCommand::SetMasters(masters) => {
+                self.masters = masters;
+                self.render_all();
             }
         }
         trace!("Finished RuntimeCommand Handling");
     }
 
+    fn set_value(&mut self, update: &UpdateChunk) {
+        if let Some(data) = self.runtime_universes.get_mut(&update.0.universe()) {
+            data[update.0.address().take() - 1] = update.1;
+        }
+    }
+
+    /// Composes the raw and the processed output values of a universe
+    fn compose(&self, universe: UniverseId) -> Option<([u8; UNIVERSE_SIZE], [u8; UNIVERSE_SIZE])> {
+        let mut frame = *self.runtime_universes.get(&universe)?;
+        for layer in self.layers.values() {
+            if let Some(values) = layer.get(&universe) {
+                for (out, value) in frame.iter_mut().zip(values.iter()) {
+                    if let Some(v) = value {
+                        *out = *v;
+                    }
+                }
+            }
+        }
+        self.apply_masters(universe, &mut frame);
+        self.apply_parked(universe, &mut frame);
+
+        let mut output = frame;
+        for (start, processor) in self.patch.output.get(&universe).into_iter().flatten() {
+            processor.apply(&mut output[*start..]);
+        }
+        self.apply_parked(universe, &mut output);
+
+        Some((frame, output))
+    }
+
+    fn apply_parked(&self, universe: UniverseId, frame: &mut [u8; UNIVERSE_SIZE]) {
+        for (i, value) in self.parked.get(&universe).into_iter().flatten() {
+            frame[*i] = *value;
+        }
+    }
+
+    /// Scales the intensity channels by grand master, universe and group submasters.
+    /// Blackout pulls them all to zero.
+    fn apply_masters(&self, universe: UniverseId, frame: &mut [u8; UNIVERSE_SIZE]) {
+        let Some(channels) = self.patch.intensity.get(&universe) else {
+            return;
+        };
+
+        let base = if self.masters.blackout {
+            0.0
+        } else {
+            self.masters.grand_master.take() * self.masters.universe_level(universe)
+        };
+
+        for (fixture, offsets) in channels {
+            let level = self
+                .patch
+                .groups
+                .get(fixture)
+                .into_iter()
+                .flatten()
+                .fold(base, |l, g| l * self.masters.group_level(g));
+
+            if level < 1.0 {
+                scale_channel(frame, offsets, level);
+            }
+        }
+    }
+
+    /// Recomposes the output of a universe and notifies the subscribers about changed channels
+    fn render(&mut self, universe: UniverseId) {
+        let Some((raw, output)) = self.compose(universe) else {
+            return;
+        };
+        if let Some(update) = self
+            .raw_universes
+            .get_mut(&universe)
+            .and_then(|r| diff(universe, r, raw))
+        {
+            self.raw_notifier.send(update).debug_ignore();
+        }
+        if let Some(update) = self
+            .output_universes
+            .get_mut(&universe)
+            .and_then(|o| diff(universe, o, output))
+        {
+            self.update_notifier.send(update).debug_ignore();
+        }
+    }
+
+    fn render_all(&mut self) {
+        for u in self.universe_ids() {
+            self.render(u);
+        }
+    }
+
+    fn universe_ids(&self) -> Vec<UniverseId> {
+        self.runtime_universes.keys().copied().collect()
+    }
+
     async fn send_universe(&mut self, universe: UniverseId) {
-        if let Some(data) = self.runtime_universes.get(universe as usize - 1) {
+        if let (Some(raw), Some(output)) = (
+            self.raw_universes.get(&universe),
+            self.output_universes.get(&universe),
+        ) {
+            self.raw_notifier
+                .send(UniverseUpdate::Entire {
+                    universe,
+                    values: Box::new(*raw),
+                })
+                .debug_ignore();
             self.update_notifier
                 .send(UniverseUpdate::Entire {
                     universe,
-                    values: Box::new(*data),
+                    values: Box::new(*output),
                 })
                 .debug_ignore();
         } else {
@@ -256,20 +474,329 @@ impl UniverseRuntime {
         };
     }
 
+    /// Follows the universes of the project, values of remaining universes are kept unless `reset`
     #[tracing::instrument]
-    async fn adapt(&mut self) {
+    async fn adapt(&mut self, reset: bool) {
         info!("Adapting");
+        if reset {
+            self.runtime_universes.clear();
+        }
+
         let p = self.project.read().await;
-        self.runtime_universes
-            .resize(p.universes.len(), [0; UNIVERSE_SIZE]);
-        for (i, u) in self.runtime_universes.iter_mut().enumerate() {
-            *u = [0; 512];
-            self.update_notifier
-                .send(UniverseUpdate::Entire {
-                    universe: (i + 1) as u16,
-                    values: Box::new(*u),
+        let ids = p.universes.iter().map(|u| u.id).collect::<Vec<_>>();
+        drop(p);
+
+        self.runtime_universes.retain(|u, _| ids.contains(u));
+        for id in &ids {
+            self.runtime_universes
+                .entry(*id)
+                .or_insert([0; UNIVERSE_SIZE]);
+        }
+        self.adapt_patch().await;
+        self.adapt_parked().await;
+
+        self.raw_universes.clear();
+        self.output_universes.clear();
+        for universe in ids {
+            let (raw, output) = self.compose(universe).expect("Must exist");
+            self.raw_universes.insert(universe, raw);
+            self.output_universes.insert(universe, output);
+            self.send_universe(universe).await;
+        }
+    }
+
+    async fn adapt_patch(&mut self) {
+        let p = self.project.read().await;
+        let mut patch = PatchInfo::default();
+
+        for universe in &p.universes {
+            let channels = universe
+                .fixtures
+                .values()
+                .filter_map(|f| Some((f, universe.fixture_address(&f.id)?.take() - 1)))
+                .flat_map(|(f, start)| {
+                    f.intensity_channels().into_iter().filter_map(move |offsets| {
+                        let offsets = offsets.into_iter().map(|o| start + o).collect::<Vec<_>>();
+                        offsets
+                            .iter()
+                            .all(|i| *i < UNIVERSE_SIZE)
+                            .then_some((f.id, offsets))
+                    })
                 })
-                .debug_ignore();
+                .collect();
+            patch.intensity.insert(universe.id, channels);
+
+            let output = universe
+                .fixtures
+                .values()
+                .filter_map(|f| {
+                    let start = universe.fixture_address(&f.id)?.take() - 1;
+                    Some((start, f.output_processor()?))
+                })
+                .collect();
+            patch.output.insert(universe.id, output);
+        }
+
+        for group in &p.groups {
+            for fixture in &group.fixtures {
+                patch.groups.entry(*fixture).or_default().push(group.id);
+            }
         }
+
+        self.patch = patch;
+    }
+
+    async fn adapt_parked(&mut self) {
+        let p = self.project.read().await;
+        self.parked.clear();
+        for (address, value) in &p.parked {
+            self.parked
+                .entry(address.universe())
+                .or_default()
+                .push((address.address().take() - 1, *value));
+        }
+    }
+}
+
+/// Updates `old` to `new` and creates an update for every changed channel
+fn diff(
+    universe: UniverseId,
+    old: &mut [u8; UNIVERSE_SIZE],
+    new: [u8; UNIVERSE_SIZE],
+) -> Option<UniverseUpdate> {
+    let mut updates = old
+        .iter()
+        .zip(new.iter())
+        .enumerate()
+        .filter(|(_, (old, new))| old != new)
+        .map(|(i, (_, new))| {
+            (
+                FixtureAddress::new(universe, UniverseAddress::create(i + 1)),
+                *new,
+            )
+        })
+        .collect::<Vec<_>>();
+    *old = new;
+
+    match updates.len() {
+        0 => None,
+        1 => Some(UniverseUpdate::Single {
+            update: updates.remove(0),
+        }),
+        _ => Some(UniverseUpdate::Many { updates }),
+    }
+}
+
+/// Scales a (possibly multi byte, coarse first) channel value by the given level
+fn scale_channel(frame: &mut [u8; UNIVERSE_SIZE], offsets: &[usize], level: f32) {
+    let value = offsets
+        .iter()
+        .fold(0_u64, |v, o| (v << 8) | frame[*o] as u64);
+    let mut scaled = (value as f32 * level.clamp(0.0, 1.0)).round() as u64;
+
+    for o in offsets.iter().rev() {
+        frame[*o] = (scaled & 0xFF) as u8;
+        scaled >>= 8;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RuntimeCommand, RuntimeLayer, UniverseRuntime, UniverseUpdate, scale_channel};
+    use crate::project::{Project, create_default_project};
+    use crate::test_util::{dimmer, fixture, patch, service_obj};
+    use mlc_communication::services::general::Masters;
+    use mlc_communication::services::project::ProjectService;
+    use mlc_data::SavePercentage;
+    use mlc_data::fixture::blueprint::CapabilityKind;
+    use mlc_data::fixture::patched::output::ChannelProcessing;
+    use mlc_data::project::group::FixtureGroup;
+    use mlc_data::project::universe::{
+        FixtureAddress, FixtureUniverse, UNIVERSE_SIZE, UniverseAddress, UniverseId,
+    };
+    use mlc_data::uuid::Uuid;
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    /// A runtime following the project without the service around it, every channel is at 200
+    async fn runtime(project: Project) -> UniverseRuntime {
+        let mut runtime = UniverseRuntime {
+            runtime_universes: BTreeMap::new(),
+            layers: BTreeMap::new(),
+            raw_universes: BTreeMap::new(),
+            output_universes: BTreeMap::new(),
+            masters: Masters::default(),
+            patch: Default::default(),
+            parked: HashMap::new(),
+            update_notifier: tokio::sync::broadcast::channel(32).0,
+            raw_notifier: tokio::sync::broadcast::channel(32).0,
+            cmd_recv: tokio::sync::mpsc::unbounded_channel().1,
+            project: Arc::new(RwLock::new(project)),
+        };
+        runtime.adapt(true).await;
+        for universe in runtime.universe_ids() {
+            let values = Box::new([200; UNIVERSE_SIZE]);
+            let update = UniverseUpdate::Entire { universe, values };
+            runtime.handle_cmd(RuntimeCommand::UpdateData(update)).await;
+        }
+        runtime
+    }
+
+    /// The first output channels of the universe
+    fn output(runtime: &UniverseRuntime, universe: UniverseId) -> [u8; 4] {
+        let (_, output) = runtime.compose(universe).unwrap();
+        output[..4].try_into().unwrap()
+    }
+
+    #[tokio::test]
+    async fn apply_masters() {
+        let mut project = create_default_project();
+        project
+            .universes
+            .push(FixtureUniverse::new(2, "Universe 2".to_string()));
+        // Only the pure dimmer channel is scaled, the mixed one also controls something else
+        let channels = vec![
+            ("Dimmer", vec![dimmer()]),
+            ("Mixed", vec![dimmer(), CapabilityKind::Generic]),
+            ("Other", vec![CapabilityKind::Generic]),
+        ];
+        patch(&mut project, 1, 1, fixture(channels));
+        let dimmers = || fixture(vec![("Dimmer", vec![dimmer()])]);
+        let grouped = patch(&mut project, 1, 4, dimmers());
+        patch(&mut project, 2, 1, dimmers());
+        let group = FixtureGroup {
+            id: Uuid::new_v4(),
+            name: "Group".to_string(),
+            fixtures: vec![grouped],
+        };
+        project.groups.push(group.clone());
+
+        let mut runtime = runtime(project.clone()).await;
+        assert_eq!(output(&runtime, 1), [200, 200, 200, 200]);
+
+        let level = SavePercentage::create;
+        let cases: [(Masters, [u8; 4], u8); 5] = [
+            (
+                Masters {
+                    grand_master: level(0.5),
+                    ..Masters::default()
+                },
+                [100, 200, 200, 100],
+                100,
+            ),
+            (
+                Masters {
+                    universes: vec![(1, level(0.5))],
+                    ..Masters::default()
+                },
+                [100, 200, 200, 100],
+                200,
+            ),
+            (
+                Masters {
+                    groups: vec![(group.id, level(0.5))],
+                    ..Masters::default()
+                },
+                [200, 200, 200, 100],
+                200,
+            ),
+            // The levels multiply
+            (
+                Masters {
+                    grand_master: level(0.5),
+                    universes: vec![(1, level(0.5))],
+                    groups: vec![(group.id, level(0.5))],
+                    blackout: false,
+                },
+                [50, 200, 200, 25],
+                100,
+            ),
+            (
+                Masters {
+                    blackout: true,
+                    ..Masters::default()
+                },
+                [0, 200, 200, 0],
+                0,
+            ),
+        ];
+        for (masters, first, second) in cases {
+            runtime
+                .handle_cmd(RuntimeCommand::SetMasters(masters.clone()))
+                .await;
+            assert_eq!(output(&runtime, 1), first, "{masters:?}");
+            assert_eq!(output(&runtime, 2)[0], second, "{masters:?}");
+        }
+
+        // Deleting a group drops its submaster
+        let (service_obj, _) = service_obj(project);
+        service_obj.set_group_level(group.id, 0.5);
+        service_obj.set_universe_level(1, 0.5);
+        service_obj.group_delete(group.id).await.unwrap();
+        let masters = service_obj.masters.borrow().clone();
+        assert!(masters.groups.is_empty());
+        assert_eq!(masters.universes, [(1, level(0.5))]);
+    }
+
+    #[tokio::test]
+    async fn park_channels() {
+        let mut project = create_default_project();
+        let mut fixture = fixture(vec![
+            ("Dimmer", vec![dimmer()]),
+            ("Other", vec![CapabilityKind::Generic]),
+        ]);
+        fixture.output.channels = ["Dimmer", "Other"]
+            .map(|c| ChannelProcessing {
+                invert: true,
+                ..ChannelProcessing::new(c.to_string())
+            })
+            .to_vec();
+        patch(&mut project, 1, 1, fixture);
+        project
+            .parked
+            .push((FixtureAddress::new(1, UniverseAddress::create(1)), 42));
+
+        let mut runtime = runtime(project).await;
+        let frame = |values: &[u8]| {
+            let mut frame = Box::new([None; UNIVERSE_SIZE]);
+            for (out, value) in frame.iter_mut().zip(values) {
+                *out = Some(*value);
+            }
+            HashMap::from([(1, frame)])
+        };
+        for (layer, frames) in [
+            (RuntimeLayer::Cues, frame(&[10, 10])),
+            (RuntimeLayer::Programmer, frame(&[20])),
+        ] {
+            let cmd = RuntimeCommand::SetLayer { layer, frames };
+            runtime.handle_cmd(cmd).await;
+        }
+        let blackout = Masters {
+            blackout: true,
+            ..Masters::default()
+        };
+        runtime
+            .handle_cmd(RuntimeCommand::SetMasters(blackout))
+            .await;
+
+        // The parked channel ignores the layers, the masters and the inversion
+        let (raw, processed) = runtime.compose(1).unwrap();
+        assert_eq!(raw[..2], [42, 10]);
+        assert_eq!(processed[..2], [42, 245]);
+
+        runtime.project.write().await.parked.clear();
+        runtime.adapt_parked().await;
+        assert_eq!(output(&runtime, 1)[..2], [255, 245]);
+    }
+
+    #[test]
+    fn scale_fine_channels() {
+        let mut frame = [0; UNIVERSE_SIZE];
+        frame[..2].copy_from_slice(&[0x80, 0x01]);
+        scale_channel(&mut frame, &[0, 1], 0.5);
+        assert_eq!(frame[..2], [0x40, 0x01]);
+        scale_channel(&mut frame, &[0, 1], 2.0);
+        assert_eq!(frame[..2], [0x40, 0x01]);
     }
 }